@@ -0,0 +1,49 @@
+// Costo de los cambios de contexto de mypthreads frente a hilos del sistema.
+//
+//     cargo bench --bench context_switch
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use mypthreads::bench::{measure_mutex_roundtrip, measure_realtime_dispatch, measure_switch_latency};
+
+const ITERATIONS: u32 = 100_000;
+
+/// Ping-pong entre dos hilos nativos con un `Mutex` + `Condvar`: cada
+/// iteración son dos cambios de contexto, igual que en `measure_switch_latency`.
+fn native_switch_latency(iterations: u32) -> Duration {
+    let turn = Arc::new((Mutex::new(false), Condvar::new()));
+    let start = Instant::now();
+    let players: Vec<_> = [false, true]
+        .into_iter()
+        .map(|me| {
+            let turn = Arc::clone(&turn);
+            thread::spawn(move || {
+                let (lock, cvar) = &*turn;
+                for _ in 0..iterations {
+                    let mut current = cvar.wait_while(lock.lock().unwrap(), |t| *t != me).unwrap();
+                    *current = !me;
+                    cvar.notify_one();
+                }
+            })
+        })
+        .collect();
+    for player in players {
+        player.join().unwrap();
+    }
+    start.elapsed() / iterations
+}
+
+fn main() {
+    println!("{ITERATIONS} iteraciones");
+    println!("mypthreads  yield ida y vuelta: {:?}", measure_switch_latency(ITERATIONS));
+    println!("mypthreads  traspaso de mutex:  {:?}", measure_mutex_roundtrip(ITERATIONS));
+    println!("std::thread ida y vuelta:       {:?}", native_switch_latency(ITERATIONS));
+    for threads in [10, 100, 1000] {
+        println!(
+            "mypthreads  dispatch RT con {threads:>4} listos: {:?}",
+            measure_realtime_dispatch(threads, 100),
+        );
+    }
+}
@@ -0,0 +1,100 @@
+// src/bench.rs
+
+//! Micro-benchmarks del scheduler para comparar contra hilos nativos.
+//!
+//! Cada función crea sus propios hilos y les hace join antes de volver, así
+//! que se pueden llamar muchas veces sin acumular TCBs.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::{
+    my_mutex_lock, my_mutex_unlock, my_thread_join, my_thread_spawn, my_thread_yield, MyMutex,
+    SchedPolicy,
+};
+
+/// Costo promedio de una ida y vuelta de `swapcontext`: dos hilos RR se
+/// ceden la CPU `iterations` veces cada uno, así que cada iteración son dos
+/// cambios de contexto.
+pub fn measure_switch_latency(iterations: u32) -> Duration {
+    if iterations == 0 {
+        return Duration::ZERO;
+    }
+
+    let start = Instant::now();
+    let tids = [(); 2].map(|_| {
+        my_thread_spawn(
+            move || {
+                for _ in 0..iterations {
+                    my_thread_yield();
+                }
+            },
+            SchedPolicy::RoundRobin,
+        )
+    });
+    for tid in tids {
+        my_thread_join(tid);
+    }
+    start.elapsed() / iterations
+}
+
+/// Costo promedio de pasarse un `MyMutex` entre dos hilos: cada hilo lo toma
+/// `iterations` veces y cede la CPU con el lock tomado, así que el otro
+/// queda en la cola de waiters y cada `my_mutex_unlock` es un traspaso
+/// directo. Devuelve el promedio por traspaso.
+pub fn measure_mutex_roundtrip(iterations: u32) -> Duration {
+    if iterations == 0 {
+        return Duration::ZERO;
+    }
+
+    let mutex = Rc::new(MyMutex::new());
+    let handoffs = Rc::new(Cell::new(0u32));
+    let start = Instant::now();
+    let tids = [(); 2].map(|_| {
+        let (mutex, handoffs) = (Rc::clone(&mutex), Rc::clone(&handoffs));
+        my_thread_spawn(
+            move || {
+                for _ in 0..iterations {
+                    my_mutex_lock(&mutex);
+                    handoffs.set(handoffs.get() + 1);
+                    my_thread_yield();
+                    my_mutex_unlock(&mutex);
+                }
+            },
+            SchedPolicy::RoundRobin,
+        )
+    });
+    for tid in tids {
+        my_thread_join(tid);
+    }
+    start.elapsed() / handoffs.get()
+}
+
+/// Costo promedio de un dispatch con `threads` hilos de Tiempo Real listos a
+/// la vez: cada uno cede la CPU `rounds` veces, así que cada yield vuelve a
+/// encolarlo entre todos los demás. Sirve para ver cómo crece el costo de
+/// elegir al de menor deadline con el tamaño de la cola.
+pub fn measure_realtime_dispatch(threads: u32, rounds: u32) -> Duration {
+    if threads == 0 || rounds == 0 {
+        return Duration::ZERO;
+    }
+
+    let start = Instant::now();
+    let tids: Vec<_> = (0..threads)
+        .map(|i| {
+            my_thread_spawn(
+                move || {
+                    for _ in 0..rounds {
+                        my_thread_yield();
+                    }
+                },
+                SchedPolicy::RealTime { deadline: u64::from(i % 16) + 1 },
+            )
+        })
+        .collect();
+    for tid in tids {
+        my_thread_join(tid);
+    }
+    start.elapsed() / (threads * rounds)
+}
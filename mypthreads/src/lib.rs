@@ -1,66 +1,651 @@
 // src/lib.rs
 
-use std::collections::{HashMap, VecDeque};
+use std::cell::{Cell, RefCell};
+use std::any::{Any, TypeId};
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::fmt;
 use std::mem;
 use std::os::raw::{c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
 use std::ptr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use libc::{ucontext_t, getcontext, makecontext, swapcontext, EBUSY, EINVAL};
+use libc::{ucontext_t, getcontext, makecontext, swapcontext, EAGAIN, EBUSY, EDEADLK, EINVAL, ENOTRECOVERABLE, ESRCH, ETIMEDOUT};
+
+pub mod bench;
+mod pool;
+
+pub use pool::ThreadPool;
 
 pub type MyThreadId = usize;
+/// Grupo de scheduling (ver `my_sched_group_create`).
+pub type MySchedGroupId = usize;
 pub type ThreadFunc = extern "C" fn(*mut c_void) -> *mut c_void;
 
 /// Estados posibles de un hilo de usuario.
+#[allow(dead_code)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 enum ThreadState {
     New,
     Ready,
     Running,
     Blocked,
+    /// Congelado con `my_thread_suspend` hasta un `my_thread_resume`.
+    Suspended,
+    Finished,
+}
+
+/// Estado de un hilo tal como lo reportan `my_thread_list` y
+/// `my_thread_state`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MyThreadState {
+    /// Reservado: hoy los hilos quedan `Ready` apenas se crean.
+    New,
+    Ready,
+    Running,
+    Blocked,
+    Suspended,
     Finished,
+    /// El id nunca existió, ya se liberó su TCB o es el hilo idle.
+    Unknown,
+}
+
+impl From<ThreadState> for MyThreadState {
+    fn from(state: ThreadState) -> Self {
+        match state {
+            ThreadState::New => MyThreadState::New,
+            ThreadState::Ready => MyThreadState::Ready,
+            ThreadState::Running => MyThreadState::Running,
+            ThreadState::Blocked => MyThreadState::Blocked,
+            ThreadState::Suspended => MyThreadState::Suspended,
+            ThreadState::Finished => MyThreadState::Finished,
+        }
+    }
+}
+
+/// Por qué está bloqueado un hilo, tal como lo reporta `my_thread_list`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MyBlockReason {
+    Join { target: MyThreadId },
+    JoinAny,
+    Mutex { mutex: MyMutexId, owner: MyThreadId },
+    Once { initializer: MyThreadId },
+    Sleep { until: u64 },
+    Cond { until: Option<u64> },
+    Channel { sending: bool },
+    RwLock { write: bool },
+    Semaphore,
+    Barrier,
+    WaitGroup,
+    Other,
+}
+
+/// Foto de un hilo tomada por `my_thread_list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MyThreadInfo {
+    pub tid: MyThreadId,
+    pub name: Option<String>,
+    pub state: MyThreadState,
+    /// Política con los tickets base o el deadline actual, como en
+    /// `my_thread_getsched`.
+    pub policy: SchedPolicy,
+    pub detached: bool,
+    /// Solo para hilos bloqueados.
+    pub block_reason: Option<MyBlockReason>,
+    /// Hilo que hace join sobre este, si hay.
+    pub joined_by: Option<MyThreadId>,
+}
+
+/// Un mutex tomado, tal como lo muestra `my_sched_lock_report`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MyMutexInfo {
+    pub id: MyMutexId,
+    pub owner: MyThreadId,
+    /// Hilos bloqueados esperándolo, en el orden en que lo van a recibir.
+    pub waiters: Vec<MyThreadId>,
+}
+
+/// Quién tiene y quién espera cada mutex (ver `my_sched_lock_report`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MyLockReport {
+    /// Mutexes tomados, ordenados por id.
+    pub mutexes: Vec<MyMutexInfo>,
+    /// Mutexes de cada hilo que tiene alguno, en el orden en que los tomó.
+    pub held: Vec<(MyThreadId, Vec<MyMutexId>)>,
+}
+
+impl fmt::Display for MyLockReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids = |ids: &[usize], prefix: &str| {
+            ids.iter().map(|id| format!("{prefix}{id}")).collect::<Vec<_>>().join(", ")
+        };
+        for m in &self.mutexes {
+            write!(f, "mutex #{}: dueño hilo {}", m.id, m.owner)?;
+            if !m.waiters.is_empty() {
+                write!(f, ", esperan {}", ids(&m.waiters, "hilo "))?;
+            }
+            writeln!(f)?;
+        }
+        for (tid, held) in &self.held {
+            writeln!(f, "hilo {tid}: tiene {}", ids(held, "#"))?;
+        }
+        Ok(())
+    }
 }
 
 /// Políticas de scheduling compatibles.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SchedPolicy {
     RoundRobin,
     Lottery { tickets: u32 },
     RealTime { deadline: u64 }, // interpretado como prioridad (menor = más urgente)
+    Mlfq,                       // multi-level feedback queue (ver `MLFQ_LEVELS`)
+    Fifo { priority: u8 },      // prioridad estricta, no expropiativa (mayor = más urgente)
+    /// Clase registrada con `my_sched_register_class`; `param` queda a
+    /// criterio de la clase (ver `Thread::policy`).
+    Custom { class_id: usize, param: u64 },
+}
+
+/// Cantidad de niveles de la MLFQ (0 = más prioritario).
+pub const MLFQ_LEVELS: usize = 3;
+
+/// Quantum de cada nivel de la MLFQ, medido en dispatches consecutivos sin
+/// bloquearse. Al agotarlo el hilo baja un nivel.
+pub const MLFQ_QUANTA: [u32; MLFQ_LEVELS] = [4, 8, 16];
+
+/// Cada cuántos ticks se suben todos los hilos MLFQ al nivel 0 (evita inanición).
+pub const MLFQ_BOOST_PERIOD: u64 = 64;
+
+/// Semilla inicial del sorteo Lottery (ver `my_sched_set_seed`).
+pub const DEFAULT_SEED: u64 = 0xdead_beef_cafe_babe;
+
+/// Límite por defecto de hilos vivos (ver `my_sched_set_max_threads`).
+pub const DEFAULT_MAX_THREADS: usize = 1024;
+
+/// Tamaño de pila por defecto de los hilos nuevos.
+pub const DEFAULT_STACK_SIZE: usize = 64 * 1024;
+
+/// Tamaño de pila mínimo aceptado por `my_sched_set_stack_size`.
+pub const MIN_STACK_SIZE: usize = 16 * 1024;
+
+/// Bytes al fondo de cada pila que se llenan con `STACK_CANARY`. Si cambian,
+/// el hilo se salió de su pila.
+const STACK_CANARY_LEN: usize = 1024;
+const STACK_CANARY: [u8; 8] = 0xDEAD_C0DE_5AFE_57AC_u64.to_le_bytes();
+
+/// Máximo de veces seguidas que un hilo puede despertar al frente de la
+/// cola RR con `UnblockPolicy::FrontOfQueue`; la siguiente va al fondo.
+pub const MAX_FRONT_WAKEUPS: u32 = 4;
+
+/// Dónde se encola un hilo Round Robin al despertar (ver
+/// `my_sched_set_unblock_policy`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum UnblockPolicy {
+    /// Al fondo de la cola, como cualquier hilo que cede la CPU.
+    #[default]
+    BackOfQueue,
+    /// Al frente: favorece a los hilos que se bloquean en vez de agotar su
+    /// quantum.
+    FrontOfQueue,
+}
+
+/// Tipo de política, sin sus parámetros (útil para estadísticas y configuración).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SchedPolicyKind {
+    RoundRobin,
+    Lottery,
+    RealTime,
+    Mlfq,
+    Fifo,
+    Custom { class_id: usize },
+}
+
+impl SchedPolicy {
+    /// Devuelve el tipo de la política, descartando sus parámetros.
+    pub fn kind(&self) -> SchedPolicyKind {
+        match self {
+            SchedPolicy::RoundRobin => SchedPolicyKind::RoundRobin,
+            SchedPolicy::Lottery { .. } => SchedPolicyKind::Lottery,
+            SchedPolicy::RealTime { .. } => SchedPolicyKind::RealTime,
+            SchedPolicy::Mlfq => SchedPolicyKind::Mlfq,
+            SchedPolicy::Fifo { .. } => SchedPolicyKind::Fifo,
+            SchedPolicy::Custom { class_id, .. } => SchedPolicyKind::Custom { class_id: *class_id },
+        }
+    }
+}
+
+/// Estadísticas globales del scheduler.
+#[derive(Debug, Clone, Default)]
+pub struct SchedStats {
+    /// Ticks virtuales transcurridos (un tick por cada dispatch).
+    pub ticks: u64,
+    /// Veces que se eligió un hilo para correr (incluye re-elegir al mismo).
+    pub dispatches: u64,
+    /// Cambios de contexto efectivos (`swapcontext` entre hilos distintos).
+    pub context_switches: u64,
+    /// Dispatches realizados por cada política.
+    pub dispatches_by_policy: HashMap<SchedPolicyKind, u64>,
+    /// Dispatches realizados por cada grupo de scheduling, incluido
+    /// `MY_SCHED_GROUP_DEFAULT` (ver `my_sched_group_create`).
+    pub dispatches_by_group: HashMap<MySchedGroupId, u64>,
+    /// TCBs vivos, incluyendo main y los hilos terminados sin join.
+    pub live_threads: usize,
+}
+
+/// Estadísticas de un hilo de usuario.
+#[derive(Debug, Clone)]
+pub struct ThreadStats {
+    /// Veces que el hilo fue elegido por el scheduler.
+    pub run_count: u64,
+    /// Ticks que el hilo pasó en estado Ready.
+    pub ready_ticks: u64,
+    /// Ticks que el hilo pasó en estado Blocked.
+    pub blocked_ticks: u64,
+    /// Política actual del hilo.
+    pub policy: SchedPolicy,
+    /// Nivel actual en la MLFQ, si el hilo usa `SchedPolicy::Mlfq`.
+    pub mlfq_level: Option<usize>,
+    /// Slots de quantum consumidos: cada dispatch más cada yield en el que
+    /// siguió corriendo por no haber agotado su quantum. Cada slot es un tick
+    /// del reloj virtual, así que también es su tiempo de CPU en ticks.
+    pub slices: u64,
+    /// Veces que `swapcontext` le devolvió la CPU a este hilo. No cuenta los
+    /// slices en que siguió corriendo sin cambio de contexto.
+    pub switch_count: u64,
+    /// Estado actual del hilo.
+    pub state: MyThreadState,
+    /// Veces que despertó tras estar bloqueado.
+    pub wakeups: u64,
+    /// Promedio de ticks entre despertar (pasar de Blocked a Ready) y volver
+    /// a correr; 0 si nunca despertó.
+    pub avg_wakeup_latency: f64,
+    /// Tick virtual en que se creó el hilo (ver `my_sched_ticks`).
+    pub created_at: u64,
+    /// Tick de su primer dispatch, o `None` si todavía no corrió.
+    pub first_run_at: Option<u64>,
+    /// Tick en que terminó, o `None` si sigue vivo.
+    pub finished_at: Option<u64>,
+}
+
+/// Métricas de Tiempo Real de un hilo (ver `my_thread_rt_stats`).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct RtStats {
+    /// Veces que el hilo pasó a Ready siendo de Tiempo Real.
+    pub activations: u64,
+    /// Deadlines incumplidos.
+    pub deadline_misses: u64,
+    /// Mayor atraso observado al detectar un incumplimiento, en ticks.
+    pub worst_lateness: u64,
+    /// Promedio de ticks entre pasar a Ready y volver a correr.
+    pub avg_response_ticks: f64,
+}
+
+/// Errores al crear un hilo o al consultarlo.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ThreadError {
+    /// Ya hay tantos hilos vivos como permite `my_sched_set_max_threads`.
+    ResourceLimit { max_threads: usize },
+    /// Parámetros de política sin sentido: `Lottery` con 0 tickets,
+    /// `RealTime` con deadline 0 o `Custom` con una clase no registrada.
+    InvalidPolicy(SchedPolicy),
+    /// No se pudo reservar la pila del hilo.
+    StackAllocation { size: usize },
+    /// `getcontext` falló con el `errno` indicado.
+    Context { errno: c_int },
+    /// No existe un hilo con ese id (o ya se liberó su TCB).
+    NoSuchThread { tid: MyThreadId },
+    /// No se puede hacer join de ese hilo: es el actual, el idle, está
+    /// detached o ya lo espera otro hilo.
+    NotJoinable { tid: MyThreadId },
+    /// El hilo no terminó dentro del plazo (ver `my_thread_join_timeout`).
+    JoinTimeout { tid: MyThreadId },
+}
+
+impl ThreadError {
+    /// Código `errno` equivalente, como lo devolvería `pthread_create`.
+    pub fn errno(&self) -> c_int {
+        match self {
+            ThreadError::ResourceLimit { .. } | ThreadError::StackAllocation { .. } => EAGAIN,
+            ThreadError::InvalidPolicy(_) | ThreadError::NotJoinable { .. } => EINVAL,
+            ThreadError::Context { errno } => *errno,
+            ThreadError::NoSuchThread { .. } => ESRCH,
+            ThreadError::JoinTimeout { .. } => ETIMEDOUT,
+        }
+    }
 }
 
-/// Razón de bloqueo (para depuración/extensión).
+impl fmt::Display for ThreadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ThreadError::ResourceLimit { max_threads } => {
+                write!(f, "se alcanzó el límite de {max_threads} hilos vivos")
+            }
+            ThreadError::InvalidPolicy(policy) => write!(f, "política inválida: {policy:?}"),
+            ThreadError::StackAllocation { size } => {
+                write!(f, "no se pudo reservar una pila de {size} bytes")
+            }
+            ThreadError::Context { errno } => write!(f, "getcontext falló (errno {errno})"),
+            ThreadError::NoSuchThread { tid } => write!(f, "no existe el hilo {tid}"),
+            ThreadError::NotJoinable { tid } => write!(f, "no se puede hacer join del hilo {tid}"),
+            ThreadError::JoinTimeout { tid } => write!(f, "el hilo {tid} no terminó a tiempo"),
+        }
+    }
+}
+
+impl std::error::Error for ThreadError {}
+
+/// Razón de bloqueo. Cada variante indica por quién espera el hilo, lo que
+/// permite armar el grafo de espera para detectar deadlocks.
+#[allow(dead_code)]
 #[derive(Debug, Copy, Clone)]
 enum BlockReason {
-    Join { target: MyThreadId },
-    Mutex,
+    /// Espera que termine `target`; con `until`, a lo sumo hasta ese tick
+    /// (ver `my_thread_join_timeout`).
+    Join { target: MyThreadId, until: Option<u64> },
+    /// Espera que termine cualquiera de varios hilos (`my_thread_join_any`).
+    JoinAny,
+    /// Espera el mutex en `mutex` (de id `id`), que hoy tiene `owner`; con
+    /// `until`, a lo sumo hasta ese tick (ver `my_mutex_timedlock`).
+    Mutex { mutex: *const MutexCore, id: MyMutexId, owner: MyThreadId, until: Option<u64> },
+    /// Espera a que `initializer` termine de ejecutar el `my_once` en `once`.
+    Once { once: *const MyOnce, initializer: MyThreadId },
+    /// Duerme hasta que el reloj virtual llegue a `until` (ver `my_thread_sleep`).
+    Sleep { until: u64 },
+    /// Espera una señal de `cond`; con `until`, a lo sumo hasta ese tick.
+    Cond { cond: *const MyCondVar, until: Option<u64> },
+    /// Espera lugar (`sending`) o un mensaje en el canal `chan`. `forget`
+    /// saca a un hilo de sus colas sin conocer el tipo de los mensajes.
+    Channel { chan: *const c_void, sending: bool, forget: fn(*const c_void, MyThreadId) },
+    /// Espera el rwlock `lock` para escribir (`write`) o para leer.
+    RwLock { lock: *const MyRwLock, write: bool },
+    /// Espera que el semáforo `sem` tenga un permiso.
+    Semaphore { sem: *const MySemaphore },
+    /// Espera que el resto del grupo llegue a la barrera `barrier`.
+    Barrier { barrier: *const MyBarrier },
+    /// Espera que el contador de `group` llegue a cero.
+    WaitGroup { group: *const MyWaitGroup },
     Other,
 }
 
+impl BlockReason {
+    /// Hilo por el que se espera, si se conoce.
+    fn waits_for(&self) -> Option<MyThreadId> {
+        match *self {
+            // Una espera con plazo se resuelve sola: no cierra ciclos.
+            BlockReason::Join { target, until: None } => Some(target),
+            BlockReason::Mutex { owner, until: None, .. } => Some(owner),
+            BlockReason::Once { initializer, .. } => Some(initializer),
+            BlockReason::Join { .. }
+            | BlockReason::JoinAny
+            | BlockReason::Mutex { .. }
+            | BlockReason::Sleep { .. }
+            | BlockReason::Cond { .. }
+            | BlockReason::Channel { .. }
+            | BlockReason::RwLock { .. }
+            | BlockReason::Semaphore { .. }
+            | BlockReason::Barrier { .. }
+            | BlockReason::WaitGroup { .. }
+            | BlockReason::Other => None,
+        }
+    }
+
+    fn public(&self) -> MyBlockReason {
+        match *self {
+            BlockReason::Join { target, .. } => MyBlockReason::Join { target },
+            BlockReason::JoinAny => MyBlockReason::JoinAny,
+            BlockReason::Mutex { id, owner, .. } => MyBlockReason::Mutex { mutex: id, owner },
+            BlockReason::Once { initializer, .. } => MyBlockReason::Once { initializer },
+            BlockReason::Sleep { until } => MyBlockReason::Sleep { until },
+            BlockReason::Cond { until, .. } => MyBlockReason::Cond { until },
+            BlockReason::Channel { sending, .. } => MyBlockReason::Channel { sending },
+            BlockReason::RwLock { write, .. } => MyBlockReason::RwLock { write },
+            BlockReason::Semaphore { .. } => MyBlockReason::Semaphore,
+            BlockReason::Barrier { .. } => MyBlockReason::Barrier,
+            BlockReason::WaitGroup { .. } => MyBlockReason::WaitGroup,
+            BlockReason::Other => MyBlockReason::Other,
+        }
+    }
+
+    /// Tick en que vence la espera, si tiene plazo.
+    fn until(&self) -> Option<u64> {
+        match *self {
+            BlockReason::Sleep { until } => Some(until),
+            BlockReason::Join { until, .. }
+            | BlockReason::Cond { until, .. }
+            | BlockReason::Mutex { until, .. } => until,
+            _ => None,
+        }
+    }
+
+    /// Describe la espera de `who` (normalmente `Scheduler::label`).
+    fn describe(&self, who: &str) -> String {
+        match *self {
+            BlockReason::Join { target, until: None } => {
+                format!("{who} espera el join del hilo {target}")
+            }
+            BlockReason::Join { target, until: Some(until) } => {
+                format!("{who} espera el join del hilo {target} hasta el tick {until}")
+            }
+            BlockReason::JoinAny => format!("{who} espera que termine alguno de varios hilos"),
+            BlockReason::Mutex { id, owner, until: None, .. } => {
+                format!("{who} espera el mutex #{id} del hilo {owner}")
+            }
+            BlockReason::Mutex { id, owner, until: Some(until), .. } => {
+                format!("{who} espera el mutex #{id} del hilo {owner} hasta el tick {until}")
+            }
+            BlockReason::Once { once, initializer } => {
+                format!("{who} espera el once {once:p} que inicializa el hilo {initializer}")
+            }
+            BlockReason::Sleep { until } => format!("{who} duerme hasta el tick {until}"),
+            BlockReason::Cond { cond, until: None } => {
+                format!("{who} espera la condición {cond:p}")
+            }
+            BlockReason::Cond { cond, until: Some(until) } => {
+                format!("{who} espera la condición {cond:p} hasta el tick {until}")
+            }
+            BlockReason::Channel { chan, sending: true, .. } => {
+                format!("{who} espera lugar en el canal {chan:p}")
+            }
+            BlockReason::Channel { chan, sending: false, .. } => {
+                format!("{who} espera un mensaje del canal {chan:p}")
+            }
+            BlockReason::RwLock { lock, write: true } => {
+                format!("{who} espera escribir en el rwlock {lock:p}")
+            }
+            BlockReason::RwLock { lock, write: false } => {
+                format!("{who} espera leer del rwlock {lock:p}")
+            }
+            BlockReason::Semaphore { sem } => format!("{who} espera el semáforo {sem:p}"),
+            BlockReason::Barrier { barrier } => format!("{who} espera en la barrera {barrier:p}"),
+            BlockReason::WaitGroup { group } => format!("{who} espera el grupo {group:p}"),
+            BlockReason::Other => format!("{who} está bloqueado"),
+        }
+    }
+}
+
 /// Parámetros de tiempo real (aquí lo mantenemos simple).
 #[derive(Debug, Copy, Clone)]
 struct RealTimeParams {
     deadline: u64,
+    /// Tick en que vence el deadline actual.
+    abs_deadline: u64,
+    /// Ya se reportó que el deadline actual se incumplió.
+    missed: bool,
 }
 
-/// Thread Control Block.
-struct Thread {
+impl RealTimeParams {
+    /// Deadline relativo al tick `now` (creación o cambio de política).
+    fn relative(deadline: u64, now: u64) -> Self {
+        RealTimeParams {
+            deadline,
+            abs_deadline: now.saturating_add(deadline),
+            missed: false,
+        }
+    }
+}
+
+/// Función invocada cuando un hilo de Tiempo Real incumple su deadline.
+/// Recibe el id del hilo y el atraso en ticks.
+pub type DeadlineMissHandler = fn(MyThreadId, u64);
+
+/// Handler de limpieza registrado con `my_cleanup_push`.
+pub type CleanupFn = fn(*mut c_void);
+
+/// Clave de almacenamiento local de hilo (ver `my_tls_key_create`).
+pub type MyTlsKey = usize;
+
+/// Nombre al estilo pthread de `MyTlsKey`.
+#[allow(non_camel_case_types)]
+pub type my_tls_key_t = MyTlsKey;
+
+/// Destructor de un valor local de hilo; recibe el valor guardado.
+pub type TlsDestructor = fn(*mut c_void);
+
+/// Finalizador de un hilo detached (ver `my_thread_set_finalizer`); recibe
+/// el resultado del hilo.
+pub type ThreadFinalizer = fn(*mut c_void);
+
+/// Estado de un hilo dentro de la MLFQ.
+#[derive(Debug, Copy, Clone, Default)]
+struct MlfqParams {
+    level: usize,
+    /// Dispatches consecutivos desde la última vez que se bloqueó.
+    used: u32,
+}
+
+/// Contadores internos de un hilo para `my_thread_stats`.
+#[derive(Debug, Copy, Clone, Default)]
+struct ThreadAccounting {
+    run_count: u64,
+    slices: u64,
+    switches: u64,
+    ready_ticks: u64,
+    blocked_ticks: u64,
+    /// Tick en que el hilo entró a su estado actual.
+    state_since: u64,
+    wakeups: u64,
+    wakeup_latency_ticks: u64,
+    /// Tick en que despertó, mientras espera su dispatch.
+    woken_at: Option<u64>,
+    /// Despertares seguidos al frente de la cola RR (ver `MAX_FRONT_WAKEUPS`).
+    front_wakeups: u32,
+    created_at: u64,
+    first_run_at: Option<u64>,
+    finished_at: Option<u64>,
+}
+
+/// Contadores de Tiempo Real de un hilo para `my_thread_rt_stats`. Se crean
+/// la primera vez que el hilo se encola como RealTime y se congelan (sin
+/// borrarse) si cambia de política.
+#[derive(Debug, Copy, Clone, Default)]
+struct RtAccounting {
+    activations: u64,
+    misses: u64,
+    worst_lateness: u64,
+    responses: u64,
+    response_ticks: u64,
+    /// Tick en que pasó a Ready, mientras espera su dispatch.
+    ready_since: Option<u64>,
+}
+
+/// Thread Control Block. Las clases de scheduling lo reciben de solo lectura.
+pub struct Thread {
     id: MyThreadId,
+    /// Nombre para los reportes (ver `my_thread_setname`).
+    name: Option<String>,
     context: ucontext_t,
+    // Solo se mantiene viva hasta que el TCB se libera (ver `reclaim`).
+    #[allow(dead_code)]
     stack: Vec<u8>,
     state: ThreadState,
 
     scheduler: SchedPolicy,
+    /// Grupo de scheduling (ver `my_thread_set_group`).
+    group: MySchedGroupId,
     tickets: u32,
+    /// Tickets extra acumulados por perder sorteos (ver `lottery_aging`).
+    bonus_tickets: u32,
+    /// Tickets prestados por hilos Lottery que esperan un mutex de este hilo.
+    donated_tickets: u32,
+    /// Donación que este hilo hace mientras espera un mutex: (dueño, tickets).
+    donating_to: Option<(MyThreadId, u32)>,
     rt_params: Option<RealTimeParams>,
+    deadline_misses: u64,
+    rt_acct: Option<RtAccounting>,
+    mlfq: MlfqParams,
+    /// Slots consumidos del quantum actual; el dispatch cuenta como el primero.
+    slice_used: u32,
+    /// Se pidió suspenderlo mientras estaba bloqueado: al despertar queda
+    /// Suspended en vez de Ready.
+    suspend_pending: bool,
+    /// Se pidió cancelarlo (ver `my_thread_cancel`).
+    cancelled: bool,
+    /// Mutexes que tiene tomados, en orden de adquisición.
+    held_mutexes: Vec<Rc<MutexCore>>,
+    /// Mutex `MutexHandoff::Barging` por el que lo despertaron y que todavía
+    /// no volvió a intentar tomar.
+    barge_wakeup: Option<Rc<MutexCore>>,
 
     start_routine: Option<ThreadFunc>,
     arg: *mut c_void,
+    /// Closure de `my_thread_spawn`; `closure_start` la saca al arrancar.
+    closure: Option<Box<dyn FnOnce()>>,
+    /// Valor devuelto por un hilo de `my_thread_spawn_with_result`.
+    output: Option<Box<dyn Any>>,
+    /// Pánico con el que terminó la closure; el join lo relanza.
+    panic: Option<Box<dyn Any + Send>>,
+    /// Tipo del `Box` que dejó `my_thread_end_boxed` en `result`.
+    result_type: Option<TypeId>,
     result: *mut c_void,
 
     joined_by: Option<MyThreadId>,
     detached: bool,
 
     block_reason: Option<BlockReason>,
+    /// `MyOnce` cuyo inicializador está ejecutando este hilo (pueden anidarse).
+    running_onces: Vec<*mut MyOnce>,
+    /// Pila de handlers de limpieza (el último se ejecuta primero).
+    cleanup_handlers: Vec<(CleanupFn, *mut c_void)>,
+    /// Valores locales del hilo, por clave.
+    tls: HashMap<MyTlsKey, *mut c_void>,
+    /// Se llama con `result` cuando se libera el TCB de un hilo detached.
+    finalizer: Option<ThreadFinalizer>,
+
+    acct: ThreadAccounting,
+}
+
+impl Thread {
+    pub fn id(&self) -> MyThreadId {
+        self.id
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Política actual del hilo.
+    pub fn policy(&self) -> SchedPolicy {
+        self.scheduler
+    }
+
+    /// Tickets con los que participa en el próximo sorteo.
+    fn effective_tickets(&self) -> u32 {
+        self.tickets
+            .saturating_add(self.bonus_tickets)
+            .saturating_add(self.donated_tickets)
+    }
+
+    /// Grupo en el que compite: las clases registradas no se separan por
+    /// grupo, así que sus hilos cuentan siempre en el grupo por defecto.
+    fn sched_group(&self) -> MySchedGroupId {
+        match self.scheduler {
+            SchedPolicy::Custom { .. } => MY_SCHED_GROUP_DEFAULT,
+            _ => self.group,
+        }
+    }
 }
 
 /// RNG simple para Lottery scheduler (LCG).
@@ -81,656 +666,7634 @@ impl Rng {
     }
 }
 
-/// Scheduler global de hilos de usuario.
-struct Scheduler {
-    threads: HashMap<MyThreadId, Thread>,
-    current: Option<MyThreadId>,
-    next_id: MyThreadId,
+// ================== Clases de scheduling ================== //
 
-    rr_queue: VecDeque<MyThreadId>,
-    lottery_list: Vec<MyThreadId>,
-    realtime_list: Vec<MyThreadId>,
+/// Prioridades de las clases incorporadas. `pick_next` le pide un hilo a cada
+/// clase de mayor a menor prioridad; a igual prioridad gana la registrada antes.
+pub const PRIO_REALTIME: u32 = 500;
+pub const PRIO_FIFO: u32 = 400;
+pub const PRIO_MLFQ: u32 = 300;
+pub const PRIO_LOTTERY: u32 = 200;
+pub const PRIO_ROUND_ROBIN: u32 = 100;
 
-    rng: Rng,
+/// Política de scheduling enchufable: mantiene sus propios hilos listos y
+/// decide cuál de ellos corre.
+///
+/// Los métodos se llaman con el scheduler tomado, así que no pueden usar
+/// ninguna función de esta biblioteca.
+pub trait SchedClass {
+    /// `tid` pasó a Ready y usa esta clase.
+    fn enqueue(&mut self, tid: MyThreadId, thread: &Thread);
+
+    /// `tid` dejó de estar listo sin ser elegido (se bloqueó, terminó o
+    /// cambió de política). Puede llamarse con hilos que la clase no tiene.
+    fn remove(&mut self, tid: MyThreadId);
+
+    /// Elige el próximo hilo y lo saca de sus listos. `None` si no tiene
+    /// ninguno, en cuyo caso se consulta la clase siguiente.
+    fn pick(&mut self, threads: &HashMap<MyThreadId, Thread>) -> Option<MyThreadId>;
 }
 
-impl Scheduler {
-    fn new() -> Self {
-        Scheduler {
-            threads: HashMap::new(),
-            current: None,
-            next_id: 0,
-            rr_queue: VecDeque::new(),
-            lottery_list: Vec::new(),
-            realtime_list: Vec::new(),
-            rng: Rng::new(0xdead_beef_cafe_babe),
+#[derive(Default)]
+struct RoundRobinClass {
+    queue: VecDeque<MyThreadId>,
+}
+
+impl SchedClass for RoundRobinClass {
+    fn enqueue(&mut self, tid: MyThreadId, _thread: &Thread) {
+        self.queue.push_back(tid);
+    }
+
+    fn remove(&mut self, tid: MyThreadId) {
+        self.queue.retain(|&id| id != tid);
+    }
+
+    fn pick(&mut self, _threads: &HashMap<MyThreadId, Thread>) -> Option<MyThreadId> {
+        self.queue.pop_front()
+    }
+}
+
+impl RoundRobinClass {
+    fn has_ready(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    /// Pasa al frente a un hilo recién encolado al fondo.
+    fn move_back_to_front(&mut self, tid: MyThreadId) {
+        if self.queue.back() == Some(&tid) {
+            self.queue.pop_back();
+            self.queue.push_front(tid);
         }
     }
+}
+
+/// Tiempo Real: menor deadline primero; a igual deadline, el que llegó antes.
+///
+/// Las bajas son perezosas: `remove` solo olvida la entrada vigente del hilo
+/// y `pick` descarta las que quedaron viejas, así que ninguna operación
+/// recorre la cola. Cuando las entradas viejas son mayoría se reconstruye.
+#[derive(Default)]
+struct RealTimeClass {
+    heap: BinaryHeap<(Reverse<u64>, Reverse<u64>, MyThreadId)>,
+    /// Número de secuencia de la entrada vigente de cada hilo encolado.
+    queued: HashMap<MyThreadId, u64>,
+    seq: u64,
+}
+
+impl RealTimeClass {
+    fn has_ready(&self) -> bool {
+        !self.queued.is_empty()
+    }
 
-    /// Inicializa el hilo main (id 0) si aún no existe.
-    fn ensure_main_thread(&mut self) {
-        if !self.threads.is_empty() {
+    fn is_live(&self, seq: u64, tid: MyThreadId) -> bool {
+        self.queued.get(&tid) == Some(&seq)
+    }
+}
+
+impl SchedClass for RealTimeClass {
+    fn enqueue(&mut self, tid: MyThreadId, thread: &Thread) {
+        if let Some(rt) = thread.rt_params {
+            self.seq += 1;
+            self.queued.insert(tid, self.seq);
+            self.heap.push((Reverse(rt.deadline), Reverse(self.seq), tid));
+        }
+    }
+
+    fn remove(&mut self, tid: MyThreadId) {
+        if self.queued.remove(&tid).is_none() {
             return;
         }
+        if self.heap.len() >= 64 && self.heap.len() > 2 * self.queued.len() {
+            let queued = mem::take(&mut self.queued);
+            self.heap.retain(|&(_, Reverse(seq), id)| queued.get(&id) == Some(&seq));
+            self.queued = queued;
+        }
+    }
 
-        // Capturamos el contexto actual como el hilo 0 (main).
-        let mut ctx: ucontext_t = unsafe { mem::zeroed() };
-        unsafe {
-            getcontext(&mut ctx as *mut ucontext_t);
+    fn pick(&mut self, _threads: &HashMap<MyThreadId, Thread>) -> Option<MyThreadId> {
+        while let Some((_, Reverse(seq), tid)) = self.heap.pop() {
+            if self.is_live(seq, tid) {
+                self.queued.remove(&tid);
+                return Some(tid);
+            }
         }
+        None
+    }
+}
 
-        let main_thread = Thread {
-            id: 0,
-            context: ctx,
-            stack: Vec::new(), // main usa la pila del proceso
-            state: ThreadState::Running,
-            scheduler: SchedPolicy::RoundRobin,
-            tickets: 0,
-            rt_params: None,
-            start_routine: None,
-            arg: ptr::null_mut(),
-            result: ptr::null_mut(),
-            joined_by: None,
-            detached: false,
-            block_reason: None,
-        };
+/// Fifo: mayor prioridad primero; a igual prioridad, orden de llegada.
+#[derive(Default)]
+struct FifoClass {
+    heap: BinaryHeap<(u8, Reverse<u64>, MyThreadId)>,
+    seq: u64,
+}
 
-        self.threads.insert(0, main_thread);
-        self.current = Some(0);
-        self.next_id = 1;
+impl FifoClass {
+    fn has_ready(&self) -> bool {
+        !self.heap.is_empty()
     }
+}
 
-    fn current_thread_id(&self) -> Option<MyThreadId> {
-        self.current
+impl SchedClass for FifoClass {
+    fn enqueue(&mut self, tid: MyThreadId, thread: &Thread) {
+        if let SchedPolicy::Fifo { priority } = thread.scheduler {
+            self.seq += 1;
+            self.heap.push((priority, Reverse(self.seq), tid));
+        }
     }
 
-    fn get_thread(&self, id: MyThreadId) -> Option<&Thread> {
-        self.threads.get(&id)
+    fn remove(&mut self, tid: MyThreadId) {
+        self.heap.retain(|&(_, _, id)| id != tid);
     }
 
-    fn get_thread_mut(&mut self, id: MyThreadId) -> Option<&mut Thread> {
-        self.threads.get_mut(&id)
+    fn pick(&mut self, _threads: &HashMap<MyThreadId, Thread>) -> Option<MyThreadId> {
+        self.heap.pop().map(|(_, _, tid)| tid)
     }
+}
 
-    /// Inserta un hilo en la cola de Ready correspondiente, según su política.
-    fn enqueue_ready(&mut self, tid: MyThreadId) {
-        let t = self.threads.get(&tid).expect("thread no encontrado en enqueue_ready");
-        match t.scheduler {
-            SchedPolicy::RoundRobin => self.rr_queue.push_back(tid),
-            SchedPolicy::Lottery { .. } => self.lottery_list.push(tid),
-            SchedPolicy::RealTime { .. } => self.realtime_list.push(tid),
-        }
-    }
+/// MLFQ: el primer hilo del nivel más alto no vacío. Los cambios de nivel y
+/// el boost los maneja el scheduler (ver `mlfq_on_yield` y `mlfq_boost`).
+#[derive(Default)]
+struct MlfqClass {
+    queues: [VecDeque<MyThreadId>; MLFQ_LEVELS],
+}
 
-    /// Elimina un hilo de todas las estructuras de Ready (por cambio de scheduler, bloqueo, etc.).
-    fn remove_from_ready_lists(&mut self, tid: MyThreadId) {
-        self.rr_queue.retain(|&id| id != tid);
-        self.lottery_list.retain(|&id| id != tid);
-        self.realtime_list.retain(|&id| id != tid);
+impl MlfqClass {
+    fn has_ready(&self) -> bool {
+        self.queues.iter().any(|q| !q.is_empty())
     }
 
-    /// Crea un nuevo hilo y lo deja en estado Ready.
-    fn create_thread(
-        &mut self,
-        start_routine: ThreadFunc,
-        arg: *mut c_void,
-        policy: SchedPolicy,
-    ) -> MyThreadId {
-        self.ensure_main_thread();
+    /// Pasa todos los hilos listos al nivel 0, conservando su orden.
+    fn boost(&mut self) {
+        for level in 1..MLFQ_LEVELS {
+            let queue = mem::take(&mut self.queues[level]);
+            self.queues[0].extend(queue);
+        }
+    }
+}
 
-        let id = self.next_id;
-        self.next_id += 1;
+impl SchedClass for MlfqClass {
+    fn enqueue(&mut self, tid: MyThreadId, thread: &Thread) {
+        self.queues[thread.mlfq.level].push_back(tid);
+    }
 
-        const STACK_SIZE: usize = 64 * 1024; // 64 KB (ajustable)
-        let mut stack = vec![0u8; STACK_SIZE];
+    fn remove(&mut self, tid: MyThreadId) {
+        for queue in &mut self.queues {
+            queue.retain(|&id| id != tid);
+        }
+    }
 
-        let mut ctx: ucontext_t = unsafe { mem::zeroed() };
-        unsafe {
-            getcontext(&mut ctx as *mut ucontext_t);
+    fn pick(&mut self, _threads: &HashMap<MyThreadId, Thread>) -> Option<MyThreadId> {
+        self.queues.iter_mut().find_map(|q| q.pop_front())
+    }
+}
 
-            // Asociar la pila al contexto
-            ctx.uc_stack.ss_sp = stack.as_mut_ptr() as *mut c_void;
-            ctx.uc_stack.ss_size = STACK_SIZE;
-            ctx.uc_link = ptr::null_mut();
+/// Árbol de Fenwick con los tickets de cada posición de la cola Lottery:
+/// actualizar una posición y buscar al ganador de un sorteo son O(log n).
+#[derive(Default)]
+struct TicketTree {
+    tree: Vec<u64>,
+}
 
-            // thread_trampoline no recibe argumentos en este diseño.
-            makecontext(
-                &mut ctx as *mut ucontext_t,
-                thread_trampoline as extern "C" fn(),
-                0,
-            );
+impl TicketTree {
+    fn push(&mut self, tickets: u64) {
+        // El nodo i (base 1) cubre las posiciones (i - lowbit(i), i].
+        let i = self.tree.len() + 1;
+        let lowbit = i & i.wrapping_neg();
+        let mut sum = tickets;
+        let mut j = i - 1;
+        while j > i - lowbit {
+            sum += self.tree[j - 1];
+            j -= j & j.wrapping_neg();
         }
+        self.tree.push(sum);
+    }
 
-        // Configurar tickets / RT params según la política
-        let mut tickets = 0;
-        let mut rt_params = None;
+    fn add(&mut self, pos: usize, delta: i64) {
+        let mut i = pos + 1;
+        while i <= self.tree.len() {
+            self.tree[i - 1] = self.tree[i - 1].wrapping_add_signed(delta);
+            i += i & i.wrapping_neg();
+        }
+    }
 
-        match policy {
-            SchedPolicy::RoundRobin => {}
-            SchedPolicy::Lottery { tickets: t } => {
-                tickets = if t == 0 { 1 } else { t };
-            }
-            SchedPolicy::RealTime { deadline } => {
-                rt_params = Some(RealTimeParams { deadline });
+    /// Primera posición cuya suma acumulada supera `r`.
+    fn find(&self, mut r: u64) -> usize {
+        let mut pos = 0;
+        let mut step = self.tree.len().checked_ilog2().map_or(0, |b| 1 << b);
+        while step > 0 {
+            if pos + step <= self.tree.len() && self.tree[pos + step - 1] <= r {
+                pos += step;
+                r -= self.tree[pos - 1];
             }
+            step >>= 1;
         }
-
-        let t = Thread {
-            id,
+        pos
+    }
+}
+
+/// Lottery: sorteo ponderado por los tickets efectivos de cada hilo. El
+/// envejecimiento de los perdedores lo aplica el scheduler.
+///
+/// Los hilos ocupan posiciones en orden de llegada; al salir dejan un hueco
+/// con 0 tickets, así que el ganador es el mismo que daría recorrer la cola
+/// en orden. El total se mantiene al día para no sumarlo en cada sorteo.
+struct LotteryClass {
+    slots: Vec<Option<MyThreadId>>,
+    weights: Vec<u64>,
+    positions: HashMap<MyThreadId, usize>,
+    tree: TicketTree,
+    total: u64,
+    rng: Rng,
+}
+
+impl LotteryClass {
+    fn new(seed: u64) -> Self {
+        LotteryClass {
+            slots: Vec::new(),
+            weights: Vec::new(),
+            positions: HashMap::new(),
+            tree: TicketTree::default(),
+            total: 0,
+            rng: Rng::new(seed),
+        }
+    }
+
+    fn has_ready(&self) -> bool {
+        !self.positions.is_empty()
+    }
+
+    /// Hilos en la cola, en orden de llegada.
+    fn ready(&self) -> impl Iterator<Item = MyThreadId> + '_ {
+        self.slots.iter().flatten().copied()
+    }
+
+    /// Cambia los tickets de un hilo encolado sin moverlo de su lugar.
+    fn update(&mut self, tid: MyThreadId, tickets: u32) {
+        let Some(&pos) = self.positions.get(&tid) else {
+            return;
+        };
+        let delta = tickets as i64 - self.weights[pos] as i64;
+        self.weights[pos] = tickets as u64;
+        self.tree.add(pos, delta);
+        self.total = self.total.wrapping_add_signed(delta);
+    }
+
+    /// Reconstruye las posiciones sin huecos cuando son mayoría.
+    fn compact(&mut self) {
+        if self.slots.len() < 64 || self.slots.len() < 2 * self.positions.len() {
+            return;
+        }
+        let live: Vec<(MyThreadId, u64)> = self
+            .slots
+            .iter()
+            .zip(&self.weights)
+            .filter_map(|(slot, &w)| slot.map(|tid| (tid, w)))
+            .collect();
+        self.slots.clear();
+        self.weights.clear();
+        self.positions.clear();
+        self.tree = TicketTree::default();
+        self.total = 0;
+        for (tid, w) in live {
+            self.push(tid, w);
+        }
+    }
+
+    fn push(&mut self, tid: MyThreadId, tickets: u64) {
+        self.positions.insert(tid, self.slots.len());
+        self.slots.push(Some(tid));
+        self.weights.push(tickets);
+        self.tree.push(tickets);
+        self.total += tickets;
+    }
+}
+
+impl SchedClass for LotteryClass {
+    fn enqueue(&mut self, tid: MyThreadId, thread: &Thread) {
+        self.push(tid, thread.effective_tickets() as u64);
+    }
+
+    fn remove(&mut self, tid: MyThreadId) {
+        let Some(pos) = self.positions.remove(&tid) else {
+            return;
+        };
+        self.tree.add(pos, -(self.weights[pos] as i64));
+        self.total -= self.weights[pos];
+        self.weights[pos] = 0;
+        self.slots[pos] = None;
+        self.compact();
+    }
+
+    fn pick(&mut self, _threads: &HashMap<MyThreadId, Thread>) -> Option<MyThreadId> {
+        if self.total == 0 {
+            return None;
+        }
+
+        let r = self.rng.next_u32() as u64 % self.total;
+        let tid = self.slots[self.tree.find(r)].expect("el sorteo cayó en un hueco");
+        self.remove(tid);
+        Some(tid)
+    }
+}
+
+/// Colas de listos de las clases incorporadas. Cada grupo de scheduling
+/// tiene las suyas, así que la política de un hilo solo lo ordena frente a
+/// los de su grupo.
+struct ReadyQueues {
+    rr: RoundRobinClass,
+    lottery: LotteryClass,
+    realtime: RealTimeClass,
+    mlfq: MlfqClass,
+    fifo: FifoClass,
+}
+
+impl ReadyQueues {
+    fn new(seed: u64) -> Self {
+        ReadyQueues {
+            rr: RoundRobinClass::default(),
+            lottery: LotteryClass::new(seed),
+            realtime: RealTimeClass::default(),
+            mlfq: MlfqClass::default(),
+            fifo: FifoClass::default(),
+        }
+    }
+
+    fn has_ready(&self) -> bool {
+        self.rr.has_ready()
+            || self.lottery.has_ready()
+            || self.realtime.has_ready()
+            || self.mlfq.has_ready()
+            || self.fifo.has_ready()
+    }
+
+    fn remove(&mut self, tid: MyThreadId) {
+        self.rr.remove(tid);
+        self.lottery.remove(tid);
+        self.realtime.remove(tid);
+        self.mlfq.remove(tid);
+        self.fifo.remove(tid);
+    }
+}
+
+/// Grupo por defecto: el de main y de todo hilo recién creado.
+pub const MY_SCHED_GROUP_DEFAULT: MySchedGroupId = 0;
+
+/// Un grupo de scheduling: compite por la CPU con los demás según su peso.
+struct SchedGroup {
+    weight: u32,
+    queues: ReadyQueues,
+    dispatches: u64,
+}
+
+impl SchedGroup {
+    fn new(weight: u32, seed: u64) -> Self {
+        SchedGroup { weight, queues: ReadyQueues::new(seed), dispatches: 0 }
+    }
+}
+
+/// Semilla del sorteo Lottery interno del grupo `group`: el grupo por
+/// defecto usa `seed` tal cual, para no cambiar las corridas sin grupos.
+fn group_seed(seed: u64, group: MySchedGroupId) -> u64 {
+    seed.wrapping_add(group as u64)
+}
+
+/// Scheduler global de hilos de usuario.
+struct Scheduler {
+    threads: HashMap<MyThreadId, Thread>,
+    current: Option<MyThreadId>,
+    next_id: MyThreadId,
+
+    /// Grupos de scheduling, indexados por id; el 0 es el grupo por defecto.
+    groups: Vec<SchedGroup>,
+    /// Sorteo entre grupos; solo se usa si hay más de uno con hilos listos.
+    group_rng: Rng,
+    mlfq_last_boost: u64,
+    /// Clases registradas con `my_sched_register_class`, indexadas por id.
+    custom_classes: Vec<Box<dyn SchedClass>>,
+    /// Orden en que `pick_next` consulta a las clases, de mayor a menor prioridad.
+    class_order: Vec<(u32, SchedPolicyKind)>,
+
+    /// Última semilla con la que se reinició el sorteo Lottery.
+    seed: u64,
+    /// Quantum de cada política, en slots (ver `my_sched_set_quantum`). Las
+    /// que no aparecen usan 1.
+    quanta: HashMap<SchedPolicyKind, u32>,
+    /// Tickets extra que gana un hilo Lottery cada vez que pierde un sorteo.
+    /// Con 0 no hay envejecimiento.
+    lottery_aging: u32,
+    /// Dónde despiertan los hilos RR (ver `my_sched_set_unblock_policy`).
+    unblock_policy: UnblockPolicy,
+
+    ticks: u64,
+    context_switches: u64,
+    deadline_miss_handler: Option<DeadlineMissHandler>,
+    /// Incumplimientos detectados que todavía no se reportaron al handler.
+    pending_misses: Vec<(MyThreadId, u64)>,
+    /// Finalizadores de hilos detached liberados, pendientes de llamar
+    /// fuera del scheduler (ver `fire_finalizers`).
+    pending_finalizers: Vec<(ThreadFinalizer, *mut c_void)>,
+    dispatches_by_policy: HashMap<SchedPolicyKind, u64>,
+
+    /// Hilos detached que ya terminaron y esperan ser liberados. No se pueden
+    /// liberar dentro de `finish_current` porque todavía corremos en su pila.
+    zombies: Vec<MyThreadId>,
+
+    /// Si es `true`, la llamada que cerraría un ciclo de espera devuelve
+    /// `EDEADLK` en vez de abortar el proceso.
+    deadlock_errors: bool,
+    /// Destructor de cada clave TLS creada; la clave es el índice.
+    tls_destructors: Vec<Option<TlsDestructor>>,
+    /// Tamaño de pila de los hilos que se creen a partir de ahora.
+    stack_size: usize,
+    /// Máximo de TCBs vivos (incluido main).
+    max_threads: usize,
+
+    /// Esperas con plazo (`my_thread_sleep`, `my_cond_timedwait`), ordenadas
+    /// por el tick en que vencen. Puede tener entradas viejas de hilos que
+    /// despertaron antes por otra vía: se descartan al revisarlas.
+    sleepers: BinaryHeap<Reverse<(u64, MyThreadId)>>,
+    /// Hilo interno que corre cuando todos duermen, para que el reloj virtual
+    /// avance. Se crea la primera vez que hace falta.
+    idle_tid: Option<MyThreadId>,
+}
+
+impl Scheduler {
+    fn new() -> Self {
+        Scheduler {
+            threads: HashMap::new(),
+            current: None,
+            next_id: 0,
+            groups: vec![SchedGroup::new(1, DEFAULT_SEED)],
+            group_rng: Rng::new(!DEFAULT_SEED),
+            mlfq_last_boost: 0,
+            custom_classes: Vec::new(),
+            class_order: vec![
+                (PRIO_REALTIME, SchedPolicyKind::RealTime),
+                (PRIO_FIFO, SchedPolicyKind::Fifo),
+                (PRIO_MLFQ, SchedPolicyKind::Mlfq),
+                (PRIO_LOTTERY, SchedPolicyKind::Lottery),
+                (PRIO_ROUND_ROBIN, SchedPolicyKind::RoundRobin),
+            ],
+            seed: DEFAULT_SEED,
+            quanta: HashMap::new(),
+            lottery_aging: 0,
+            unblock_policy: UnblockPolicy::BackOfQueue,
+            ticks: 0,
+            context_switches: 0,
+            deadline_miss_handler: None,
+            pending_misses: Vec::new(),
+            pending_finalizers: Vec::new(),
+            dispatches_by_policy: HashMap::new(),
+            zombies: Vec::new(),
+            deadlock_errors: false,
+            tls_destructors: Vec::new(),
+            stack_size: DEFAULT_STACK_SIZE,
+            max_threads: DEFAULT_MAX_THREADS,
+            sleepers: BinaryHeap::new(),
+            idle_tid: None,
+        }
+    }
+
+    /// Inicializa el hilo main (id 0) si aún no existe.
+    fn ensure_main_thread(&mut self) {
+        if !self.threads.is_empty() {
+            return;
+        }
+
+        // Capturamos el contexto actual como el hilo 0 (main).
+        let mut ctx: ucontext_t = unsafe { mem::zeroed() };
+        unsafe {
+            getcontext(&mut ctx as *mut ucontext_t);
+        }
+
+        let main_thread = Thread {
+            id: 0,
+            name: None,
             context: ctx,
-            stack,
-            state: ThreadState::Ready,
-            scheduler: policy,
-            tickets,
-            rt_params,
-            start_routine: Some(start_routine),
-            arg,
+            stack: Vec::new(), // main usa la pila del proceso
+            state: ThreadState::Running,
+            scheduler: SchedPolicy::RoundRobin,
+            group: MY_SCHED_GROUP_DEFAULT,
+            tickets: 0,
+            bonus_tickets: 0,
+            donated_tickets: 0,
+            donating_to: None,
+            rt_params: None,
+            deadline_misses: 0,
+            rt_acct: None,
+            mlfq: MlfqParams::default(),
+            slice_used: 1, // main ya está corriendo
+            suspend_pending: false,
+            cancelled: false,
+            held_mutexes: Vec::new(),
+            barge_wakeup: None,
+            start_routine: None,
+            arg: ptr::null_mut(),
+            closure: None,
+            output: None,
+            panic: None,
+            result_type: None,
             result: ptr::null_mut(),
             joined_by: None,
             detached: false,
             block_reason: None,
+            running_onces: Vec::new(),
+            cleanup_handlers: Vec::new(),
+            tls: HashMap::new(),
+            finalizer: None,
+            acct: ThreadAccounting::default(),
         };
 
-        self.threads.insert(id, t);
-        self.enqueue_ready(id);
+        self.threads.insert(0, main_thread);
+        self.current = Some(0);
+        self.next_id = 1;
+    }
 
-        id
+    fn current_thread_id(&self) -> Option<MyThreadId> {
+        self.current
     }
 
-    /// Selecciona el próximo hilo a ejecutar según RT > Lottery > RR.
-    fn pick_next(&mut self) -> Option<MyThreadId> {
-        // Hilos de Tiempo Real: menor deadline primero
-        if !self.realtime_list.is_empty() {
-            let mut best_idx = 0;
-            let mut best_deadline = {
-                let tid = self.realtime_list[0];
-                let t = self.threads.get(&tid).unwrap();
-                t.rt_params.unwrap().deadline
-            };
+    fn get_thread(&self, id: MyThreadId) -> Option<&Thread> {
+        self.threads.get(&id)
+    }
 
-            for (i, &tid) in self.realtime_list.iter().enumerate().skip(1) {
-                let d = self.threads.get(&tid).unwrap().rt_params.unwrap().deadline;
-                if d < best_deadline {
-                    best_deadline = d;
-                    best_idx = i;
-                }
-            }
+    fn get_thread_mut(&mut self, id: MyThreadId) -> Option<&mut Thread> {
+        self.threads.get_mut(&id)
+    }
 
-            let tid = self.realtime_list.remove(best_idx);
-            let thr = self.threads.get_mut(&tid).unwrap();
-            thr.state = ThreadState::Running;
-            return Some(tid);
+    /// Cambia el estado de un hilo, acumulando el tiempo que pasó en el anterior.
+    fn set_state(&mut self, tid: MyThreadId, state: ThreadState) {
+        let now = self.ticks;
+        let thr = self.threads.get_mut(&tid).expect("thread no encontrado en set_state");
+        let elapsed = now - thr.acct.state_since;
+        match thr.state {
+            ThreadState::Ready => thr.acct.ready_ticks += elapsed,
+            ThreadState::Blocked => thr.acct.blocked_ticks += elapsed,
+            _ => {}
         }
+        thr.state = state;
+        thr.acct.state_since = now;
+    }
 
-        // Lottery scheduler
-        if !self.lottery_list.is_empty() {
-            let total_tickets: u32 = self
-                .lottery_list
-                .iter()
-                .map(|tid| self.threads.get(tid).unwrap().tickets)
-                .sum();
+    /// Registra (una sola vez por deadline) si un hilo de Tiempo Real ya
+    /// pasó su deadline, e invoca el handler global si existe.
+    fn check_deadline(&mut self, tid: MyThreadId) {
+        let now = self.ticks;
+        let thr = self.threads.get_mut(&tid).unwrap();
+        let Some(rt) = thr.rt_params.as_mut() else {
+            return;
+        };
+        if rt.missed || now <= rt.abs_deadline {
+            return;
+        }
 
-            if total_tickets > 0 {
-                let mut r = self.rng.next_u32() % total_tickets;
-                let mut winner_idx = 0;
+        rt.missed = true;
+        let lateness = now - rt.abs_deadline;
+        thr.deadline_misses += 1;
+        let acct = thr.rt_acct.get_or_insert_default();
+        acct.misses += 1;
+        acct.worst_lateness = acct.worst_lateness.max(lateness);
 
-                for (i, &tid) in self.lottery_list.iter().enumerate() {
-                    let t = self.threads.get(&tid).unwrap().tickets;
-                    if r < t {
-                        winner_idx = i;
-                        break;
-                    } else {
-                        r -= t;
-                    }
-                }
+        // El handler es código del usuario: se llama fuera del scheduler
+        // (ver `fire_deadline_misses`).
+        if self.deadline_miss_handler.is_some() {
+            self.pending_misses.push((tid, lateness));
+        }
+    }
 
-                let tid = self.lottery_list.remove(winner_idx);
-                let thr = self.threads.get_mut(&tid).unwrap();
-                thr.state = ThreadState::Running;
-                return Some(tid);
+    /// Marca como Running al hilo elegido por `pick_next` y actualiza contadores.
+    fn dispatch(&mut self, tid: MyThreadId) {
+        self.ticks += 1;
+        self.set_state(tid, ThreadState::Running);
+
+        self.check_deadline(tid);
+
+        let ticks = self.ticks;
+        let thr = self.threads.get_mut(&tid).unwrap();
+        thr.acct.run_count += 1;
+        thr.acct.slices += 1;
+        thr.acct.first_run_at.get_or_insert(ticks);
+        if let Some(woken_at) = thr.acct.woken_at.take() {
+            thr.acct.wakeups += 1;
+            thr.acct.wakeup_latency_ticks += ticks - woken_at;
+        }
+        if let Some(since) = thr.rt_acct.as_mut().and_then(|a| a.ready_since.take()) {
+            let acct = thr.rt_acct.as_mut().unwrap();
+            acct.responses += 1;
+            acct.response_ticks += ticks - since;
+        }
+        thr.slice_used = 1;
+        if let SchedPolicy::Mlfq = thr.scheduler {
+            thr.mlfq.used += 1;
+        }
+        *self
+            .dispatches_by_policy
+            .entry(thr.scheduler.kind())
+            .or_insert(0) += 1;
+        self.groups[thr.sched_group()].dispatches += 1;
+    }
+
+    /// Inserta un hilo en la cola de Ready correspondiente, según su política.
+    fn enqueue_ready(&mut self, tid: MyThreadId) {
+        let t = self.threads.get(&tid).expect("thread no encontrado en enqueue_ready");
+        // Un hilo terminado nunca debe volver a correr: su contexto quedó
+        // detenido dentro de `my_thread_end`.
+        if t.state == ThreadState::Finished || self.idle_tid == Some(tid) {
+            return;
+        }
+        if t.scheduler.kind() == SchedPolicyKind::RealTime {
+            let now = self.ticks;
+            let acct = self.threads.get_mut(&tid).unwrap().rt_acct.get_or_insert_default();
+            // Un reencolado (por ejemplo, al cambiar el deadline) no es una
+            // activación nueva.
+            if acct.ready_since.is_none() {
+                acct.activations += 1;
+                acct.ready_since = Some(now);
             }
         }
+        let t = &self.threads[&tid];
+        let queues = &mut self.groups[t.sched_group()].queues;
+        match t.scheduler.kind() {
+            SchedPolicyKind::RoundRobin => queues.rr.enqueue(tid, t),
+            SchedPolicyKind::Lottery => queues.lottery.enqueue(tid, t),
+            SchedPolicyKind::RealTime => queues.realtime.enqueue(tid, t),
+            SchedPolicyKind::Mlfq => queues.mlfq.enqueue(tid, t),
+            SchedPolicyKind::Fifo => queues.fifo.enqueue(tid, t),
+            SchedPolicyKind::Custom { class_id } => self.custom_classes[class_id].enqueue(tid, t),
+        }
+    }
 
-        // Round Robin
-        if let Some(tid) = self.rr_queue.pop_front() {
-            let thr = self.threads.get_mut(&tid).unwrap();
-            thr.state = ThreadState::Running;
-            return Some(tid);
+    /// Elimina un hilo de todas las estructuras de Ready (por cambio de scheduler, bloqueo, etc.).
+    fn remove_from_ready_lists(&mut self, tid: MyThreadId) {
+        for group in &mut self.groups {
+            group.queues.remove(tid);
+        }
+        for class in &mut self.custom_classes {
+            class.remove(tid);
         }
+    }
 
-        None
+    /// Un hilo MLFQ que cede la CPU: si agotó el quantum de su nivel, baja uno.
+    fn mlfq_on_yield(&mut self, tid: MyThreadId) {
+        let thr = self.threads.get_mut(&tid).unwrap();
+        if let SchedPolicy::Mlfq = thr.scheduler
+            && thr.mlfq.used >= MLFQ_QUANTA[thr.mlfq.level]
+        {
+            thr.mlfq.level = (thr.mlfq.level + 1).min(MLFQ_LEVELS - 1);
+            thr.mlfq.used = 0;
+        }
+    }
+
+    /// Un hilo MLFQ que despierta tras bloquearse sube un nivel.
+    fn mlfq_on_wakeup(&mut self, tid: MyThreadId) {
+        let thr = self.threads.get_mut(&tid).unwrap();
+        if let SchedPolicy::Mlfq = thr.scheduler {
+            thr.mlfq.level = thr.mlfq.level.saturating_sub(1);
+            thr.mlfq.used = 0;
+        }
+    }
+
+    /// Sube todos los hilos MLFQ al nivel 0.
+    fn mlfq_boost(&mut self) {
+        self.mlfq_last_boost = self.ticks;
+        for group in &mut self.groups {
+            group.queues.mlfq.boost();
+        }
+        for thr in self.threads.values_mut() {
+            if let SchedPolicy::Mlfq = thr.scheduler {
+                thr.mlfq = MlfqParams::default();
+            }
+        }
     }
 
-    /// El hilo actual cede la CPU voluntariamente.
-    fn yield_current(&mut self) {
+    /// Crea un nuevo hilo y lo deja en estado Ready.
+    fn create_thread(
+        &mut self,
+        start_routine: ThreadFunc,
+        arg: *mut c_void,
+        policy: SchedPolicy,
+    ) -> Result<MyThreadId, ThreadError> {
         self.ensure_main_thread();
 
-        let curr_id = match self.current {
-            Some(id) => id,
-            None => return,
-        };
+        // Se valida antes de reservar la pila.
+        if !self.class_exists(policy) {
+            return Err(ThreadError::InvalidPolicy(policy));
+        }
+        if self.live_threads() >= self.max_threads {
+            return Err(ThreadError::ResourceLimit { max_threads: self.max_threads });
+        }
 
-        // Marcar actual como Ready y encolar
-        {
-            let thr = self.threads.get_mut(&curr_id).unwrap();
-            if thr.state == ThreadState::Running {
-                thr.state = ThreadState::Ready;
-                self.enqueue_ready(curr_id);
-            }
+        self.spawn_thread(start_routine, arg, policy)
+    }
+
+    /// Reserva la pila y el contexto de un hilo nuevo, sin revisar el límite
+    /// de hilos (el idle no cuenta para él).
+    fn spawn_thread(
+        &mut self,
+        start_routine: ThreadFunc,
+        arg: *mut c_void,
+        policy: SchedPolicy,
+    ) -> Result<MyThreadId, ThreadError> {
+        let stack_size = self.stack_size;
+        let mut stack = Vec::new();
+        stack
+            .try_reserve_exact(stack_size)
+            .map_err(|_| ThreadError::StackAllocation { size: stack_size })?;
+        stack.resize(stack_size, 0u8);
+        // La pila crece hacia abajo: el canario queda en el extremo bajo.
+        for (i, byte) in stack[..STACK_CANARY_LEN].iter_mut().enumerate() {
+            *byte = STACK_CANARY[i % STACK_CANARY.len()];
         }
 
-        // Elegir siguiente
-        if let Some(next_id) = self.pick_next() {
-            if next_id == curr_id {
-                return;
+        let mut ctx: ucontext_t = unsafe { mem::zeroed() };
+        unsafe {
+            if getcontext(&mut ctx as *mut ucontext_t) != 0 {
+                let errno = std::io::Error::last_os_error().raw_os_error().unwrap_or(EINVAL);
+                return Err(ThreadError::Context { errno });
             }
 
-            // Preparar punteros a contextos
-            let (curr_ctx_ptr, next_ctx_ptr) = {
-                let curr_ctx: *mut ucontext_t =
-                    &mut self.threads.get_mut(&curr_id).unwrap().context;
-                let next_ctx: *mut ucontext_t =
-                    &mut self.threads.get_mut(&next_id).unwrap().context;
-                (curr_ctx, next_ctx)
-            };
+            // Asociar la pila al contexto
+            ctx.uc_stack.ss_sp = stack.as_mut_ptr() as *mut c_void;
+            ctx.uc_stack.ss_size = stack_size;
+            ctx.uc_link = ptr::null_mut();
+            // Los hilos arrancan sin preempción aunque se creen dentro de
+            // `my_thread_preemptible` (ver esa función).
+            libc::sigaddset(&mut ctx.uc_sigmask, libc::SIGALRM);
 
-            self.current = Some(next_id);
+            // thread_trampoline no recibe argumentos en este diseño.
+            makecontext(
+                &mut ctx as *mut ucontext_t,
+                thread_trampoline as extern "C" fn(),
+                0,
+            );
+        }
 
-            unsafe {
-                swapcontext(curr_ctx_ptr, next_ctx_ptr);
+        // El id se asigna recién cuando ya no hay nada que pueda fallar.
+        let id = self.next_id;
+        self.next_id += 1;
+
+        // Configurar tickets / RT params según la política
+        let mut tickets = 0;
+        let mut rt_params = None;
+        let mut policy = policy;
+
+        match policy {
+            SchedPolicy::RoundRobin => {}
+            SchedPolicy::Lottery { tickets: t } => {
+                tickets = if t == 0 { 1 } else { t };
+                policy = SchedPolicy::Lottery { tickets };
+            }
+            SchedPolicy::RealTime { deadline } => {
+                rt_params = Some(RealTimeParams::relative(deadline, self.ticks));
             }
+            SchedPolicy::Mlfq | SchedPolicy::Fifo { .. } | SchedPolicy::Custom { .. } => {}
+        }
+
+        let t = Thread {
+            id,
+            name: None,
+            context: ctx,
+            stack,
+            state: ThreadState::Ready,
+            scheduler: policy,
+            group: MY_SCHED_GROUP_DEFAULT,
+            tickets,
+            bonus_tickets: 0,
+            donated_tickets: 0,
+            donating_to: None,
+            rt_params,
+            deadline_misses: 0,
+            rt_acct: None,
+            mlfq: MlfqParams::default(),
+            slice_used: 0,
+            suspend_pending: false,
+            cancelled: false,
+            held_mutexes: Vec::new(),
+            barge_wakeup: None,
+            start_routine: Some(start_routine),
+            arg,
+            closure: None,
+            output: None,
+            panic: None,
+            result_type: None,
+            result: ptr::null_mut(),
+            joined_by: None,
+            detached: false,
+            block_reason: None,
+            running_onces: Vec::new(),
+            cleanup_handlers: Vec::new(),
+            tls: HashMap::new(),
+            finalizer: None,
+            acct: ThreadAccounting {
+                state_since: self.ticks,
+                created_at: self.ticks,
+                ..ThreadAccounting::default()
+            },
+        };
+
+        self.threads.insert(id, t);
+        self.enqueue_ready(id);
+
+        Ok(id)
+    }
+
+    /// `false` si `policy` usa una clase que no se registró.
+    fn class_exists(&self, policy: SchedPolicy) -> bool {
+        match policy {
+            SchedPolicy::Custom { class_id, .. } => class_id < self.custom_classes.len(),
+            _ => true,
         }
     }
 
-    /// Bloquea el hilo actual (por mutex, join, etc.) y hace schedule.
-    fn block_current(&mut self, reason: BlockReason) {
-        self.ensure_main_thread();
+    /// Cantidad de TCBs vivos, sin contar el hilo idle.
+    fn live_threads(&self) -> usize {
+        self.threads.len() - usize::from(self.idle_tid.is_some())
+    }
 
-        let curr_id = self.current.expect("no hay hilo actual en block_current");
+    /// `true` si `tid` es un hilo del usuario (incluido main) y no el idle.
+    fn is_user_thread(&self, tid: MyThreadId) -> bool {
+        self.idle_tid != Some(tid) && self.threads.contains_key(&tid)
+    }
 
-        {
-            let thr = self.threads.get_mut(&curr_id).unwrap();
-            thr.state = ThreadState::Blocked;
-            thr.block_reason = Some(reason);
+    /// `true` si la entrada `(until, tid)` de `sleepers` sigue vigente.
+    fn waits_until(&self, tid: MyThreadId, until: u64) -> bool {
+        self.threads.get(&tid).is_some_and(|t| {
+            t.state == ThreadState::Blocked && t.block_reason.and_then(|r| r.until()) == Some(until)
+        })
+    }
+
+    /// Despierta a los hilos cuyo plazo de espera ya se cumplió.
+    fn wake_sleepers(&mut self) {
+        while let Some(&Reverse((until, tid))) = self.sleepers.peek() {
+            if until > self.ticks {
+                break;
+            }
+            self.sleepers.pop();
+            if self.waits_until(tid, until) {
+                self.unblock(tid);
+            }
         }
+    }
 
-        self.remove_from_ready_lists(curr_id);
+    /// `true` si algún hilo bloqueado va a despertar solo cuando avance el
+    /// reloj. Aprovecha para descartar las entradas viejas.
+    fn has_timed_waits(&mut self) -> bool {
+        let stale: Vec<_> = self
+            .sleepers
+            .iter()
+            .filter(|&&Reverse((until, tid))| !self.waits_until(tid, until))
+            .copied()
+            .collect();
+        if !stale.is_empty() {
+            self.sleepers.retain(|entry| !stale.contains(entry));
+        }
+        !self.sleepers.is_empty()
+    }
+
+    /// Pone a correr el hilo idle, creándolo si hace falta. No cuenta como
+    /// dispatch de ninguna política; solo avanza el reloj virtual.
+    fn run_idle(&mut self) -> MyThreadId {
+        let idle = match self.idle_tid {
+            Some(tid) => tid,
+            None => {
+                let tid = self
+                    .spawn_thread(idle_loop, ptr::null_mut(), SchedPolicy::RoundRobin)
+                    .unwrap_or_else(|e| panic!("no se pudo crear el hilo idle: {e}"));
+                self.remove_from_ready_lists(tid);
+                self.idle_tid = Some(tid);
+                tid
+            }
+        };
+        self.ticks += 1;
+        self.set_state(idle, ThreadState::Running);
+        self.threads.get_mut(&idle).unwrap().acct.run_count += 1;
+        idle
+    }
+
+    /// Selecciona el próximo hilo a ejecutar consultando las clases por
+    /// prioridad; sin clases registradas: RT > Fifo > MLFQ > Lottery > RR.
+    ///
+    /// Si ninguno está listo pero hay hilos dormidos, elige al idle hasta que
+    /// alguno despierte.
+    ///
+    /// Con más de un grupo de scheduling primero se sortea un grupo entre los
+    /// que tienen hilos listos, ponderado por su peso, y recién dentro de él
+    /// se consulta a las clases.
+    fn pick_next(&mut self) -> Option<MyThreadId> {
+        self.wake_sleepers();
+
+        let mut candidates: Vec<MySchedGroupId> = (0..self.groups.len()).collect();
+        while let Some(group) = self.choose_group(&mut candidates) {
+            for i in 0..self.class_order.len() {
+                let kind = self.class_order[i].1;
+                if let Some(tid) = self.pick_from(group, kind) {
+                    self.dispatch(tid);
+                    return Some(tid);
+                }
+            }
+        }
+
+        if self.has_timed_waits() {
+            return Some(self.run_idle());
+        }
+
+        None
+    }
+
+    /// Sortea un grupo entre `candidates` según su peso y lo saca de la
+    /// lista. Descarta antes a los que no tienen hilos listos; el por defecto
+    /// se queda si hay clases registradas, porque de ellas no se sabe.
+    fn choose_group(&mut self, candidates: &mut Vec<MySchedGroupId>) -> Option<MySchedGroupId> {
+        candidates.retain(|&g| {
+            self.groups[g].queues.has_ready()
+                || (g == MY_SCHED_GROUP_DEFAULT && !self.custom_classes.is_empty())
+        });
+        if candidates.len() <= 1 {
+            return candidates.pop();
+        }
+
+        let total: u64 = candidates.iter().map(|&g| self.groups[g].weight as u64).sum();
+        let mut r = self.group_rng.next_u32() as u64 % total;
+        let pos = candidates
+            .iter()
+            .position(|&g| {
+                let weight = self.groups[g].weight as u64;
+                if r < weight {
+                    return true;
+                }
+                r -= weight;
+                false
+            })
+            .unwrap();
+        Some(candidates.remove(pos))
+    }
+
+    /// Le pide un hilo del grupo `group` a la clase `kind`.
+    fn pick_from(&mut self, group: MySchedGroupId, kind: SchedPolicyKind) -> Option<MyThreadId> {
+        match kind {
+            SchedPolicyKind::RealTime => self.groups[group].queues.realtime.pick(&self.threads),
+            SchedPolicyKind::Fifo => self.groups[group].queues.fifo.pick(&self.threads),
+            SchedPolicyKind::Mlfq => {
+                if self.ticks - self.mlfq_last_boost >= MLFQ_BOOST_PERIOD {
+                    self.mlfq_boost();
+                }
+                self.groups[group].queues.mlfq.pick(&self.threads)
+            }
+            SchedPolicyKind::Lottery => {
+                let lottery = &mut self.groups[group].queues.lottery;
+                let tid = lottery.pick(&self.threads)?;
+
+                // Envejecimiento: los perdedores acumulan tickets extra y el
+                // ganador vuelve a sus tickets base. Sin envejecimiento el
+                // sorteo no recorre la cola.
+                if self.lottery_aging > 0 {
+                    let losers: Vec<MyThreadId> = lottery.ready().collect();
+                    for loser in losers {
+                        let thr = self.threads.get_mut(&loser).unwrap();
+                        thr.bonus_tickets = thr.bonus_tickets.saturating_add(self.lottery_aging);
+                        lottery.update(loser, thr.effective_tickets());
+                    }
+                }
+                self.threads.get_mut(&tid).unwrap().bonus_tickets = 0;
+                Some(tid)
+            }
+            SchedPolicyKind::RoundRobin => self.groups[group].queues.rr.pick(&self.threads),
+            SchedPolicyKind::Custom { .. } if group != MY_SCHED_GROUP_DEFAULT => None,
+            SchedPolicyKind::Custom { class_id } => {
+                let tid = self.custom_classes[class_id].pick(&self.threads)?;
+                // Un id inventado o de un hilo que no está listo corrompería
+                // el scheduler: mejor fallar acá.
+                let ready = self.threads.get(&tid).is_some_and(|t| {
+                    t.state == ThreadState::Ready && t.scheduler.kind() == kind
+                });
+                assert!(ready, "la clase {class_id} eligió al hilo {tid}, que no está listo en ella");
+                Some(tid)
+            }
+        }
+    }
+
+    /// Aborta si el hilo actual pisó el canario al fondo de su pila. Seguir
+    /// corriendo solo haría que el daño apareciera lejos de aquí.
+    fn check_stack(&self) {
+        let Some(thr) = self.current.and_then(|tid| self.threads.get(&tid)) else {
+            return;
+        };
+        // El main usa la pila del proceso.
+        if thr.stack.is_empty() {
+            return;
+        }
+        let intact = thr.stack[..STACK_CANARY_LEN]
+            .iter()
+            .enumerate()
+            .all(|(i, &byte)| byte == STACK_CANARY[i % STACK_CANARY.len()]);
+        if !intact {
+            eprintln!(
+                "mypthreads: desbordamiento de pila en el {} (pila de {} bytes)",
+                self.label(thr.id),
+                thr.stack.len()
+            );
+            std::process::abort();
+        }
+    }
+
+    /// Prepara el cambio de contexto de `curr` a `next`; quien lo ejecuta es
+    /// `switch_context`, ya sin el scheduler prestado.
+    fn switch_to(&mut self, curr: MyThreadId, next: MyThreadId) -> ContextSwitch {
+        let from: *mut ucontext_t = &mut self.threads.get_mut(&curr).unwrap().context;
+        let to: *mut ucontext_t = &mut self.threads.get_mut(&next).unwrap().context;
+        self.current = Some(next);
+        self.context_switches += 1;
+        self.threads.get_mut(&next).unwrap().acct.switches += 1;
+        ContextSwitch { from, to }
+    }
+
+    /// Si al hilo que cede la CPU le quedan slots de su quantum, consume uno y
+    /// sigue corriendo sin pasar por `pick_next`. No cuenta como dispatch,
+    /// pero avanza el reloj como si lo fuera.
+    fn continue_slice(&mut self, tid: MyThreadId) -> bool {
+        if self.idle_tid == Some(tid) {
+            return false;
+        }
+        let thr = self.threads.get_mut(&tid).unwrap();
+        let quantum = self.quanta.get(&thr.scheduler.kind()).copied().unwrap_or(1);
+        if thr.state != ThreadState::Running || thr.slice_used >= quantum {
+            return false;
+        }
+        thr.slice_used += 1;
+        thr.acct.slices += 1;
+        self.ticks += 1;
+        self.check_deadline(tid);
+        true
+    }
+
+    /// El hilo actual cede la CPU voluntariamente. Devuelve el cambio de
+    /// contexto a realizar, si le toca a otro hilo.
+    fn yield_current(&mut self) -> Option<ContextSwitch> {
+        self.ensure_main_thread();
+        self.check_stack();
+
+        let curr_id = self.current?;
+
+        if self.continue_slice(curr_id) {
+            return None;
+        }
+
+        // Marcar actual como Ready y encolar
+        if self.threads[&curr_id].state == ThreadState::Running {
+            self.set_state(curr_id, ThreadState::Ready);
+            self.mlfq_on_yield(curr_id);
+            self.enqueue_ready(curr_id);
+        }
+
+        // Elegir siguiente
+        let next_id = self.pick_next()?;
+        if next_id == curr_id {
+            return None;
+        }
+        Some(self.switch_to(curr_id, next_id))
+    }
+
+    /// El hilo actual le cede la CPU directamente a `target`, salteando a
+    /// `pick_next`. El que cede vuelve a su cola como en un yield normal.
+    fn yield_to(&mut self, target: MyThreadId) -> Result<ContextSwitch, c_int> {
+        self.ensure_main_thread();
+        self.check_stack();
+
+        let curr_id = self.current.expect("no hay hilo actual en yield_to");
+        if target == curr_id || !self.is_user_thread(target) {
+            return Err(EINVAL);
+        }
+        if self.threads[&target].state != ThreadState::Ready {
+            return Err(EAGAIN);
+        }
+
+        if self.threads[&curr_id].state == ThreadState::Running {
+            self.set_state(curr_id, ThreadState::Ready);
+            self.mlfq_on_yield(curr_id);
+            self.enqueue_ready(curr_id);
+        }
+        self.wake_sleepers();
+        self.remove_from_ready_lists(target);
+        self.dispatch(target);
+        Ok(self.switch_to(curr_id, target))
+    }
+
+    /// Bloquea el hilo actual (por mutex, join, etc.) y elige el siguiente.
+    fn block_current(&mut self, reason: BlockReason) -> ContextSwitch {
+        self.ensure_main_thread();
+        self.check_stack();
+
+        let curr_id = self.current.expect("no hay hilo actual en block_current");
+        self.threads.get_mut(&curr_id).unwrap().block_reason = Some(reason);
+        self.deschedule_current(curr_id, ThreadState::Blocked)
+    }
+
+    /// Saca de la CPU al hilo actual dejándolo en `state` y elige el siguiente.
+    fn deschedule_current(&mut self, curr_id: MyThreadId, state: ThreadState) -> ContextSwitch {
+        self.set_state(curr_id, state);
+        self.remove_from_ready_lists(curr_id);
+
+        // Elegir siguiente
+        if let Some(next_id) = self.pick_next() {
+            self.switch_to(curr_id, next_id)
+        } else {
+            // Nadie puede correr: si volviéramos, el llamador creería que lo
+            // despertaron.
+            self.abort_all_blocked();
+        }
+    }
+
+    /// Busca un ciclo en el grafo de esperas entre los hilos bloqueados
+    /// (join, mutex, once), siguiendo `block_reason` de cada uno. Devuelve
+    /// los hilos del ciclo en orden de espera, empezando por el de menor id.
+    fn detect_deadlock(&self) -> Option<Vec<MyThreadId>> {
+        let mut blocked: Vec<_> = self
+            .threads
+            .values()
+            .filter(|t| t.state == ThreadState::Blocked)
+            .filter_map(|t| Some((t.id, t.block_reason?)))
+            .collect();
+        blocked.sort_by_key(|&(tid, _)| tid);
+        blocked.into_iter().find_map(|(tid, reason)| self.find_wait_cycle(tid, reason))
+    }
+
+    /// Aborta listando por qué espera cada hilo bloqueado, y el ciclo de
+    /// espera que los trabó si lo hay.
+    fn abort_all_blocked(&self) -> ! {
+        if let Some(cycle) = self.detect_deadlock() {
+            let mut lines: Vec<String> = cycle
+                .iter()
+                .map(|&t| self.threads[&t].block_reason.unwrap().describe(&self.label(t)))
+                .collect();
+            lines.push(format!(
+                "ciclo: {} -> {}",
+                cycle.iter().map(|&t| self.label(t)).collect::<Vec<_>>().join(" -> "),
+                self.label(cycle[0]),
+            ));
+            self.abort_deadlock("todos los hilos están bloqueados en un ciclo de espera", &lines);
+        }
+
+        let mut lines: Vec<String> = self
+            .threads
+            .values()
+            .filter_map(|t| match t.state {
+                ThreadState::Blocked => t.block_reason.map(|r| r.describe(&self.label(t.id))),
+                ThreadState::Suspended => Some(format!("{} está suspendido", self.label(t.id))),
+                _ => None,
+            })
+            .collect();
+        lines.sort();
+        self.abort_deadlock("todos los hilos están bloqueados", &lines);
+    }
+
+    /// Busca el ciclo que se formaría si `tid` se bloqueara por `reason`.
+    /// Devuelve la cadena de hilos del ciclo, empezando por `tid`.
+    fn find_wait_cycle(&self, tid: MyThreadId, reason: BlockReason) -> Option<Vec<MyThreadId>> {
+        let mut path = vec![tid];
+        let mut next = reason.waits_for()?;
+        while next != tid {
+            // Un ciclo que no pasa por `tid` no lo causa esta llamada.
+            if path.contains(&next) {
+                return None;
+            }
+            path.push(next);
+            let thr = self.threads.get(&next)?;
+            if thr.state != ThreadState::Blocked {
+                return None;
+            }
+            next = thr.block_reason?.waits_for()?;
+        }
+        Some(path)
+    }
+
+    /// Verifica que `tid` pueda bloquearse por `reason` sin cerrar un ciclo de
+    /// espera. Si lo cerraría devuelve `EDEADLK` (con `deadlock_errors`) o
+    /// aborta el proceso con un reporte del ciclo.
+    fn check_deadlock(&self, tid: MyThreadId, reason: BlockReason) -> c_int {
+        let Some(cycle) = self.find_wait_cycle(tid, reason) else {
+            return 0;
+        };
+        if self.deadlock_errors {
+            return EDEADLK;
+        }
+
+        let lines: Vec<String> = cycle
+            .iter()
+            .map(|&t| {
+                let r = if t == tid { Some(reason) } else { self.threads[&t].block_reason };
+                r.map_or_else(|| self.label(t), |r| r.describe(&self.label(t)))
+            })
+            .collect();
+        self.abort_deadlock("ciclo de espera detectado", &lines);
+    }
+
+    /// `hilo 7`, o `hilo 7 (Ambulance-3)` si tiene nombre, para los reportes.
+    fn label(&self, tid: MyThreadId) -> String {
+        match self.threads.get(&tid).and_then(|t| t.name.as_deref()) {
+            Some(name) => format!("hilo {tid} ({name})"),
+            None => format!("hilo {tid}"),
+        }
+    }
+
+    fn abort_deadlock(&self, summary: &str, lines: &[String]) -> ! {
+        eprintln!("mypthreads: deadlock: {summary}");
+        for line in lines {
+            eprintln!("  {line}");
+        }
+        std::process::abort();
+    }
+
+    /// Actualiza el dueño esperado por un hilo bloqueado en un mutex que
+    /// cambió de manos.
+    fn retarget_mutex_wait(&mut self, waiter: MyThreadId, new_owner: MyThreadId) {
+        if let Some(BlockReason::Mutex { owner, .. }) =
+            self.threads.get_mut(&waiter).and_then(|t| t.block_reason.as_mut())
+        {
+            *owner = new_owner;
+        }
+    }
+
+    /// Despierta al primero de la cola de un mutex `MutexHandoff::Barging`
+    /// libre para que reintente tomarlo. Los que siguen en la cola lo
+    /// esperan a él.
+    fn barge_wake(&mut self, m: &Rc<MutexCore>) {
+        let Some(next) = m.waiters.borrow_mut().pop_front() else {
+            m.woken.set(None);
+            return;
+        };
+        m.woken.set(Some(next));
+        self.revoke_donation(next);
+        for &waiter in m.waiters.borrow().iter() {
+            self.revoke_donation(waiter);
+            self.donate_tickets(waiter, next);
+            self.retarget_mutex_wait(waiter, next);
+        }
+        self.threads.get_mut(&next).unwrap().barge_wakeup = Some(Rc::clone(m));
+        self.unblock(next);
+    }
+
+    /// Marca un hilo como Ready y lo encola en su scheduler.
+    fn unblock(&mut self, tid: MyThreadId) {
+        // Un hilo que ya despertó por otra vía (por ejemplo, un timeout) no
+        // se vuelve a encolar.
+        let blocked = self.threads.get(&tid).is_some_and(|t| t.state == ThreadState::Blocked);
+        if blocked && mem::take(&mut self.threads.get_mut(&tid).unwrap().suspend_pending) {
+            // Lo que esperaba ya ocurrió, pero sigue congelado hasta el resume.
+            self.set_state(tid, ThreadState::Suspended);
+            self.threads.get_mut(&tid).unwrap().block_reason = None;
+        } else if blocked {
+            self.set_state(tid, ThreadState::Ready);
+            self.threads.get_mut(&tid).unwrap().block_reason = None;
+            self.mlfq_on_wakeup(tid);
+            self.enqueue_ready(tid);
+            self.apply_unblock_policy(tid);
+        }
+    }
+
+    /// Con `UnblockPolicy::FrontOfQueue`, adelanta a un hilo RR que acaba de
+    /// despertar, salvo que ya lo haya hecho `MAX_FRONT_WAKEUPS` veces
+    /// seguidas: así los que esperan al fondo no se quedan sin CPU.
+    fn apply_unblock_policy(&mut self, tid: MyThreadId) {
+        let now = self.ticks;
+        let thr = self.threads.get_mut(&tid).unwrap();
+        thr.acct.woken_at = Some(now);
+        if thr.scheduler.kind() != SchedPolicyKind::RoundRobin {
+            return;
+        }
+        if self.unblock_policy == UnblockPolicy::FrontOfQueue
+            && thr.acct.front_wakeups < MAX_FRONT_WAKEUPS
+        {
+            thr.acct.front_wakeups += 1;
+            self.groups[thr.group].queues.rr.move_back_to_front(tid);
+        } else {
+            thr.acct.front_wakeups = 0;
+        }
+    }
+
+    /// Saca el último handler de limpieza del hilo actual.
+    fn pop_cleanup_handler(&mut self) -> Option<(CleanupFn, *mut c_void)> {
+        self.ensure_main_thread();
+        let curr = self.current?;
+        self.threads.get_mut(&curr)?.cleanup_handlers.pop()
+    }
+
+    /// Registra que `tid` tomó el mutex `m`.
+    fn note_mutex_acquired(&mut self, tid: MyThreadId, m: &Rc<MutexCore>) {
+        if let Some(t) = self.threads.get_mut(&tid) {
+            t.held_mutexes.push(Rc::clone(m));
+        }
+    }
+
+    /// Registra que `tid` soltó el mutex `m`.
+    fn note_mutex_released(&mut self, tid: MyThreadId, m: &Rc<MutexCore>) {
+        if let Some(t) = self.threads.get_mut(&tid)
+            && let Some(pos) = t.held_mutexes.iter().rposition(|h| Rc::ptr_eq(h, m))
+        {
+            t.held_mutexes.remove(pos);
+        }
+    }
+
+    /// Olvida un mutex que se destruyó: deja de figurar entre los que tiene
+    /// su dueño y nadie va a reintentar tomarlo.
+    fn forget_mutex(&mut self, m: &Rc<MutexCore>) {
+        for t in self.threads.values_mut() {
+            t.held_mutexes.retain(|h| !Rc::ptr_eq(h, m));
+            if t.barge_wakeup.as_ref().is_some_and(|w| Rc::ptr_eq(w, m)) {
+                t.barge_wakeup = None;
+            }
+        }
+    }
+
+    /// Saca del hilo actual un valor TLS no nulo cuya clave tenga destructor,
+    /// para ejecutarlo fuera del scheduler.
+    fn take_tls_destructor(&mut self) -> Option<(TlsDestructor, *mut c_void)> {
+        let curr = self.current?;
+        let t = self.threads.get_mut(&curr)?;
+        let destructors = &self.tls_destructors;
+        let key = t
+            .tls
+            .iter()
+            .find(|&(&key, value)| !value.is_null() && destructors[key].is_some())
+            .map(|(&key, _)| key)?;
+        let value = t.tls.remove(&key)?;
+        Some((destructors[key]?, value))
+    }
+
+    /// Quita (sin ejecutarlo) el handler más reciente de `tid` registrado
+    /// con `arg`, aunque no sea el último de la pila. Devuelve si lo encontró.
+    fn remove_cleanup_handler(&mut self, tid: MyThreadId, arg: *mut c_void) -> bool {
+        if let Some(t) = self.threads.get_mut(&tid)
+            && let Some(pos) = t.cleanup_handlers.iter().rposition(|&(_, a)| a == arg)
+        {
+            t.cleanup_handlers.remove(pos);
+            return true;
+        }
+        false
+    }
+
+    /// Finaliza el hilo actual y elige el siguiente. Los handlers de
+    /// limpieza ya deben haberse ejecutado (ver `my_thread_end`).
+    fn finish_current(&mut self, retval: *mut c_void) -> ContextSwitch {
+        self.ensure_main_thread();
+        self.check_stack();
+
+        let curr_id = self.current.expect("no hay hilo actual en finish_current");
+
+        self.check_deadline(curr_id);
+        self.mark_finished(curr_id, retval);
+
+        // Elegir siguiente
+        if let Some(next_id) = self.pick_next() {
+            return self.switch_to(curr_id, next_id);
+        }
+
+        // No quedan hilos listos. Se prefiere volver a main, para que el
+        // programa siga después de su último join/yield, en vez de terminar
+        // el proceso y perder lo que main tenía pendiente.
+        match self.threads.get(&0).map(|t| t.state) {
+            Some(ThreadState::Ready | ThreadState::Running) if curr_id != 0 => {
+                self.remove_from_ready_lists(0);
+                self.dispatch(0);
+                self.switch_to(curr_id, 0)
+            }
+            // Main espera algo que ya nadie puede darle.
+            Some(ThreadState::Blocked | ThreadState::Suspended) => self.abort_all_blocked(),
+            // Main ya terminó: si algún hilo con join pendiente quedó
+            // esperando, nunca va a terminar.
+            _ if self.has_stuck_joinable_threads() => self.abort_all_blocked(),
+            // Main terminó con `my_thread_end` y esperaba a que todo lo demás
+            // terminara: se vuelve a su contexto (sin marcarlo Running) para
+            // que salga del proceso desde ahí.
+            Some(ThreadState::Finished) if curr_id != 0 => self.switch_to(curr_id, 0),
+            _ => std::process::exit(main_exit_status(self.threads.get(&0).map_or(ptr::null_mut(), |t| t.result))),
+        }
+    }
+
+    /// Deja a `tid` como Finished con resultado `retval`: despierta a quien
+    /// le hace join, envenena los `my_once` que inicializaba y lo saca de las
+    /// colas de Ready. Si es detached queda como zombie.
+    fn mark_finished(&mut self, tid: MyThreadId, retval: *mut c_void) {
+        self.set_state(tid, ThreadState::Finished);
+        let ticks = self.ticks;
+        let (joined_by, detached) = {
+            let thr = self.threads.get_mut(&tid).unwrap();
+            thr.result = retval;
+            thr.acct.finished_at = Some(ticks);
+            (thr.joined_by, thr.detached)
+        };
+
+        // Nadie hará join de un hilo detached: su TCB se libera en cuanto
+        // otro hilo tome la CPU.
+        if detached && tid != 0 {
+            self.zombies.push(tid);
+        }
+
+        // Despertar al que hizo join, si existe
+        if let Some(jid) = joined_by {
+            self.unblock(jid);
+        }
+
+        // Si terminó antes de reintentar un mutex que le dejaron libre, le
+        // toca al siguiente de la cola.
+        if let Some(m) = self.threads.get_mut(&tid).unwrap().barge_wakeup.take()
+            && !m.locked.get()
+            && m.woken.get() == Some(tid)
+        {
+            self.barge_wake(&m);
+        }
+
+        // Terminar dentro de un inicializador de `my_once` lo deja envenenado.
+        let onces = mem::take(&mut self.threads.get_mut(&tid).unwrap().running_onces);
+        for once in onces {
+            let once = unsafe { &mut *once };
+            once.state = OnceState::Poisoned;
+            for waiter in mem::take(&mut once.waiters) {
+                self.unblock(waiter);
+            }
+        }
+
+        // No lo encolamos de nuevo (ya terminó)
+        self.remove_from_ready_lists(tid);
+    }
+
+    /// Termina a la fuerza a `tid`, que no es el hilo actual. Devuelve los
+    /// mutexes que tenía tomados, para soltarlos fuera del scheduler.
+    fn kill(&mut self, tid: MyThreadId) -> Result<Vec<Rc<MutexCore>>, c_int> {
+        self.ensure_main_thread();
+        if tid == 0 || self.current == Some(tid) || !self.is_user_thread(tid) {
+            return Err(EINVAL);
+        }
+        let thr = self.threads.get_mut(&tid).unwrap();
+        if thr.state == ThreadState::Finished {
+            return Err(EINVAL);
+        }
+
+        if thr.state == ThreadState::Blocked
+            && let Some(reason) = thr.block_reason
+        {
+            self.forget_waiter(tid, reason);
+        }
+        let held = mem::take(&mut self.threads.get_mut(&tid).unwrap().held_mutexes);
+        self.mark_finished(tid, MY_THREAD_KILLED);
+        Ok(held)
+    }
+
+    /// Saca a `tid`, que va a morir bloqueado por `reason`, de la cola donde
+    /// espera, para que ningún unlock, post o send se lo entregue a él. El
+    /// objeto sigue vivo: el hilo lo tiene prestado en la llamada que lo
+    /// bloqueó.
+    fn forget_waiter(&mut self, tid: MyThreadId, reason: BlockReason) {
+        // Join y join_any lo anotan en los hilos que espera.
+        for t in self.threads.values_mut() {
+            if t.joined_by == Some(tid) {
+                t.joined_by = None;
+            }
+        }
+        match reason {
+            BlockReason::Mutex { mutex, .. } => {
+                unsafe { &*mutex }.waiters.borrow_mut().retain(|&w| w != tid);
+                self.revoke_donation(tid);
+            }
+            BlockReason::Once { once, .. } => {
+                unsafe { &mut *(once as *mut MyOnce) }.waiters.retain(|&w| w != tid);
+            }
+            BlockReason::Cond { cond, .. } => {
+                unsafe { &mut *(cond as *mut MyCondVar) }.waiters.retain(|&w| w != tid);
+            }
+            BlockReason::Channel { chan, forget, .. } => forget(chan, tid),
+            BlockReason::RwLock { lock, .. } => {
+                let l = unsafe { &mut *(lock as *mut MyRwLock) };
+                l.read_waiters.retain(|&w| w != tid);
+                l.write_waiters.retain(|&w| w != tid);
+                // Si era el único escritor en espera, los lectores que se
+                // encolaron detrás de él ya pueden entrar.
+                if l.writer.is_none() && l.write_waiters.is_empty() && l.readers > 0 {
+                    for reader in mem::take(&mut l.read_waiters) {
+                        l.readers += 1;
+                        self.unblock(reader);
+                    }
+                }
+            }
+            BlockReason::Semaphore { sem } => {
+                unsafe { &*sem }.waiters.borrow_mut().retain(|&w| w != tid);
+            }
+            BlockReason::Barrier { barrier } => {
+                unsafe { &mut *(barrier as *mut MyBarrier) }.waiters.retain(|&w| w != tid);
+            }
+            BlockReason::WaitGroup { group } => {
+                unsafe { &*group }.waiters.borrow_mut().retain(|&w| w != tid);
+            }
+            BlockReason::Join { .. }
+            | BlockReason::JoinAny
+            | BlockReason::Sleep { .. }
+            | BlockReason::Other => {}
+        }
+    }
+
+    /// `true` si queda algún hilo no detached que nunca va a terminar porque
+    /// espera algo (o está suspendido) sin hilos listos que se lo den.
+    fn has_stuck_joinable_threads(&self) -> bool {
+        self.threads.values().any(|t| {
+            matches!(t.state, ThreadState::Blocked | ThreadState::Suspended)
+                && !t.detached
+                && self.idle_tid != Some(t.id)
+        })
+    }
+
+    /// Suspende `tid`. Devuelve el cambio de contexto a realizar si se
+    /// suspendió el hilo actual.
+    fn suspend(&mut self, tid: MyThreadId) -> Result<Option<ContextSwitch>, c_int> {
+        self.ensure_main_thread();
+        let state = match self.threads.get(&tid) {
+            Some(t) if self.idle_tid != Some(tid) => t.state,
+            _ => return Err(EINVAL),
+        };
+        match state {
+            ThreadState::Finished => Err(EINVAL),
+            ThreadState::Blocked => {
+                self.threads.get_mut(&tid).unwrap().suspend_pending = true;
+                Ok(None)
+            }
+            ThreadState::Running => {
+                self.check_stack();
+                Ok(Some(self.deschedule_current(tid, ThreadState::Suspended)))
+            }
+            ThreadState::New | ThreadState::Ready => {
+                self.remove_from_ready_lists(tid);
+                self.set_state(tid, ThreadState::Suspended);
+                Ok(None)
+            }
+            ThreadState::Suspended => Ok(None),
+        }
+    }
+
+    /// Deshace `suspend`: vuelve a encolar a `tid`, o cancela la suspensión
+    /// pendiente si sigue bloqueado.
+    fn resume(&mut self, tid: MyThreadId) -> c_int {
+        let state = match self.threads.get_mut(&tid) {
+            Some(t) if self.idle_tid != Some(tid) => {
+                t.suspend_pending = false;
+                t.state
+            }
+            _ => return EINVAL,
+        };
+        match state {
+            ThreadState::Finished => EINVAL,
+            ThreadState::Suspended => {
+                self.set_state(tid, ThreadState::Ready);
+                self.enqueue_ready(tid);
+                0
+            }
+            _ => 0,
+        }
+    }
+
+    /// Libera el TCB (y con él la pila) de un hilo terminado.
+    ///
+    /// Nunca libera el hilo actual ni el main: el primero seguiría ejecutando
+    /// sobre la pila que se está liberando.
+    fn reclaim(&mut self, tid: MyThreadId) {
+        if tid == 0 || self.current == Some(tid) {
+            return;
+        }
+        if self.threads.get(&tid).map(|t| t.state) == Some(ThreadState::Finished) {
+            let thr = self.threads.remove(&tid).unwrap();
+            // Nadie va a recibir el resultado de un detached: se lo pasamos
+            // al finalizador, si lo tiene, para que libere lo que haga falta.
+            if let (true, Some(finalizer)) = (thr.detached, thr.finalizer) {
+                self.pending_finalizers.push((finalizer, thr.result));
+            }
+        }
+    }
+
+    /// Libera los hilos detached terminados. Se llama cada vez que un hilo
+    /// retoma la CPU, momento en que ya no se ejecuta sobre la pila del zombie.
+    fn reap_zombies(&mut self) {
+        for tid in mem::take(&mut self.zombies) {
+            if self.current == Some(tid) {
+                self.zombies.push(tid);
+            } else {
+                self.reclaim(tid);
+            }
+        }
+    }
+
+    /// Foto de las estadísticas globales.
+    fn sched_stats(&self) -> SchedStats {
+        SchedStats {
+            ticks: self.ticks,
+            dispatches: self.dispatches_by_policy.values().sum(),
+            context_switches: self.context_switches,
+            dispatches_by_policy: self.dispatches_by_policy.clone(),
+            dispatches_by_group: self.groups.iter().map(|g| g.dispatches).enumerate().collect(),
+            live_threads: self.live_threads(),
+        }
+    }
+
+    /// Foto de las estadísticas de un hilo. El tiempo en el estado actual
+    /// se incluye aunque el hilo todavía no haya salido de él.
+    fn thread_stats(&self, tid: MyThreadId) -> Option<ThreadStats> {
+        if !self.is_user_thread(tid) {
+            return None;
+        }
+        let thr = self.threads.get(&tid)?;
+        let mut acct = thr.acct;
+        let elapsed = self.ticks - acct.state_since;
+        match thr.state {
+            ThreadState::Ready => acct.ready_ticks += elapsed,
+            ThreadState::Blocked => acct.blocked_ticks += elapsed,
+            _ => {}
+        }
+        Some(ThreadStats {
+            run_count: acct.run_count,
+            slices: acct.slices,
+            ready_ticks: acct.ready_ticks,
+            blocked_ticks: acct.blocked_ticks,
+            policy: thr.scheduler,
+            mlfq_level: match thr.scheduler {
+                SchedPolicy::Mlfq => Some(thr.mlfq.level),
+                _ => None,
+            },
+            switch_count: acct.switches,
+            state: thr.state.into(),
+            wakeups: acct.wakeups,
+            avg_wakeup_latency: if acct.wakeups == 0 {
+                0.0
+            } else {
+                acct.wakeup_latency_ticks as f64 / acct.wakeups as f64
+            },
+            created_at: acct.created_at,
+            first_run_at: acct.first_run_at,
+            finished_at: acct.finished_at,
+        })
+    }
+
+    /// Intenta hacer join inmediato; si el hilo ya terminó, retorna Some(result).
+    fn try_join_immediate(&self, target: MyThreadId) -> Option<*mut c_void> {
+        let t = self.threads.get(&target)?;
+        // Un detached terminado es un zombie a punto de liberarse: su
+        // resultado ya no le pertenece a nadie.
+        if t.state == ThreadState::Finished && !t.detached {
+            Some(t.result)
+        } else {
+            None
+        }
+    }
+
+    /// Cambia la política de scheduling de un hilo.
+    fn change_scheduler(&mut self, tid: MyThreadId, policy: SchedPolicy) -> c_int {
+        match self.threads.get(&tid) {
+            Some(t) if t.state != ThreadState::Finished && self.idle_tid != Some(tid) => {}
+            _ => return EINVAL,
+        }
+        if !self.class_exists(policy) {
+            return EINVAL;
+        }
+
+        self.remove_from_ready_lists(tid);
+
+        let now = self.ticks;
+        {
+            let thr = self.threads.get_mut(&tid).unwrap();
+            thr.scheduler = policy;
+            thr.tickets = 0;
+            thr.bonus_tickets = 0;
+            thr.rt_params = None;
+            thr.mlfq = MlfqParams::default();
+            if let Some(acct) = thr.rt_acct.as_mut() {
+                acct.ready_since = None;
+            }
+
+            match policy {
+                SchedPolicy::RoundRobin => {}
+                SchedPolicy::Lottery { tickets } => {
+                    thr.tickets = if tickets == 0 { 1 } else { tickets };
+                }
+                SchedPolicy::RealTime { deadline } => {
+                    thr.rt_params = Some(RealTimeParams::relative(deadline, now));
+                }
+                SchedPolicy::Mlfq | SchedPolicy::Fifo { .. } | SchedPolicy::Custom { .. } => {}
+            }
+        }
+
+        // Si el hilo está listo, re-encolar según nueva política.
+        if self.threads.get(&tid).unwrap().state == ThreadState::Ready {
+            self.enqueue_ready(tid);
+        }
+
+        0
+    }
+
+    /// Actualiza el deadline de un hilo en el scheduler de Tiempo Real.
+    ///
+    /// El `deadline` se interpreta como un tiempo absoluto en las mismas
+    /// unidades que use la simulación (por ejemplo, ticks). Solo es válido
+    /// para hilos cuya política actual sea `SchedPolicy::RealTime`, en caso
+    /// contrario se devuelve `EINVAL`.
+    fn set_realtime_deadline(&mut self, tid: MyThreadId, deadline: u64) -> c_int {
+        // Verificamos que el hilo exista.
+        let thr = match self.threads.get_mut(&tid) {
+            None => return EINVAL,
+            Some(t) => t,
+        };
+
+        // Solo tiene sentido actualizar el deadline de hilos Tiempo Real.
+        match thr.scheduler {
+            SchedPolicy::RealTime { .. } => {
+                thr.rt_params = Some(RealTimeParams {
+                    deadline,
+                    abs_deadline: deadline,
+                    missed: false,
+                });
+            }
+            _ => return EINVAL,
+        }
+
+        // Si estaba listo, lo reinsertamos en la lista Tiempo Real
+        // para que el orden por deadline se actualice.
+        if thr.state == ThreadState::Ready {
+            self.remove_from_ready_lists(tid);
+            self.enqueue_ready(tid);
+        }
+
+        0
+    }
+
+    /// Un hilo Lottery que se bloquea esperando un mutex le presta sus tickets
+    /// al dueño, para que este lo libere antes.
+    fn donate_tickets(&mut self, waiter: MyThreadId, owner: MyThreadId) {
+        let amount = match self.threads.get(&waiter) {
+            Some(t) if matches!(t.scheduler, SchedPolicy::Lottery { .. }) => {
+                t.tickets.saturating_add(t.donated_tickets)
+            }
+            _ => return,
+        };
+        let Some(owner_thr) = self.threads.get_mut(&owner) else {
+            return;
+        };
+        owner_thr.donated_tickets = owner_thr.donated_tickets.saturating_add(amount);
+        self.groups[owner_thr.group].queues.lottery.update(owner, owner_thr.effective_tickets());
+        self.threads.get_mut(&waiter).unwrap().donating_to = Some((owner, amount));
+    }
+
+    /// Deshace la donación de un hilo (porque obtuvo el mutex o dejó de esperarlo).
+    /// Devuelve la cantidad de tickets que estaba donando.
+    fn revoke_donation(&mut self, waiter: MyThreadId) -> u32 {
+        let Some((owner, amount)) = self.threads.get_mut(&waiter).and_then(|t| t.donating_to.take())
+        else {
+            return 0;
+        };
+        if let Some(owner_thr) = self.threads.get_mut(&owner) {
+            owner_thr.donated_tickets = owner_thr.donated_tickets.saturating_sub(amount);
+            self.groups[owner_thr.group].queues.lottery.update(owner, owner_thr.effective_tickets());
+        }
+        amount
+    }
+
+    /// Cambia los tickets de un hilo Lottery sin sacarlo de su lugar en la cola.
+    fn set_tickets(&mut self, tid: MyThreadId, tickets: u32) -> c_int {
+        let Some(thr) = self.threads.get_mut(&tid) else {
+            return EINVAL;
+        };
+        let SchedPolicy::Lottery { .. } = thr.scheduler else {
+            return EINVAL;
+        };
+
+        let tickets = if tickets == 0 { 1 } else { tickets };
+        thr.tickets = tickets;
+        thr.scheduler = SchedPolicy::Lottery { tickets };
+        self.groups[thr.group].queues.lottery.update(tid, thr.effective_tickets());
+        0
+    }
+
+    /// Pasa tickets base de un hilo Lottery a otro. `from` conserva al menos
+    /// uno, así que solo se mueve lo que realmente se le quitó.
+    fn transfer_tickets(&mut self, from: MyThreadId, to: MyThreadId, amount: u32) -> c_int {
+        let is_lottery = |tid| {
+            self.threads
+                .get(&tid)
+                .is_some_and(|t| matches!(t.scheduler, SchedPolicy::Lottery { .. }))
+        };
+        if !is_lottery(from) || !is_lottery(to) {
+            return EINVAL;
+        }
+        if from == to {
+            return 0;
+        }
+
+        let moved = amount.min(self.threads[&from].tickets - 1);
+        let from_tickets = self.threads[&from].tickets - moved;
+        let to_tickets = self.threads[&to].tickets.saturating_add(moved);
+        self.set_tickets(from, from_tickets);
+        self.set_tickets(to, to_tickets);
+        0
+    }
+
+    /// Pasa un hilo a otro grupo; si estaba listo, cambia de colas.
+    fn set_group(&mut self, tid: MyThreadId, group: MySchedGroupId) -> c_int {
+        if group >= self.groups.len() || !self.is_user_thread(tid) {
+            return EINVAL;
+        }
+        let thr = self.threads.get_mut(&tid).unwrap();
+        if thr.state == ThreadState::Finished {
+            return EINVAL;
+        }
+        if thr.group == group {
+            return 0;
+        }
+        let ready = thr.state == ThreadState::Ready;
+        thr.group = group;
+        if ready {
+            self.remove_from_ready_lists(tid);
+            self.enqueue_ready(tid);
+        }
+        0
+    }
+
+    /// Política actual de un hilo con sus parámetros vigentes.
+    fn policy_of(&self, tid: MyThreadId) -> Result<SchedPolicy, ThreadError> {
+        let thr = match self.threads.get(&tid) {
+            Some(t) if self.idle_tid != Some(tid) => t,
+            _ => return Err(ThreadError::NoSuchThread { tid }),
+        };
+        Ok(match thr.scheduler {
+            // Tickets base: los de envejecimiento o donación son transitorios.
+            SchedPolicy::Lottery { .. } => SchedPolicy::Lottery { tickets: thr.tickets },
+            // `my_thread_set_realtime_deadline` solo actualiza `rt_params`.
+            SchedPolicy::RealTime { deadline } => SchedPolicy::RealTime {
+                deadline: thr.rt_params.map_or(deadline, |rt| rt.deadline),
+            },
+            policy => policy,
+        })
+    }
+
+    /// Foto de todos los hilos de usuario (y main), ordenada por id.
+    fn thread_list(&self) -> Vec<MyThreadInfo> {
+        let mut list: Vec<MyThreadInfo> = self
+            .threads
+            .values()
+            .filter(|t| self.is_user_thread(t.id))
+            .map(|t| MyThreadInfo {
+                tid: t.id,
+                name: t.name.clone(),
+                state: t.state.into(),
+                policy: self.policy_of(t.id).unwrap_or(t.scheduler),
+                detached: t.detached,
+                block_reason: match t.state {
+                    ThreadState::Blocked => t.block_reason.map(|r| r.public()),
+                    _ => None,
+                },
+                joined_by: t.joined_by,
+            })
+            .collect();
+        list.sort_by_key(|info| info.tid);
+        list
+    }
+
+    /// Arma el reporte de locks a partir de los mutexes que tiene cada hilo.
+    fn lock_report(&self) -> MyLockReport {
+        let mut held: Vec<_> = self
+            .threads
+            .values()
+            .filter(|t| !t.held_mutexes.is_empty())
+            .map(|t| (t.id, &t.held_mutexes))
+            .collect();
+        held.sort_by_key(|&(tid, _)| tid);
+
+        let mut mutexes: Vec<MyMutexInfo> = held
+            .iter()
+            .flat_map(|&(tid, ms)| ms.iter().map(move |m| (tid, m)))
+            .map(|(owner, m)| MyMutexInfo {
+                id: m.id,
+                owner,
+                waiters: m.waiters.borrow().iter().copied().collect(),
+            })
+            .collect();
+        mutexes.sort_by_key(|m| m.id);
+
+        MyLockReport {
+            mutexes,
+            held: held
+                .into_iter()
+                .map(|(tid, ms)| (tid, ms.iter().map(|m| m.id).collect()))
+                .collect(),
+        }
+    }
+
+    /// Cantidad de deadlines incumplidos por un hilo.
+    fn deadline_misses(&self, tid: MyThreadId) -> Option<u64> {
+        self.threads.get(&tid).map(|t| t.deadline_misses)
+    }
+
+    /// Métricas de Tiempo Real de un hilo que alguna vez fue RealTime.
+    fn rt_stats(&self, tid: MyThreadId) -> Option<RtStats> {
+        if !self.is_user_thread(tid) {
+            return None;
+        }
+        let acct = self.threads[&tid].rt_acct?;
+        Some(RtStats {
+            activations: acct.activations,
+            deadline_misses: acct.misses,
+            worst_lateness: acct.worst_lateness,
+            avg_response_ticks: if acct.responses == 0 {
+                0.0
+            } else {
+                acct.response_ticks as f64 / acct.responses as f64
+            },
+        })
+    }
+
+    /// Obtiene el deadline actual de un hilo de Tiempo Real, si lo tiene.
+    fn get_realtime_deadline(&self, tid: MyThreadId) -> Option<u64> {
+        let thr = self.threads.get(&tid)?;
+        thr.rt_params.as_ref().map(|p| p.deadline)
+    }
+}
+
+
+/// Cambio de contexto preparado por el scheduler (ver `switch_context`).
+struct ContextSwitch {
+    from: *mut ucontext_t,
+    to: *mut ucontext_t,
+}
+
+thread_local! {
+    /// Scheduler global en espacio de usuario. Hay uno por hilo del sistema
+    /// operativo, de modo que cada hilo de kernel (por ejemplo, cada test)
+    /// tiene su propio conjunto de hilos de usuario.
+    ///
+    /// Invariante: solo se accede con `with_scheduler`, y el préstamo nunca
+    /// sigue vivo durante un `swapcontext` ni mientras corre código del
+    /// usuario (rutinas de hilos, handlers, inicializadores de `my_once`).
+    /// Así, aunque los hilos de usuario compartan el hilo del sistema, nunca
+    /// hay dos `&mut Scheduler` a la vez; si el invariante se rompe, el
+    /// `RefCell` entra en pánico en vez de producir UB.
+    static SCHEDULER: RefCell<Scheduler> = RefCell::new(Scheduler::new());
+}
+
+/// Presta el scheduler a `f` durante una sola operación.
+fn with_scheduler<R>(f: impl FnOnce(&mut Scheduler) -> R) -> R {
+    SCHEDULER.with(|cell| f(&mut cell.borrow_mut()))
+}
+
+/// Id del hilo actual (inicializando main si hace falta).
+fn current_thread(caller: &str) -> MyThreadId {
+    with_scheduler(|s| {
+        s.ensure_main_thread();
+        s.current_thread_id()
+    })
+    .unwrap_or_else(|| panic!("{caller} sin hilo actual"))
+}
+
+/// Llama al handler de deadlines con los incumplimientos pendientes.
+fn fire_deadline_misses() {
+    let (handler, misses) =
+        with_scheduler(|s| (s.deadline_miss_handler, mem::take(&mut s.pending_misses)));
+    if let Some(handler) = handler {
+        for (tid, lateness) in misses {
+            handler(tid, lateness);
+        }
+    }
+}
+
+/// Llama a los finalizadores de los hilos detached que se liberaron.
+fn fire_finalizers() {
+    for (finalizer, result) in with_scheduler(|s| mem::take(&mut s.pending_finalizers)) {
+        finalizer(result);
+    }
+}
+
+/// Ejecuta un cambio de contexto preparado por el scheduler. Cuando el hilo
+/// vuelve a correr, libera los zombies que hayan quedado.
+fn switch_context(switch: ContextSwitch) {
+    fire_deadline_misses();
+    unsafe {
+        swapcontext(switch.from, switch.to);
+    }
+    with_scheduler(|s| s.reap_zombies());
+    fire_finalizers();
+}
+
+/// Bloquea el hilo actual por `reason` hasta que otro lo despierte.
+fn block_and_switch(reason: BlockReason) {
+    let switch = with_scheduler(|s| s.block_current(reason));
+    switch_context(switch);
+}
+
+/// Resultado de preparar una operación que puede tener que bloquearse.
+enum Wait<T> {
+    /// Terminó sin bloquearse.
+    Ready(T),
+    /// Hay que bloquearse por esta razón.
+    Block(BlockReason),
+}
+
+/// Trampolín: es la función que todos los hilos nuevos ejecutan primero.
+extern "C" fn thread_trampoline() {
+    // Obtenemos función y argumento del TCB
+    let (func, arg) = with_scheduler(|s| {
+        s.reap_zombies();
+        let tid = s.current_thread_id().expect("no current thread in trampoline");
+        let t = s.get_thread(tid).expect("thread not found in trampoline");
+        (t.start_routine.expect("no start_routine"), t.arg)
+    });
+    fire_finalizers();
+
+    let result = func(arg);
+    my_thread_end(result);
+}
+
+/// Rutina de los hilos creados con `my_thread_spawn`. La closure se saca del
+/// TCB antes de llamarla, así que corre una sola vez y, si el hilo nunca
+/// llega a ejecutarse, se libera junto con el TCB.
+extern "C" fn closure_start(_arg: *mut c_void) -> *mut c_void {
+    let f = with_scheduler(|s| {
+        let tid = s.current_thread_id().expect("no current thread in closure_start");
+        s.get_thread_mut(tid).and_then(|t| t.closure.take())
+    })
+    .expect("closure_start: el hilo no tiene closure");
+    // Un pánico no puede cruzar el `extern "C"`: se detiene acá, después de
+    // que el unwinding soltó los guards del hilo, y el hilo termina. Quien
+    // haga join lo relanza.
+    if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(f)) {
+        with_scheduler(|s| {
+            let tid = s.current_thread_id().expect("no current thread in closure_start");
+            s.get_thread_mut(tid).unwrap().panic = Some(payload);
+        });
+    }
+    ptr::null_mut()
+}
+
+// ============ API pública estilo mypthreads ============ //
+
+/// Crea un hilo de usuario con la política indicada.
+/// Devuelve el id del hilo (MyThreadId).
+///
+/// Se mantiene por compatibilidad: acepta los mismos parámetros de política
+/// que antes (`0` tickets se interpreta como `1`). Para validar la política y
+/// recibir los errores, usar `my_thread_try_create`.
+///
+/// # Panics
+/// Si no se puede reservar el hilo (límite de hilos, memoria o contexto).
+pub fn my_thread_create(
+    start_routine: ThreadFunc,
+    arg: *mut c_void,
+    policy: SchedPolicy,
+) -> MyThreadId {
+    with_scheduler(|s| s.create_thread(start_routine, arg, policy))
+        .unwrap_or_else(|e| panic!("my_thread_create: {e}"))
+}
+
+/// Crea un hilo de usuario que ejecuta la closure `f`.
+///
+/// Es la versión segura de `my_thread_create`: no hace falta una función
+/// `extern "C"` ni pasar argumentos como punteros crudos. El hilo devuelve
+/// un resultado nulo a quien haga join.
+///
+/// No se exige `Send`: todos los hilos de usuario corren sobre el hilo del
+/// sistema que los creó, así que la closure puede capturar `Rc` o `Cell`.
+///
+/// # Panics
+/// En los mismos casos que `my_thread_create`.
+pub fn my_thread_spawn<F>(f: F, policy: SchedPolicy) -> MyThreadId
+where
+    F: FnOnce() + 'static,
+{
+    with_scheduler(|s| {
+        let tid = s.create_thread(closure_start, ptr::null_mut(), policy)?;
+        if let Some(t) = s.get_thread_mut(tid) {
+            t.closure = Some(Box::new(f));
+        }
+        Ok(tid)
+    })
+    .unwrap_or_else(|e: ThreadError| panic!("my_thread_spawn: {e}"))
+}
+
+/// Crea un hilo de usuario que ejecuta `f` y deja su valor de retorno para
+/// `MyJoinHandle::join`.
+///
+/// # Panics
+/// En los mismos casos que `my_thread_create`.
+pub fn my_thread_spawn_with_result<F, T>(f: F, policy: SchedPolicy) -> MyJoinHandle<T>
+where
+    F: FnOnce() -> T + 'static,
+    T: 'static,
+{
+    let tid = my_thread_spawn(
+        move || {
+            let value: Box<dyn Any> = Box::new(f());
+            let curr = current_thread("spawn_with_result");
+            with_scheduler(|s| s.get_thread_mut(curr).unwrap().output = Some(value));
+        },
+        policy,
+    );
+    MyJoinHandle { tid: Some(tid), _value: PhantomData }
+}
+
+/// Hilo creado con `my_thread_spawn_with_result`. Si se descarta sin hacer
+/// `join`, el hilo queda detached.
+pub struct MyJoinHandle<T> {
+    tid: Option<MyThreadId>,
+    _value: PhantomData<T>,
+}
+
+impl<T: 'static> MyJoinHandle<T> {
+    pub fn tid(&self) -> MyThreadId {
+        self.tid.expect("handle ya consumido")
+    }
+
+    /// Espera a que el hilo termine y devuelve el valor de la closure.
+    ///
+    /// # Panics
+    /// Si el hilo terminó sin devolverlo (por ejemplo con `my_thread_end`) o
+    /// si el join no pudo esperar (join a uno mismo o `EDEADLK`). Si la
+    /// closure entró en pánico, se relanza ese mismo pánico.
+    pub fn join(mut self) -> T {
+        let tid = self.tid.take().expect("handle ya consumido");
+        let output = join_thread(tid).output;
+        let value = output.unwrap_or_else(|| panic!("join: el hilo {tid} no devolvió un valor"));
+        *value.downcast::<T>().expect("join: tipo de resultado inesperado")
+    }
+}
+
+impl<T> Drop for MyJoinHandle<T> {
+    fn drop(&mut self) {
+        if let Some(tid) = self.tid {
+            my_thread_detach(tid);
+        }
+    }
+}
+
+/// Crea un hilo de usuario validando la política.
+///
+/// # Errores
+/// * `ThreadError::InvalidPolicy` con `Lottery { tickets: 0 }` o
+///   `RealTime { deadline: 0 }`.
+/// * `ThreadError::ResourceLimit` si ya hay tantos hilos vivos como permite
+///   `my_sched_set_max_threads`.
+/// * `ThreadError::StackAllocation` o `ThreadError::Context` si no se pudo
+///   preparar la pila o el contexto.
+///
+/// En caso de error no queda ningún TCB registrado.
+pub fn my_thread_try_create(
+    start_routine: ThreadFunc,
+    arg: *mut c_void,
+    policy: SchedPolicy,
+) -> Result<MyThreadId, ThreadError> {
+    match policy {
+        SchedPolicy::Lottery { tickets: 0 } | SchedPolicy::RealTime { deadline: 0 } => {
+            Err(ThreadError::InvalidPolicy(policy))
+        }
+        _ => with_scheduler(|s| s.create_thread(start_routine, arg, policy)),
+    }
+}
+
+/// Finaliza el hilo actual, devolviendo `retval` a quien haga join.
+/// No debería regresar.
+///
+/// Si quien termina es main, los demás hilos siguen corriendo: el proceso
+/// sale recién cuando no queda ninguno listo, con `retval` como código de
+/// salida (nulo es 0). Si para entonces algún hilo no detached sigue
+/// bloqueado, aborta con el reporte de deadlock.
+pub fn my_thread_end(retval: *mut c_void) -> ! {
+    // Los handlers de limpieza corren todavía sobre la pila del hilo que
+    // termina. Se sacan de a uno porque pueden volver a entrar al scheduler.
+    while let Some((routine, arg)) = with_scheduler(|s| s.pop_cleanup_handler()) {
+        routine(arg);
+    }
+    // Después, los destructores de los valores TLS. El valor sale del hilo
+    // antes de llamar al destructor, que puede guardar otro.
+    while let Some((destructor, value)) = with_scheduler(|s| s.take_tls_destructor()) {
+        destructor(value);
+    }
+
+    let tid = current_thread("my_thread_end");
+    // Un hilo cancelado no vuelve a su código: los mutexes que no soltó un
+    // handler de limpieza se pasan al siguiente que los espera.
+    let orphaned = with_scheduler(|s| {
+        let t = s.get_thread_mut(tid).unwrap();
+        if t.cancelled { mem::take(&mut t.held_mutexes) } else { Vec::new() }
+    });
+    for m in orphaned.into_iter().rev() {
+        mutex_unlock_as(&m, tid);
+    }
+
+    let switch = with_scheduler(|s| s.finish_current(retval));
+    fire_deadline_misses();
+    unsafe {
+        swapcontext(switch.from, switch.to);
+    }
+
+    // Main vuelve acá solo cuando ya no queda nada por correr: el proceso
+    // termina como si main hubiera retornado `retval`.
+    if tid == 0 {
+        std::process::exit(main_exit_status(retval));
+    }
+
+    // Alguien reanudó un hilo terminado: no hay nada sensato que ejecutar.
+    eprintln!("mypthreads: se reanudó el hilo {tid}, que ya había terminado");
+    std::process::abort();
+}
+
+/// Finaliza el hilo actual dejando `value` como resultado, para recuperarlo
+/// con `my_thread_join_boxed::<T>`. El tipo queda registrado en el TCB para
+/// que el join lo verifique.
+///
+/// Si nadie hace join (por ejemplo, un hilo detached sin finalizador), el
+/// `Box` no se libera.
+pub fn my_thread_end_boxed<T: 'static>(value: Box<T>) -> ! {
+    let tid = current_thread("my_thread_end_boxed");
+    with_scheduler(|s| s.get_thread_mut(tid).unwrap().result_type = Some(TypeId::of::<T>()));
+    my_thread_end(Box::into_raw(value) as *mut c_void)
+}
+
+/// Código de salida del proceso cuando main termina con `my_thread_end`: el
+/// valor de `retval` tomado como entero, así que `ptr::null_mut()` es 0.
+fn main_exit_status(retval: *mut c_void) -> i32 {
+    retval as isize as i32
+}
+
+/// Cuerpo del hilo idle: cede la CPU hasta que alguien despierte.
+extern "C" fn idle_loop(_arg: *mut c_void) -> *mut c_void {
+    loop {
+        my_thread_yield();
+    }
+}
+
+/// El hilo actual cede la CPU.
+///
+/// Es un punto de cancelación: si el hilo actual fue cancelado, termina
+/// (ver `my_testcancel`).
+pub fn my_thread_yield() {
+    my_testcancel();
+    let switch = with_scheduler(|s| s.yield_current());
+    match switch {
+        Some(switch) => switch_context(switch),
+        None => fire_deadline_misses(),
+    }
+}
+
+/// El hilo actual le cede la CPU a `target` en particular, en vez de al que
+/// elegiría el scheduler. Quien cede vuelve a su cola de Ready como con
+/// `my_thread_yield`, y el traspaso cuenta como dispatch y como cambio de
+/// contexto de `target` en sus estadísticas.
+///
+/// Si `target` no está listo para correr (bloqueado, suspendido, terminado o
+/// ya en la CPU) devuelve `EAGAIN`, y `EINVAL` si no existe, es el idle o es
+/// el mismo hilo actual; en ambos casos el que llama sigue corriendo. Es un
+/// punto de cancelación, como `my_thread_yield`.
+pub fn my_thread_yield_to(target: MyThreadId) -> c_int {
+    my_testcancel();
+    match with_scheduler(|s| s.yield_to(target)) {
+        Ok(switch) => {
+            switch_context(switch);
+            0
+        }
+        Err(err) => err,
+    }
+}
+
+/// Pide cancelar el hilo `tid`. La cancelación es diferida: el hilo termina
+/// con resultado nulo la próxima vez que pase por `my_testcancel` (lo hace
+/// cada `my_thread_yield`), ejecutando antes sus handlers de limpieza.
+///
+/// Los mutexes que todavía tenga tomados al terminar se liberan y pasan al
+/// siguiente hilo que los espere, para que cancelar un vehículo no deje
+/// bloqueada su celda. Devuelve `EINVAL` si el hilo no existe, ya terminó,
+/// es el idle o es el main.
+pub fn my_thread_cancel(tid: MyThreadId) -> c_int {
+    with_scheduler(|s| {
+        if tid == 0 || !s.is_user_thread(tid) {
+            return EINVAL;
+        }
+        match s.get_thread_mut(tid) {
+            Some(t) if t.state != ThreadState::Finished => {
+                t.cancelled = true;
+                0
+            }
+            _ => EINVAL,
+        }
+    })
+}
+
+/// Resultado que recibe quien hace join de un hilo terminado con
+/// `my_thread_kill`.
+pub const MY_THREAD_KILLED: *mut c_void = usize::MAX as *mut c_void;
+
+/// Termina `tid` en el acto, sin esperar a que pase por un punto de
+/// cancelación: para hilos trabados que nunca llegan a `my_testcancel`.
+///
+/// El hilo no vuelve a correr: no se ejecutan sus handlers de limpieza ni
+/// sus destructores TLS, y lo que tenga en su pila se pierde. Los mutexes
+/// que tenía tomados pasan al siguiente hilo que los espera, sale de la cola
+/// del mutex que esperaba y quien le haga join recibe `MY_THREAD_KILLED`.
+/// Devuelve `EINVAL` si `tid` es el hilo actual, main, el idle, no existe o
+/// ya terminó.
+pub fn my_thread_kill(tid: MyThreadId) -> c_int {
+    let held = match with_scheduler(|s| s.kill(tid)) {
+        Ok(held) => held,
+        Err(err) => return err,
+    };
+    for m in held.into_iter().rev() {
+        mutex_unlock_as(&m, tid);
+    }
+    0
+}
+
+/// Punto de cancelación: si se pidió cancelar el hilo actual, termina con
+/// `my_thread_end(ptr::null_mut())`.
+pub fn my_testcancel() {
+    let cancelled = with_scheduler(|s| {
+        s.current_thread_id()
+            .and_then(|tid| s.get_thread(tid))
+            .is_some_and(|t| t.cancelled)
+    });
+    if cancelled {
+        my_thread_end(ptr::null_mut());
+    }
+}
+
+/// Duerme el hilo actual durante al menos `ticks` ticks del reloj virtual.
+///
+/// El reloj avanza con cada dispatch; si todos los hilos quedan bloqueados
+/// mientras alguno duerme, corre un hilo idle interno hasta que despierte.
+/// Con `0` equivale a `my_thread_yield`.
+pub fn my_thread_sleep(ticks: u64) {
+    if ticks == 0 {
+        my_thread_yield();
+        return;
+    }
+    let reason = with_scheduler(|sched| {
+        sched.ensure_main_thread();
+        let curr = sched.current_thread_id().expect("sleep sin hilo actual");
+        let until = sched.ticks + ticks;
+        sched.sleepers.push(Reverse((until, curr)));
+        BlockReason::Sleep { until }
+    });
+    block_and_switch(reason);
+}
+
+/// Congela el hilo `tid` hasta que se llame a `my_thread_resume`.
+///
+/// Un hilo listo sale de su cola; si es el hilo actual, cede la CPU como si
+/// se bloqueara. Si `tid` está bloqueado, la suspensión queda pendiente y se
+/// aplica cuando despierte. Quien haga join sobre un hilo suspendido sigue
+/// esperando. Devuelve `EINVAL` si el hilo no existe o ya terminó.
+pub fn my_thread_suspend(tid: MyThreadId) -> c_int {
+    match with_scheduler(|s| s.suspend(tid)) {
+        Ok(Some(switch)) => {
+            switch_context(switch);
+            0
+        }
+        Ok(None) => 0,
+        Err(errno) => errno,
+    }
+}
+
+/// Reanuda un hilo suspendido con `my_thread_suspend`. No hace nada si el
+/// hilo no estaba suspendido. Devuelve `EINVAL` si no existe o ya terminó.
+pub fn my_thread_resume(tid: MyThreadId) -> c_int {
+    with_scheduler(|s| s.resume(tid))
+}
+
+/// Bloquea hasta que el hilo `target` termine y devuelve su resultado.
+///
+/// Tras un join exitoso el TCB del hilo (y su pila) se libera, por lo que
+/// no se puede hacer join dos veces sobre el mismo hilo.
+///
+/// Si esperar a `target` cerraría un ciclo de espera, el proceso aborta con
+/// un reporte; con `my_sched_set_deadlock_errors(true)` se devuelve un
+/// puntero nulo sin bloquear.
+///
+/// # Panics
+/// Si `target` es un hilo de `my_thread_spawn` cuya closure entró en pánico,
+/// el join relanza ese pánico con `resume_unwind`, como
+/// `std::thread::JoinHandle`. Las rutinas `extern "C"` no pueden propagar
+/// pánicos: uno dentro de ellas aborta el proceso.
+pub fn my_thread_join(target: MyThreadId) -> *mut c_void {
+    join_thread(target).result
+}
+
+/// Hace join a un hilo que terminó con `my_thread_end_boxed` y recupera el
+/// `Box`, así la memoria se libera del lado de quien espera. Devuelve `None`
+/// si el resultado es nulo (por ejemplo, si el join no pudo esperar).
+///
+/// # Panics
+/// En builds de debug, si el hilo dejó un `Box` de otro tipo. Un resultado
+/// de `my_thread_end` no tiene tipo registrado y no se puede verificar.
+pub fn my_thread_join_boxed<T: 'static>(target: MyThreadId) -> Option<Box<T>> {
+    let joined = join_thread(target);
+    if joined.result.is_null() {
+        return None;
+    }
+    debug_assert!(
+        joined.result_type.is_none_or(|t| t == TypeId::of::<T>()),
+        "my_thread_join_boxed: el hilo {target} no devolvió un Box<{}>",
+        std::any::type_name::<T>(),
+    );
+    Some(unsafe { Box::from_raw(joined.result as *mut T) })
+}
+
+/// Lo que se saca del TCB de un hilo al hacerle join.
+struct Joined {
+    result: *mut c_void,
+    /// Valor tipado de `my_thread_spawn_with_result`.
+    output: Option<Box<dyn Any>>,
+    result_type: Option<TypeId>,
+}
+
+/// Espera a `target` y devuelve su resultado, sacado del TCB antes de
+/// liberarlo. Si terminó en pánico, lo relanza una vez liberado el TCB.
+fn join_thread(target: MyThreadId) -> Joined {
+    let nothing = || (Joined { result: ptr::null_mut(), output: None, result_type: None }, None);
+
+    let wait = with_scheduler(|sched| {
+        let curr = sched.current_thread_id().expect("join sin hilo actual");
+
+        if curr == target
+            || sched.idle_tid == Some(target)
+            || sched.get_thread(target).is_some_and(|t| t.detached)
+        {
+            // No tiene sentido hacer join a uno mismo, el idle nunca termina
+            // y de un detached no se hace join.
+            return Wait::Ready(nothing());
+        }
+
+        if sched.try_join_immediate(target).is_some() {
+            return Wait::Ready(take_joined(sched, target));
+        }
+
+        let reason = BlockReason::Join { target, until: None };
+        if sched.check_deadlock(curr, reason) != 0 {
+            return Wait::Ready(nothing());
+        }
+
+        // Bloqueamos el hilo actual en espera del target
+        let t = sched.get_thread_mut(target).expect("target de join no encontrado");
+        t.joined_by = Some(curr);
+        Wait::Block(reason)
+    });
+
+    let (joined, panic) = match wait {
+        Wait::Ready(res) => res,
+        Wait::Block(reason) => {
+            block_and_switch(reason);
+
+            // Cuando despertamos, ya terminó
+            with_scheduler(|sched| {
+                assert!(sched.get_thread(target).is_some(), "thread desapareció durante join");
+                take_joined(sched, target)
+            })
+        }
+    };
+    if let Some(payload) = panic {
+        panic::resume_unwind(payload);
+    }
+    joined
+}
+
+/// Saca el resultado de un hilo terminado y libera su TCB. El pánico, si lo
+/// hubo, se devuelve aparte para relanzarlo fuera del scheduler.
+fn take_joined(sched: &mut Scheduler, target: MyThreadId) -> (Joined, Option<Box<dyn Any + Send>>) {
+    let t = sched.get_thread_mut(target).unwrap();
+    let joined = Joined { result: t.result, output: t.output.take(), result_type: t.result_type };
+    let panic = t.panic.take();
+    sched.reclaim(target);
+    (joined, panic)
+}
+
+/// Como `my_thread_join`, pero espera a lo sumo `max_ticks` ticks del reloj
+/// virtual. Con `0` solo revisa si ya terminó.
+///
+/// Una espera con plazo no cuenta para la detección de deadlocks: se
+/// resuelve sola al vencer. Si vence, el hilo sigue vivo y se le puede
+/// volver a hacer join.
+///
+/// # Errores
+/// * `ThreadError::NoSuchThread` si no existe.
+/// * `ThreadError::NotJoinable` si es el hilo actual, el idle, está detached
+///   o ya lo espera otro hilo.
+/// * `ThreadError::JoinTimeout` si no terminó a tiempo.
+///
+/// # Panics
+/// Como `my_thread_join`, relanza el pánico del hilo si lo hubo.
+pub fn my_thread_join_timeout(target: MyThreadId, max_ticks: u64) -> Result<*mut c_void, ThreadError> {
+    let curr = current_thread("join_timeout");
+    let wait = with_scheduler(|sched| {
+        let t = sched.get_thread(target).ok_or(ThreadError::NoSuchThread { tid: target })?;
+        if target == curr
+            || sched.idle_tid == Some(target)
+            || t.detached
+            || t.joined_by.is_some_and(|j| j != curr)
+        {
+            return Err(ThreadError::NotJoinable { tid: target });
+        }
+        if sched.try_join_immediate(target).is_some() {
+            return Ok(Wait::Ready(take_joined(sched, target)));
+        }
+        if max_ticks == 0 {
+            return Err(ThreadError::JoinTimeout { tid: target });
+        }
+
+        let until = sched.ticks.saturating_add(max_ticks);
+        sched.get_thread_mut(target).unwrap().joined_by = Some(curr);
+        sched.sleepers.push(Reverse((until, curr)));
+        Ok(Wait::Block(BlockReason::Join { target, until: Some(until) }))
+    })?;
+
+    let (joined, panic) = match wait {
+        Wait::Ready(res) => res,
+        Wait::Block(reason) => {
+            block_and_switch(reason);
+            with_scheduler(|sched| {
+                if sched.try_join_immediate(target).is_some() {
+                    return Ok(take_joined(sched, target));
+                }
+                // Venció el plazo: que su fin ya no nos despierte.
+                sched.get_thread_mut(target).unwrap().joined_by = None;
+                Err(ThreadError::JoinTimeout { tid: target })
+            })?
+        }
+    };
+    if let Some(payload) = panic {
+        panic::resume_unwind(payload);
+    }
+    Ok(joined.result)
+}
+
+/// Espera a que termine cualquiera de los hilos de `targets` y devuelve su
+/// id y su resultado, como `my_thread_join`. Los demás quedan disponibles
+/// para un join posterior.
+///
+/// Si alguno ya había terminado, regresa de inmediato con el primero de la
+/// lista que esté terminado.
+///
+/// # Errores
+/// * `ThreadError::NoSuchThread` si un id no existe.
+/// * `ThreadError::NotJoinable` si un hilo es el actual, el idle, está
+///   detached o ya lo espera otro hilo.
+///
+/// # Panics
+/// Si `targets` está vacío.
+pub fn my_thread_join_any(targets: &[MyThreadId]) -> Result<(MyThreadId, *mut c_void), ThreadError> {
+    assert!(!targets.is_empty(), "my_thread_join_any: la lista de hilos está vacía");
+    let curr = current_thread("join_any");
+    let first_finished = |sched: &Scheduler| {
+        targets.iter().copied().find(|&tid| sched.try_join_immediate(tid).is_some())
+    };
+
+    let ready = with_scheduler(|sched| {
+        for &tid in targets {
+            let t = sched.get_thread(tid).ok_or(ThreadError::NoSuchThread { tid })?;
+            if tid == curr
+                || sched.idle_tid == Some(tid)
+                || t.detached
+                || t.joined_by.is_some_and(|j| j != curr)
+            {
+                return Err(ThreadError::NotJoinable { tid });
+            }
+        }
+        if let Some(tid) = first_finished(sched) {
+            return Ok(Some(tid));
+        }
+        // Quien termine primero nos despierta desde `finish_current`.
+        for &tid in targets {
+            sched.get_thread_mut(tid).unwrap().joined_by = Some(curr);
+        }
+        Ok(None)
+    })?;
+
+    let winner = match ready {
+        Some(tid) => tid,
+        None => {
+            block_and_switch(BlockReason::JoinAny);
+            with_scheduler(|sched| {
+                let winner = first_finished(sched).expect("join_any: despertó sin que terminara ningún hilo");
+                // Los demás no deben despertarnos más adelante.
+                for &tid in targets {
+                    if let Some(t) = sched.get_thread_mut(tid)
+                        && t.joined_by == Some(curr)
+                    {
+                        t.joined_by = None;
+                    }
+                }
+                winner
+            })
+        }
+    };
+
+    Ok((winner, join_thread(winner).result))
+}
+
+/// Le pone nombre a `tid`; los reportes de deadlock y de desbordamiento de
+/// pila lo muestran junto al id. Devuelve `EINVAL` si el hilo no existe.
+pub fn my_thread_setname(tid: MyThreadId, name: &str) -> c_int {
+    with_scheduler(|s| {
+        s.ensure_main_thread();
+        match s.get_thread_mut(tid) {
+            Some(t) => {
+                t.name = Some(name.to_owned());
+                0
+            }
+            None => EINVAL,
+        }
+    })
+}
+
+/// Nombre de `tid`, si se le puso uno. Se devuelve una copia porque el TCB
+/// vive dentro del scheduler.
+pub fn my_thread_getname(tid: MyThreadId) -> Option<String> {
+    with_scheduler(|s| s.get_thread(tid).and_then(|t| t.name.clone()))
+}
+
+/// Registra un handler de limpieza para el hilo actual.
+///
+/// Los handlers pendientes se ejecutan en orden inverso al de registro cuando
+/// el hilo termina, antes de que se despierte a quien haga join.
+pub fn my_cleanup_push(routine: CleanupFn, arg: *mut c_void) {
+    let curr = current_thread("cleanup_push");
+    with_scheduler(|s| s.get_thread_mut(curr).unwrap().cleanup_handlers.push((routine, arg)));
+}
+
+/// Quita el último handler registrado por el hilo actual y, si `execute` es
+/// `true`, lo ejecuta.
+pub fn my_cleanup_pop(execute: bool) {
+    let handler = with_scheduler(|s| s.pop_cleanup_handler());
+    if let (true, Some((routine, arg))) = (execute, handler) {
+        routine(arg);
+    }
+}
+
+/// Marca un hilo como detached (no se espera join).
+///
+/// Si el hilo ya había terminado, su TCB se libera inmediatamente (y se
+/// llama a su finalizador, si tiene).
+pub fn my_thread_detach(tid: MyThreadId) -> c_int {
+    let rc = with_scheduler(|sched| {
+        if !sched.is_user_thread(tid) {
+            return EINVAL;
+        }
+        if let Some(t) = sched.get_thread_mut(tid) {
+            t.detached = true;
+            sched.reclaim(tid);
+            0
+        } else {
+            EINVAL
+        }
+    });
+    fire_finalizers();
+    rc
+}
+
+/// Registra `finalizer` para `tid`: cuando el hilo sea detached y su TCB se
+/// libere, se lo llama con el resultado del hilo, así quien lo creó puede
+/// recuperar memoria (por ejemplo, el `Box` del retval) sin hacer join.
+///
+/// Un hilo al que se le hace join no llama a su finalizador: el resultado lo
+/// recibe quien hizo join. Devuelve `EINVAL` si `tid` no existe o ya se
+/// liberó.
+pub fn my_thread_set_finalizer(tid: MyThreadId, finalizer: ThreadFinalizer) -> c_int {
+    with_scheduler(|sched| {
+        if !sched.is_user_thread(tid) {
+            return EINVAL;
+        }
+        sched.get_thread_mut(tid).unwrap().finalizer = Some(finalizer);
+        0
+    })
+}
+
+/// Crea una clave de almacenamiento local de hilo. Cada hilo ve su propio
+/// valor para la clave, inicialmente nulo.
+///
+/// Si hay `destructor`, al terminar un hilo se lo llama con el valor que el
+/// hilo tenga guardado, si no es nulo (después de los handlers de limpieza).
+pub fn my_tls_key_create(destructor: Option<TlsDestructor>) -> MyTlsKey {
+    with_scheduler(|s| {
+        s.tls_destructors.push(destructor);
+        s.tls_destructors.len() - 1
+    })
+}
+
+/// Guarda `value` como el valor de `key` para el hilo actual. Devuelve
+/// `EINVAL` si la clave no existe.
+pub fn my_tls_set(key: MyTlsKey, value: *mut c_void) -> c_int {
+    let curr = current_thread("tls_set");
+    with_scheduler(|s| {
+        if key >= s.tls_destructors.len() {
+            return EINVAL;
+        }
+        s.get_thread_mut(curr).unwrap().tls.insert(key, value);
+        0
+    })
+}
+
+/// Valor de `key` para el hilo actual (nulo si no guardó ninguno o si la
+/// clave no existe).
+pub fn my_tls_get(key: MyTlsKey) -> *mut c_void {
+    let curr = current_thread("tls_get");
+    with_scheduler(|s| {
+        s.get_thread(curr)
+            .and_then(|t| t.tls.get(&key).copied())
+            .unwrap_or(ptr::null_mut())
+    })
+}
+
+/// Devuelve las estadísticas globales del scheduler.
+///
+/// `live_threads` cuenta los hilos terminados hasta que alguien hace join
+/// sobre ellos (o hasta que se liberan por estar detached).
+pub fn my_sched_stats() -> SchedStats {
+    with_scheduler(|sched| {
+        sched.ensure_main_thread();
+        sched.sched_stats()
+    })
+}
+
+/// Reloj virtual del scheduler: avanza un tick por cada dispatch, por cada
+/// slice extra que un hilo consume sin ceder la CPU y por cada vez que corre
+/// el hilo idle. Nunca retrocede.
+pub fn my_sched_ticks() -> u64 {
+    with_scheduler(|sched| sched.ticks)
+}
+
+/// Devuelve las estadísticas de un hilo, o `None` si no existe (o ya fue liberado).
+pub fn my_thread_stats(tid: MyThreadId) -> Option<ThreadStats> {
+    with_scheduler(|sched| {
+        sched.ensure_main_thread();
+        sched.thread_stats(tid)
+    })
+}
+
+/// Cambia la política de scheduling de un hilo. Devuelve `EINVAL` si el hilo
+/// no existe o ya terminó.
+pub fn my_thread_chsched(tid: MyThreadId, policy: SchedPolicy) -> c_int {
+    with_scheduler(|s| s.change_scheduler(tid, policy))
+}
+
+/// Id del hilo que llama; `0` en main (como `pthread_self`).
+pub fn my_thread_self() -> MyThreadId {
+    current_thread("my_thread_self")
+}
+
+/// Devuelve la política actual de un hilo con sus parámetros vigentes: los
+/// tickets ya redondeados (sin los extra por envejecimiento) y el deadline
+/// actual, incluso si cambió con `my_thread_set_realtime_deadline`. Un hilo
+/// consulta la suya con `my_thread_getsched(my_thread_self())`.
+pub fn my_thread_getsched(tid: MyThreadId) -> Result<SchedPolicy, ThreadError> {
+    with_scheduler(|s| {
+        s.ensure_main_thread();
+        s.policy_of(tid)
+    })
+}
+
+/// Devuelve una foto de todos los hilos (sin el idle), ordenada por id.
+///
+/// Se arma sin soltar el scheduler, así que ningún hilo cambia de estado
+/// mientras se construye.
+pub fn my_thread_list() -> Vec<MyThreadInfo> {
+    with_scheduler(|s| {
+        s.ensure_main_thread();
+        s.thread_list()
+    })
+}
+
+/// Devuelve qué mutexes tiene cada hilo y quién espera cada uno. Su
+/// `Display` da un texto listo para imprimir al depurar un bloqueo.
+pub fn my_sched_lock_report() -> MyLockReport {
+    with_scheduler(|s| {
+        s.ensure_main_thread();
+        s.lock_report()
+    })
+}
+
+/// Estado actual de `tid`. Es una consulta barata: no arma la lista
+/// completa como `my_thread_list`.
+///
+/// Devuelve `MyThreadState::Unknown` (sin entrar en pánico) para ids que
+/// nunca existieron o cuyo TCB ya se liberó, como un hilo detached que
+/// terminó o uno sobre el que ya se hizo join.
+pub fn my_thread_state(tid: MyThreadId) -> MyThreadState {
+    with_scheduler(|s| {
+        s.ensure_main_thread();
+        match s.get_thread(tid) {
+            Some(t) if s.is_user_thread(tid) => t.state.into(),
+            _ => MyThreadState::Unknown,
+        }
+    })
+}
+
+/// Por qué está bloqueado `tid`; `None` si no está bloqueado o no existe.
+pub fn my_thread_block_reason(tid: MyThreadId) -> Option<MyBlockReason> {
+    with_scheduler(|s| {
+        let t = s.get_thread(tid).filter(|t| t.state == ThreadState::Blocked)?;
+        t.block_reason.map(|r| r.public())
+    })
+}
+
+/// Cuántos hilos hay en cada estado (solo aparecen los estados presentes).
+pub fn my_thread_count_by_state() -> HashMap<MyThreadState, usize> {
+    let mut counts = HashMap::new();
+    for info in my_thread_list() {
+        *counts.entry(info.state).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Cambia los tickets de un hilo `SchedPolicy::Lottery` sin reasignar su
+/// política, de modo que conserva su lugar en la cola y los tickets extra
+/// acumulados por envejecimiento.
+///
+/// El cambio aplica desde el próximo sorteo. Igual que al crear el hilo,
+/// `0` tickets se interpreta como `1`. Devuelve `EINVAL` si el hilo no
+/// existe o no usa Lottery.
+pub fn my_thread_set_tickets(tid: MyThreadId, tickets: u32) -> c_int {
+    with_scheduler(|s| s.set_tickets(tid, tickets))
+}
+
+/// Transfiere `amount` tickets base del hilo Lottery `from` a `to`, por
+/// ejemplo para acelerar a un hilo del que depende quien dona.
+///
+/// A diferencia de la donación automática al esperar un mutex, la
+/// transferencia es permanente: se deshace con otra transferencia o con
+/// `my_thread_set_tickets`. `from` nunca baja de `1` ticket; si tiene menos
+/// de `amount + 1`, se mueven solo los que le sobran. Devuelve `EINVAL` si
+/// alguno de los dos hilos no existe o no usa Lottery.
+pub fn my_thread_donate_tickets(from: MyThreadId, to: MyThreadId, amount: u32) -> c_int {
+    with_scheduler(|s| s.transfer_tickets(from, to, amount))
+}
+
+/// Cambia el máximo de hilos vivos (incluido main). Por defecto es
+/// `DEFAULT_MAX_THREADS`.
+///
+/// Solo cuentan los TCBs no liberados: hacer join sobre un hilo, o que
+/// termine uno detached, libera capacidad. Bajar el límite por debajo de los
+/// hilos actuales no termina a ninguno, solo impide crear más. Devuelve
+/// `EINVAL` con `0`, ya que main siempre cuenta.
+pub fn my_sched_set_max_threads(max: usize) -> c_int {
+    if max == 0 {
+        return EINVAL;
+    }
+    with_scheduler(|s| s.max_threads = max);
+    0
+}
+
+/// Cantidad de TCBs vivos, incluyendo main y los hilos terminados sin join.
+pub fn my_sched_live_threads() -> usize {
+    with_scheduler(|sched| {
+        sched.ensure_main_thread();
+        sched.live_threads()
+    })
+}
+
+/// Cambia el tamaño de pila (en bytes) de los hilos que se creen a partir de
+/// ahora. Devuelve `EINVAL` si es menor que `MIN_STACK_SIZE`.
+///
+/// Al fondo de cada pila hay un canario que se revisa cada vez que el hilo
+/// cede la CPU, se bloquea o termina; si un desbordamiento lo pisó, el proceso
+/// aborta indicando el hilo y el tamaño de su pila.
+pub fn my_sched_set_stack_size(size: usize) -> c_int {
+    if size < MIN_STACK_SIZE {
+        return EINVAL;
+    }
+    with_scheduler(|s| s.stack_size = size);
+    0
+}
+
+/// Configura cuántos tickets extra gana un hilo Lottery cada vez que pierde
+/// un sorteo. Los tickets extra se descartan cuando el hilo finalmente corre.
+///
+/// Con `0` (el valor por defecto) no hay envejecimiento.
+pub fn my_sched_set_lottery_aging(bonus: u32) {
+    with_scheduler(|s| s.lottery_aging = bonus);
+}
+
+/// Elige dónde se encola un hilo Round Robin cuando despierta (por un mutex,
+/// un semáforo, una variable de condición, un join o un sleep). Por defecto
+/// `BackOfQueue`.
+///
+/// Con `FrontOfQueue` un hilo que pasa la mayor parte del tiempo bloqueado
+/// vuelve a correr antes que los que agotan su quantum, pero a lo sumo
+/// `MAX_FRONT_WAKEUPS` veces seguidas. `ThreadStats::avg_wakeup_latency`
+/// permite medir el efecto.
+pub fn my_sched_set_unblock_policy(policy: UnblockPolicy) {
+    with_scheduler(|s| s.unblock_policy = policy);
+}
+
+/// Reinicia el generador del sorteo Lottery con `seed`, de modo que la
+/// misma semilla y la misma carga producen la misma secuencia de ganadores.
+/// Por defecto se usa `DEFAULT_SEED`.
+pub fn my_sched_set_seed(seed: u64) {
+    with_scheduler(|s| {
+        s.seed = seed;
+        for (id, group) in s.groups.iter_mut().enumerate() {
+            group.queues.lottery.rng = Rng::new(group_seed(seed, id));
+        }
+        s.group_rng = Rng::new(!seed);
+    });
+}
+
+/// Última semilla pasada a `my_sched_set_seed` (o `DEFAULT_SEED`), para
+/// poder reproducir una corrida.
+pub fn my_sched_get_seed() -> u64 {
+    with_scheduler(|s| s.seed)
+}
+
+/// Cambia el quantum de una política: cuántos slots corre un hilo antes de
+/// rotar. Cada `my_thread_yield` consume un slot; mientras le queden, el hilo
+/// sigue corriendo en vez de ceder la CPU. El quantum por defecto es 1 (se
+/// rota en cada yield).
+///
+/// Bloquearse o terminar siempre cede la CPU, y un hilo de mayor prioridad
+/// que despierte espera a que el quantum del actual se agote. Devuelve
+/// `EINVAL` con `n == 0` o para `Mlfq`, que usa `MLFQ_QUANTA` por nivel.
+pub fn my_sched_set_quantum(kind: SchedPolicyKind, n: u32) -> c_int {
+    if n == 0 || kind == SchedPolicyKind::Mlfq {
+        return EINVAL;
+    }
+    with_scheduler(|s| s.quanta.insert(kind, n));
+    0
+}
+
+/// Crea un grupo de scheduling con peso `weight` y devuelve su id. `0` se
+/// interpreta como `1`, igual que los tickets.
+///
+/// Mientras haya hilos listos en más de un grupo, cada dispatch primero
+/// sortea un grupo entre ellos con probabilidad proporcional a su peso y
+/// después elige dentro de él según las políticas de sus hilos, así que un
+/// grupo recibe su parte sin importar cuántos hilos tengan los demás. Por
+/// eso un hilo `RealTime` solo tiene prioridad sobre los de su grupo. Los
+/// hilos de clases registradas con `my_sched_register_class` compiten
+/// siempre en `MY_SCHED_GROUP_DEFAULT`, que arranca con peso 1.
+pub fn my_sched_group_create(weight: u32) -> MySchedGroupId {
+    with_scheduler(|s| {
+        let id = s.groups.len();
+        s.groups.push(SchedGroup::new(weight.max(1), group_seed(s.seed, id)));
+        id
+    })
+}
+
+/// Cambia el peso de un grupo, incluido `MY_SCHED_GROUP_DEFAULT`. Aplica
+/// desde el próximo dispatch; `0` se interpreta como `1`. Devuelve `EINVAL`
+/// si el grupo no existe.
+pub fn my_sched_group_set_weight(group: MySchedGroupId, weight: u32) -> c_int {
+    with_scheduler(|s| match s.groups.get_mut(group) {
+        Some(g) => {
+            g.weight = weight.max(1);
+            0
+        }
+        None => EINVAL,
+    })
+}
+
+/// Pasa el hilo `tid` al grupo `group`, conservando su política. Devuelve
+/// `EINVAL` si el hilo o el grupo no existen, o si el hilo ya terminó.
+pub fn my_thread_set_group(tid: MyThreadId, group: MySchedGroupId) -> c_int {
+    with_scheduler(|s| {
+        s.ensure_main_thread();
+        s.set_group(tid, group)
+    })
+}
+
+/// Registra una clase de scheduling y devuelve su `class_id`, para usar en
+/// `SchedPolicy::Custom`.
+///
+/// `priority` la ubica entre las clases incorporadas (`PRIO_REALTIME`, ...,
+/// `PRIO_ROUND_ROBIN`): `pick_next` consulta primero a las de mayor prioridad.
+pub fn my_sched_register_class(class: Box<dyn SchedClass>, priority: u32) -> usize {
+    with_scheduler(|s| {
+        let class_id = s.custom_classes.len();
+        s.custom_classes.push(class);
+        s.class_order.push((priority, SchedPolicyKind::Custom { class_id }));
+        // Orden estable: a igual prioridad se mantiene el de registro.
+        s.class_order.sort_by_key(|&(prio, _)| Reverse(prio));
+        class_id
+    })
+}
+
+/// Actualiza el `deadline` de un hilo de Tiempo Real.
+///
+/// El valor de `deadline` es un tiempo absoluto expresado en las mismas
+/// unidades que utilice la simulación (por ejemplo, ticks). Este llamado
+/// solo es válido si el hilo fue configurado con `SchedPolicy::RealTime`.
+/// En caso contrario, devuelve `EINVAL`.
+pub fn my_thread_set_realtime_deadline(tid: MyThreadId, deadline: u64) -> c_int {
+    with_scheduler(|s| s.set_realtime_deadline(tid, deadline))
+}
+
+/// Devuelve el `deadline` actual de un hilo de Tiempo Real, si existe.
+pub fn my_thread_get_realtime_deadline(tid: MyThreadId) -> Option<u64> {
+    with_scheduler(|s| s.get_realtime_deadline(tid))
+}
+
+/// Registra la función a invocar cada vez que un hilo de Tiempo Real
+/// incumple su deadline.
+///
+/// Al crear el hilo (o al pasarlo a `SchedPolicy::RealTime`) su deadline
+/// vence en el tick actual + `deadline`; `my_thread_set_realtime_deadline`
+/// fija un tick absoluto. El incumplimiento se detecta cuando el hilo es
+/// despachado o termina después de ese tick, y se reporta una sola vez por
+/// deadline. El handler corre en el hilo que provocó el cambio de contexto,
+/// justo antes de que se realice, así que no debe ceder la CPU ni bloquearse.
+pub fn my_sched_on_deadline_miss(handler: DeadlineMissHandler) {
+    with_scheduler(|s| s.deadline_miss_handler = Some(handler));
+}
+
+/// Cantidad de deadlines incumplidos por un hilo, o `None` si no existe.
+pub fn my_thread_deadline_misses(tid: MyThreadId) -> Option<u64> {
+    with_scheduler(|s| s.deadline_misses(tid))
+}
+
+/// Métricas de Tiempo Real de `tid`: activaciones, deadlines incumplidos,
+/// peor atraso y tiempo de respuesta promedio. `None` si el hilo no existe o
+/// nunca fue RealTime; si dejó de serlo, devuelve los valores congelados.
+pub fn my_thread_rt_stats(tid: MyThreadId) -> Option<RtStats> {
+    with_scheduler(|s| s.rt_stats(tid))
+}
+
+/// Elige qué hacer cuando una llamada bloqueante cerraría un ciclo de espera.
+///
+/// Por defecto el proceso aborta mostrando el ciclo. Con `true`,
+/// `my_mutex_lock` devuelve `EDEADLK` y `my_thread_join` un puntero nulo, sin
+/// bloquear al hilo.
+pub fn my_sched_set_deadlock_errors(enabled: bool) {
+    with_scheduler(|s| s.deadlock_errors = enabled);
+}
+
+// ============ Preempción por SIGALRM (mypreempt) ============ //
+
+/// `pthread_t` del hilo del sistema que activó la preempción; 0 si nadie.
+static PREEMPT_OWNER: AtomicUsize = AtomicUsize::new(0);
+
+// El crate `libc` no expone `setitimer` para todas las plataformas.
+unsafe extern "C" {
+    fn setitimer(which: c_int, new: *const libc::itimerval, old: *mut libc::itimerval) -> c_int;
+}
+
+/// Activa la preempción: cada `interval_us` microsegundos llega un `SIGALRM`
+/// que saca de la CPU al hilo actual como si hubiera llamado a
+/// `my_thread_yield`. Con `0` se apaga el timer, se olvida al hilo dueño y
+/// `SIGALRM` pasa a ignorarse, descartando los ticks que quedaran pendientes.
+/// Devuelve el `errno` de `sigaction`/`setitimer` si fallan.
+///
+/// Los hilos son cooperativos por defecto y las primitivas asumen que nadie
+/// los interrumpe entre leer y escribir su estado, así que la señal queda
+/// bloqueada salvo dentro de `my_thread_preemptible`. Solo se preempta a los
+/// hilos de usuario del hilo del sistema que llamó a esta función.
+pub fn my_sched_set_preemption(interval_us: u64) -> c_int {
+    // Los contextos guardados recuperan su máscara al volver a correr: se
+    // bloquea la señal también en ellos.
+    with_scheduler(|s| {
+        s.ensure_main_thread();
+        for t in s.threads.values_mut() {
+            unsafe { libc::sigaddset(&mut t.context.uc_sigmask, libc::SIGALRM) };
+        }
+    });
+    unsafe {
+        let mut alrm: libc::sigset_t = mem::zeroed();
+        libc::sigemptyset(&mut alrm);
+        libc::sigaddset(&mut alrm, libc::SIGALRM);
+        libc::pthread_sigmask(libc::SIG_BLOCK, &alrm, ptr::null_mut());
+
+        if interval_us > 0 {
+            PREEMPT_OWNER.store(libc::pthread_self() as usize, Ordering::SeqCst);
+            // Sin SA_ONSTACK: el handler tiene que correr sobre la pila del
+            // hilo interrumpido, porque queda suspendido dentro del handler
+            // hasta que vuelva a tocarle; una pila alternativa compartida la
+            // pisaría la siguiente preempción.
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = on_sigalrm as extern "C" fn(c_int) as usize;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(libc::SIGALRM, &action, ptr::null_mut()) != 0 {
+                return std::io::Error::last_os_error().raw_os_error().unwrap_or(EINVAL);
+            }
+        }
+
+        let period = libc::timeval {
+            tv_sec: (interval_us / 1_000_000) as libc::time_t,
+            tv_usec: (interval_us % 1_000_000) as libc::suseconds_t,
+        };
+        let timer = libc::itimerval { it_interval: period, it_value: period };
+        if setitimer(libc::ITIMER_REAL, &timer, ptr::null_mut()) != 0 {
+            return std::io::Error::last_os_error().raw_os_error().unwrap_or(EINVAL);
+        }
+
+        if interval_us == 0 {
+            // Sin SIG_DFL: un tick que siguiera pendiente terminaría el
+            // proceso al desbloquear la señal.
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = libc::SIG_IGN;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(libc::SIGALRM, &action, ptr::null_mut()) != 0 {
+                return std::io::Error::last_os_error().raw_os_error().unwrap_or(EINVAL);
+            }
+            PREEMPT_OWNER.store(0, Ordering::SeqCst);
+        }
+    }
+    0
+}
+
+/// Ejecuta `f` con la preempción habilitada para el hilo actual: si llega un
+/// `SIGALRM` mientras corre, el hilo cede la CPU aunque no llame a
+/// `my_thread_yield`.
+///
+/// `f` debe ser cómputo puro: nada de primitivas de mypthreads, `println!`
+/// ni reservas de memoria, porque la interrupción puede caer en medio de
+/// ellas (el allocator y el scheduler no son reentrantes). La máscara de
+/// señales viaja con el contexto de cada hilo, así que la preempción queda
+/// habilitada solo para este hilo y solo durante `f`.
+pub fn my_thread_preemptible<R>(f: impl FnOnce() -> R) -> R {
+    let mut previous: libc::sigset_t = unsafe { mem::zeroed() };
+    unsafe {
+        let mut alrm: libc::sigset_t = mem::zeroed();
+        libc::sigemptyset(&mut alrm);
+        libc::sigaddset(&mut alrm, libc::SIGALRM);
+        libc::pthread_sigmask(libc::SIG_UNBLOCK, &alrm, &mut previous);
+    }
+    let result = f();
+    unsafe {
+        libc::pthread_sigmask(libc::SIG_SETMASK, &previous, ptr::null_mut());
+    }
+    result
+}
+
+/// Handler de `SIGALRM`: cede la CPU en nombre del hilo interrumpido.
+extern "C" fn on_sigalrm(_signal: c_int) {
+    let errno = unsafe { *libc::__errno_location() };
+
+    let owner = PREEMPT_OWNER.load(Ordering::SeqCst);
+    if owner != unsafe { libc::pthread_self() } as usize {
+        // `setitimer` avisa al proceso, no a un hilo: se reenvía al dueño,
+        // que la recibe cuando la desbloquee.
+        if owner != 0 {
+            unsafe { libc::pthread_kill(owner as libc::pthread_t, libc::SIGALRM) };
+        }
+    } else {
+        // Si la señal cayó mientras el scheduler estaba prestado, se pierde
+        // este tick y el hilo sigue.
+        let switch = SCHEDULER
+            .try_with(|cell| cell.try_borrow_mut().ok().and_then(|mut s| s.yield_current()))
+            .ok()
+            .flatten();
+        if let Some(switch) = switch {
+            unsafe {
+                swapcontext(switch.from, switch.to);
+            }
+            let _ = SCHEDULER.try_with(|cell| cell.try_borrow_mut().map(|mut s| s.reap_zombies()));
+        }
+    }
+
+    unsafe { *libc::__errno_location() = errno };
+}
+
+// ============ Implementación del mutex propio (mymutex) ============ //
+
+/// Identificador de un `MyMutex` en los reportes de locks.
+pub type MyMutexId = usize;
+
+static NEXT_MUTEX_ID: AtomicUsize = AtomicUsize::new(1);
+
+/// Mutex de los hilos de usuario.
+///
+/// El estado vive en `Cell`/`RefCell` para que varios hilos puedan usar el
+/// mismo mutex a través de referencias compartidas (`&MyMutex`). Es correcto
+/// porque todos los hilos de usuario corren sobre un único hilo del sistema y
+/// no se expropian en medio de una operación del mutex.
+///
+/// El estado está en un `MutexCore` aparte, que los hilos que lo tienen
+/// tomado comparten con el `MyMutex`: así mover o destruir el mutex nunca
+/// deja al scheduler con un puntero colgado.
+#[derive(Debug)]
+pub struct MyMutex {
+    core: Rc<MutexCore>,
+}
+
+#[derive(Debug)]
+struct MutexCore {
+    /// Id estable para los reportes (ver `my_sched_lock_report`).
+    id: MyMutexId,
+    handoff: MutexHandoff,
+    locked: Cell<bool>,
+    owner: Cell<Option<MyThreadId>>,
+    waiters: RefCell<VecDeque<MyThreadId>>,
+    /// Waiter que despertó el último unlock con `MutexHandoff::Barging`.
+    woken: Cell<Option<MyThreadId>>,
+    stats: Cell<MyMutexStats>,
+}
+
+/// Qué hace `my_mutex_unlock` cuando hay hilos esperando (ver
+/// `my_mutex_init_with_handoff`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum MutexHandoff {
+    /// Le pasa el mutex al primero de la cola: es justo, pero el nuevo dueño
+    /// recién lo usa cuando le toca correr, y mientras tanto los demás se
+    /// bloquean contra él (convoy).
+    #[default]
+    Direct,
+    /// Lo deja libre y despierta al primero de la cola, que compite con el
+    /// resto: el primero que corre se lo lleva. Da más throughput a costa de
+    /// justicia.
+    Barging,
+}
+
+/// Contadores de contención de un mutex (ver `my_mutex_stats`).
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct MyMutexStats {
+    /// Veces que un hilo tomó el mutex.
+    pub acquisitions: u64,
+    /// Veces que un unlock se lo pasó directamente a un waiter.
+    pub handoffs: u64,
+    /// Veces que, con `Barging`, lo tomó otro hilo antes que el waiter
+    /// despertado.
+    pub steals: u64,
+}
+
+impl MyMutex {
+    pub fn new() -> Self {
+        Self::with_handoff(MutexHandoff::Direct)
+    }
+
+    /// Mutex con la política de traspaso indicada.
+    pub fn with_handoff(handoff: MutexHandoff) -> Self {
+        let core = MutexCore {
+            id: NEXT_MUTEX_ID.fetch_add(1, Ordering::Relaxed),
+            handoff,
+            locked: Cell::new(false),
+            owner: Cell::new(None),
+            waiters: RefCell::new(VecDeque::new()),
+            woken: Cell::new(None),
+            stats: Cell::new(MyMutexStats::default()),
+        };
+        MyMutex { core: Rc::new(core) }
+    }
+
+    /// Hilo que tiene el mutex, si alguno.
+    pub fn owner(&self) -> Option<MyThreadId> {
+        self.core.owner.get()
+    }
+
+    /// Id del mutex en los reportes. Se asigna al crearlo (o al
+    /// reinicializarlo con `my_mutex_init`) y no cambia mientras tanto.
+    pub fn id(&self) -> MyMutexId {
+        self.core.id
+    }
+}
+
+impl Drop for MyMutex {
+    /// Un mutex destruido tomado deja de contar como tomado por su dueño. Si
+    /// el scheduler no está disponible (está prestado, o el hilo del sistema
+    /// está terminando) el dueño conserva su referencia al `MutexCore`, que
+    /// sigue siendo válida.
+    fn drop(&mut self) {
+        if !self.core.locked.get() && self.core.woken.get().is_none() {
+            return;
+        }
+        let _ = SCHEDULER.try_with(|cell| {
+            if let Ok(mut sched) = cell.try_borrow_mut() {
+                sched.forget_mutex(&self.core);
+            }
+        });
+    }
+}
+
+impl MutexCore {
+    /// Anota una adquisición de `tid`. Los hilos que siguen esperando pasan a
+    /// esperar (y prestarle sus tickets) al nuevo dueño.
+    fn acquired_by(self: &Rc<Self>, sched: &mut Scheduler, tid: MyThreadId) {
+        self.locked.set(true);
+        self.owner.set(Some(tid));
+        self.bump(|st| st.acquisitions += 1);
+        sched.note_mutex_acquired(tid, self);
+        sched.revoke_donation(tid);
+        for &waiter in self.waiters.borrow().iter() {
+            sched.revoke_donation(waiter);
+            sched.donate_tickets(waiter, tid);
+            sched.retarget_mutex_wait(waiter, tid);
+        }
+    }
+
+    fn bump(&self, f: impl FnOnce(&mut MyMutexStats)) {
+        let mut stats = self.stats.get();
+        f(&mut stats);
+        self.stats.set(stats);
+    }
+}
+
+impl Default for MyMutex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Inicializa un mutex.
+pub fn my_mutex_init(m: &mut MyMutex) -> c_int {
+    *m = MyMutex::new();
+    0
+}
+
+/// Inicializa un mutex con la política de traspaso `handoff`. `my_mutex_init`
+/// usa `MutexHandoff::Direct`.
+pub fn my_mutex_init_with_handoff(m: &mut MyMutex, handoff: MutexHandoff) -> c_int {
+    *m = MyMutex::with_handoff(handoff);
+    0
+}
+
+/// Contadores de contención del mutex desde que se inicializó.
+pub fn my_mutex_stats(m: &MyMutex) -> MyMutexStats {
+    m.core.stats.get()
+}
+
+/// Destruye un mutex (simple, sin liberar recursos extra).
+pub fn my_mutex_destroy(m: &mut MyMutex) -> c_int {
+    if m.core.locked.get() || !m.core.waiters.borrow().is_empty() {
+        // Semántica aproximada a pthread: no destruir si está bloqueado.
+        EBUSY
+    } else {
+        // Nada especial que hacer.
+        0
+    }
+}
+
+/// Intenta tomar el lock; si está ocupado, retorna EBUSY.
+pub fn my_mutex_trylock(m: &MyMutex) -> c_int {
+    let curr = current_thread("trylock");
+
+    if !m.core.locked.get() {
+        mutex_take_free(&m.core, curr);
+        0
+    } else {
+        EBUSY
+    }
+}
+
+/// Bloquea hasta adquirir el mutex.
+///
+/// Si esperar el mutex cerraría un ciclo de espera (incluido tomar dos veces
+/// un mutex propio), aborta con un reporte o, con
+/// `my_sched_set_deadlock_errors(true)`, devuelve `EDEADLK`.
+pub fn my_mutex_lock(m: &MyMutex) -> c_int {
+    mutex_lock(&m.core, None)
+}
+
+/// Como `my_mutex_lock`, pero espera a lo sumo `ticks` ticks del reloj
+/// virtual. Devuelve `ETIMEDOUT` si el mutex no llegó a sus manos a tiempo,
+/// o `EDEADLK` si el hilo ya es el dueño.
+///
+/// Una espera con plazo no cuenta para la detección de ciclos: si forma
+/// parte de uno, el plazo lo rompe.
+pub fn my_mutex_timedlock(m: &MyMutex, ticks: u64) -> c_int {
+    if ticks == 0 {
+        return match my_mutex_trylock(m) {
+            EBUSY if m.owner() == Some(current_thread("timedlock")) => EDEADLK,
+            EBUSY => ETIMEDOUT,
+            rc => rc,
+        };
+    }
+    mutex_lock(&m.core, Some(ticks))
+}
+
+fn mutex_lock(m: &Rc<MutexCore>, timeout: Option<u64>) -> c_int {
+    let curr = current_thread("lock");
+    let until = timeout.map(|ticks| with_scheduler(|sched| sched.ticks.saturating_add(ticks)));
+
+    // Se repite hasta tener el mutex: un despertar que no vino de
+    // `mutex_unlock_as` (espurio, o de otra vía del scheduler) no lo da.
+    loop {
+        if !m.locked.get() {
+            // Tras un despertar espurio todavía podemos estar en la cola.
+            m.waiters.borrow_mut().retain(|&tid| tid != curr);
+            mutex_take_free(m, curr);
+            return 0;
+        }
+
+        let owner = m.owner.get().expect("mutex tomado sin dueño");
+        if until.is_some() && owner == curr {
+            return EDEADLK;
+        }
+        let reason = BlockReason::Mutex { mutex: Rc::as_ptr(m), id: m.id, owner, until };
+        let err = with_scheduler(|sched| {
+            let err = sched.check_deadlock(curr, reason);
+            if err == 0 {
+                // Si ya está tomado, prestamos nuestros tickets al dueño
+                sched.donate_tickets(curr, owner);
+                if let Some(until) = until {
+                    sched.sleepers.push(Reverse((until, curr)));
+                }
+            }
+            err
+        });
+        if err != 0 {
+            m.waiters.borrow_mut().retain(|&tid| tid != curr);
+            return err;
+        }
+
+        // Nos encolamos (si no seguíamos en la cola) y bloqueamos
+        if !m.waiters.borrow().contains(&curr) {
+            m.waiters.borrow_mut().push_back(curr);
+        }
+        block_and_switch(reason);
+
+        if m.owner.get() == Some(curr) {
+            assert!(m.locked.get(), "el mutex #{} tiene dueño pero no está tomado", m.id);
+            return 0;
+        }
+
+        // No nos pasaron el mutex: soltamos el préstamo de tickets, que se
+        // vuelve a hacer al dueño de turno si seguimos esperando.
+        with_scheduler(|sched| {
+            sched.revoke_donation(curr);
+            sched.get_thread_mut(curr).unwrap().barge_wakeup = None;
+        });
+        if !m.locked.get() {
+            // Con `Barging` nos lo dejaron libre: hay que tomarlo aunque haya
+            // vencido el plazo, o los demás de la cola no se enterarían.
+            continue;
+        }
+        if until.is_some_and(|until| with_scheduler(|sched| sched.ticks) >= until) {
+            // Nos despertó el plazo: dejamos la cola.
+            m.waiters.borrow_mut().retain(|&tid| tid != curr);
+            return ETIMEDOUT;
+        }
+    }
+}
+
+/// Libera el mutex y despierta a un waiter si existe.
+pub fn my_mutex_unlock(m: &MyMutex) -> c_int {
+    mutex_unlock_as(&m.core, current_thread("unlock"))
+}
+
+fn mutex_unlock_as(m: &Rc<MutexCore>, curr: MyThreadId) -> c_int {
+    if m.owner.get() != Some(curr) {
+        // No es el dueño del mutex
+        return EINVAL;
+    }
+
+    with_scheduler(|sched| sched.note_mutex_released(curr, m));
+    if m.handoff == MutexHandoff::Barging {
+        // Queda libre y el primero de la cola reintenta en `mutex_lock`.
+        m.locked.set(false);
+        m.owner.set(None);
+        with_scheduler(|sched| sched.barge_wake(m));
+        return 0;
+    }
+
+    let next = m.waiters.borrow_mut().pop_front();
+    if let Some(next_tid) = next {
+        // Le pasamos el lock directamente al siguiente hilo.
+        m.bump(|st| st.handoffs += 1);
+        with_scheduler(|sched| {
+            m.acquired_by(sched, next_tid);
+            sched.unblock(next_tid);
+        });
+    } else {
+        // No hay nadie esperando
+        m.locked.set(false);
+        m.owner.set(None);
+    }
+
+    0
+}
+
+/// `curr` toma un mutex libre, quizás adelantándose al waiter que despertó
+/// un unlock con `MutexHandoff::Barging`.
+fn mutex_take_free(m: &Rc<MutexCore>, curr: MyThreadId) {
+    if m.woken.take().is_some_and(|woken| woken != curr) {
+        m.bump(|st| st.steals += 1);
+    }
+    with_scheduler(|sched| {
+        sched.get_thread_mut(curr).unwrap().barge_wakeup = None;
+        m.acquired_by(sched, curr);
+    });
+}
+
+impl MyMutex {
+    /// Bloquea hasta adquirir el mutex y devuelve un guard que lo libera al
+    /// salir de su alcance.
+    ///
+    /// Mientras el guard vive queda registrado como handler de limpieza, así
+    /// que si el hilo termina con `my_thread_end` el mutex también se libera.
+    ///
+    /// # Panics
+    /// Si `my_mutex_lock` falla (por ejemplo con `EDEADLK`).
+    pub fn lock_guard(&self) -> MyMutexGuard<'_> {
+        let err = my_mutex_lock(self);
+        assert_eq!(err, 0, "lock_guard: my_mutex_lock devolvió {err}");
+        MyMutexGuard::new(self)
+    }
+
+    /// Como `lock_guard`, pero devuelve `None` si el mutex está ocupado.
+    pub fn try_lock_guard(&self) -> Option<MyMutexGuard<'_>> {
+        if my_mutex_trylock(self) == 0 {
+            Some(MyMutexGuard::new(self))
+        } else {
+            None
+        }
+    }
+}
+
+/// Guard de `MyMutex::lock_guard`: libera el mutex en `Drop`.
+pub struct MyMutexGuard<'a> {
+    mutex: &'a MyMutex,
+    owner: MyThreadId,
+    /// Referencia al núcleo (`Rc::into_raw`) que se le pasó al handler de
+    /// limpieza; así el handler no depende de que el `MyMutex` siga en su
+    /// lugar si el guard se olvida con `mem::forget`.
+    handle: *const MutexCore,
+}
+
+impl<'a> MyMutexGuard<'a> {
+    fn new(mutex: &'a MyMutex) -> Self {
+        let owner = current_thread("lock_guard");
+        let handle = Rc::into_raw(Rc::clone(&mutex.core));
+        my_cleanup_push(release_guarded_mutex, handle as *mut c_void);
+        MyMutexGuard { mutex, owner, handle }
+    }
+}
+
+impl Drop for MyMutexGuard<'_> {
+    fn drop(&mut self) {
+        let arg = self.handle as *mut c_void;
+        if with_scheduler(|s| s.remove_cleanup_handler(self.owner, arg)) {
+            drop(unsafe { Rc::from_raw(self.handle) });
+        }
+        mutex_unlock_as(&self.mutex.core, self.owner);
+    }
+}
+
+/// Handler de limpieza de un guard que seguía vivo cuando el hilo terminó.
+fn release_guarded_mutex(arg: *mut c_void) {
+    let core = unsafe { Rc::from_raw(arg as *const MutexCore) };
+    mutex_unlock_as(&core, current_thread("unlock"));
+}
+
+// ============ Mutex recursivo (mymutex_recursive) ============ //
+
+/// Mutex que su dueño puede volver a tomar sin bloquearse: cada lock extra
+/// solo aumenta la profundidad y el mutex se libera cuando los unlock la
+/// llevan a cero.
+#[derive(Debug, Default)]
+pub struct MyMutexRecursive {
+    inner: MyMutex,
+    depth: Cell<usize>,
+}
+
+impl MyMutexRecursive {
+    pub fn new() -> Self {
+        MyMutexRecursive { inner: MyMutex::new(), depth: Cell::new(0) }
+    }
+
+    /// Hilo que tiene el mutex, si alguno.
+    pub fn owner(&self) -> Option<MyThreadId> {
+        self.inner.owner()
+    }
+
+    /// Cuántas veces lo tomó su dueño sin soltarlo.
+    pub fn depth(&self) -> usize {
+        self.depth.get()
+    }
+}
+
+/// Inicializa un mutex recursivo.
+pub fn my_mutex_recursive_init(m: &mut MyMutexRecursive) -> c_int {
+    *m = MyMutexRecursive::new();
+    0
+}
+
+/// Destruye un mutex recursivo; `EBUSY` si está tomado o alguien lo espera.
+pub fn my_mutex_recursive_destroy(m: &mut MyMutexRecursive) -> c_int {
+    my_mutex_destroy(&mut m.inner)
+}
+
+/// Toma el mutex. Si el hilo actual ya es el dueño, solo suma un nivel.
+pub fn my_mutex_recursive_lock(m: &MyMutexRecursive) -> c_int {
+    if m.owner() == Some(current_thread("recursive_lock")) {
+        m.depth.set(m.depth.get() + 1);
+        return 0;
+    }
+    let err = my_mutex_lock(&m.inner);
+    if err == 0 {
+        m.depth.set(1);
+    }
+    err
+}
+
+/// Como `my_mutex_recursive_lock`, pero retorna EBUSY si otro hilo lo tiene.
+pub fn my_mutex_recursive_trylock(m: &MyMutexRecursive) -> c_int {
+    if m.owner() == Some(current_thread("recursive_trylock")) {
+        m.depth.set(m.depth.get() + 1);
+        return 0;
+    }
+    let err = my_mutex_trylock(&m.inner);
+    if err == 0 {
+        m.depth.set(1);
+    }
+    err
+}
+
+/// Quita un nivel; al llegar a cero libera el mutex. Devuelve `EINVAL` si
+/// el hilo actual no es el dueño.
+pub fn my_mutex_recursive_unlock(m: &MyMutexRecursive) -> c_int {
+    if m.owner() != Some(current_thread("recursive_unlock")) {
+        return EINVAL;
+    }
+    let depth = m.depth.get() - 1;
+    m.depth.set(depth);
+    if depth == 0 { my_mutex_unlock(&m.inner) } else { 0 }
+}
+
+// ============ Variables de condición (mycond) ============ //
+
+/// Variable de condición asociada a un `MyMutex`.
+#[derive(Debug, Default)]
+pub struct MyCondVar {
+    waiters: VecDeque<MyThreadId>,
+}
+
+impl MyCondVar {
+    pub fn new() -> Self {
+        MyCondVar { waiters: VecDeque::new() }
+    }
+}
+
+/// Inicializa una variable de condición.
+pub fn my_cond_init(cvar: &mut MyCondVar) -> c_int {
+    *cvar = MyCondVar::new();
+    0
+}
+
+/// Libera `mutex`, bloquea al hilo actual hasta que otro haga
+/// `my_cond_signal` o `my_cond_broadcast`, y vuelve a tomar `mutex` antes de
+/// regresar.
+///
+/// Como los hilos no se expropian, soltar el mutex y bloquearse es atómico.
+/// Devuelve `EINVAL` si el hilo actual no es el dueño de `mutex`.
+pub fn my_cond_wait(cvar: &mut MyCondVar, mutex: &MyMutex) -> c_int {
+    cond_wait(cvar, mutex, None)
+}
+
+/// Igual que `my_cond_wait`, pero espera a lo sumo `ticks` ticks del reloj
+/// virtual. Devuelve `ETIMEDOUT` si nadie lo señaló a tiempo; en cualquier
+/// caso vuelve a tomar `mutex` antes de regresar.
+///
+/// Si el plazo vence pero una señal lo saca de la cola antes de que vuelva a
+/// correr, cuenta como señalado y devuelve `0`: la señal nunca se pierde.
+/// Con `ticks == 0` devuelve `ETIMEDOUT` sin soltar el mutex.
+pub fn my_cond_timedwait(cvar: &mut MyCondVar, mutex: &MyMutex, ticks: u64) -> c_int {
+    if ticks == 0 {
+        return if mutex.owner() == Some(current_thread("cond_timedwait")) { ETIMEDOUT } else { EINVAL };
+    }
+    cond_wait(cvar, mutex, Some(ticks))
+}
+
+fn cond_wait(cvar: &mut MyCondVar, mutex: &MyMutex, timeout: Option<u64>) -> c_int {
+    let curr = current_thread("cond_wait");
+    if mutex.owner() != Some(curr) {
+        return EINVAL;
+    }
+
+    let until = timeout.map(|ticks| {
+        with_scheduler(|sched| {
+            let until = sched.ticks.saturating_add(ticks);
+            sched.sleepers.push(Reverse((until, curr)));
+            until
+        })
+    });
+    cvar.waiters.push_back(curr);
+    my_mutex_unlock(mutex);
+    block_and_switch(BlockReason::Cond { cond: cvar as *const MyCondVar, until });
+
+    // Si seguimos en la cola nos despertó el plazo: salimos de ella para que
+    // una señal posterior no se la lleve un hilo que ya no espera.
+    let timed_out = match cvar.waiters.iter().position(|&tid| tid == curr) {
+        Some(pos) => {
+            cvar.waiters.remove(pos);
+            true
+        }
+        None => false,
+    };
+
+    let err = my_mutex_lock(mutex);
+    if err != 0 {
+        err
+    } else if timed_out {
+        ETIMEDOUT
+    } else {
+        0
+    }
+}
+
+/// Despierta al hilo que lleva más tiempo esperando en `cvar`, si hay alguno.
+pub fn my_cond_signal(cvar: &mut MyCondVar) -> c_int {
+    if let Some(tid) = cvar.waiters.pop_front() {
+        with_scheduler(|sched| sched.unblock(tid));
+    }
+    0
+}
+
+/// Despierta a todos los hilos que esperan en `cvar`.
+pub fn my_cond_broadcast(cvar: &mut MyCondVar) -> c_int {
+    let waiters = mem::take(&mut cvar.waiters);
+    with_scheduler(|sched| {
+        for tid in waiters {
+            sched.unblock(tid);
+        }
+    });
+    0
+}
+
+// ============ Lock de lectores y escritores (myrwlock) ============ //
+
+/// Lock que admite varios lectores a la vez o un único escritor.
+///
+/// Prefiere a los escritores: un lector que llega mientras hay un escritor
+/// adentro o esperando se encola, aunque otros estén leyendo. Así una
+/// escritura pendiente no queda postergada por lectores que se turnan. Al
+/// liberarse, el lock pasa primero a los escritores en espera y recién
+/// cuando no queda ninguno a todos los lectores encolados.
+#[derive(Debug, Default)]
+pub struct MyRwLock {
+    readers: usize,
+    writer: Option<MyThreadId>,
+    read_waiters: VecDeque<MyThreadId>,
+    write_waiters: VecDeque<MyThreadId>,
+}
+
+impl MyRwLock {
+    pub fn new() -> Self {
+        MyRwLock {
+            readers: 0,
+            writer: None,
+            read_waiters: VecDeque::new(),
+            write_waiters: VecDeque::new(),
+        }
+    }
+}
+
+/// Inicializa un rwlock.
+pub fn my_rwlock_init(l: &mut MyRwLock) -> c_int {
+    *l = MyRwLock::new();
+    0
+}
+
+/// Destruye un rwlock; `EBUSY` si alguien lo tiene o lo espera.
+pub fn my_rwlock_destroy(l: &mut MyRwLock) -> c_int {
+    if l.readers > 0
+        || l.writer.is_some()
+        || !l.read_waiters.is_empty()
+        || !l.write_waiters.is_empty()
+    {
+        EBUSY
+    } else {
+        0
+    }
+}
+
+/// Toma el lock para leer. Se bloquea si hay un escritor adentro o
+/// esperando, así que un hilo que ya lee y vuelve a pedirlo mientras un
+/// escritor espera se queda esperando a ese escritor.
+///
+/// Devuelve `EDEADLK` si el hilo actual ya lo tiene para escribir.
+pub fn my_rwlock_rdlock(l: &mut MyRwLock) -> c_int {
+    let curr = current_thread("rdlock");
+    match l.writer {
+        None if l.write_waiters.is_empty() => {
+            l.readers += 1;
+            0
+        }
+        Some(writer) if writer == curr => EDEADLK,
+        _ => {
+            l.read_waiters.push_back(curr);
+            block_and_switch(BlockReason::RwLock { lock: l as *const MyRwLock, write: false });
+            // Quien liberó el lock ya nos contó como lectores.
+            debug_assert!(l.readers > 0);
+            0
+        }
+    }
+}
+
+/// Toma el lock para escribir: espera a que no quede ningún lector ni
+/// escritor.
+///
+/// Devuelve `EDEADLK` si el hilo actual ya lo tiene para escribir.
+pub fn my_rwlock_wrlock(l: &mut MyRwLock) -> c_int {
+    let curr = current_thread("wrlock");
+    if l.writer == Some(curr) {
+        return EDEADLK;
+    }
+    if l.writer.is_none() && l.readers == 0 {
+        l.writer = Some(curr);
+        return 0;
+    }
+
+    l.write_waiters.push_back(curr);
+    block_and_switch(BlockReason::RwLock { lock: l as *const MyRwLock, write: true });
+    debug_assert_eq!(l.writer, Some(curr));
+    0
+}
+
+/// Libera el lock, ya sea de lectura o de escritura.
+///
+/// Cuando queda libre se lo pasa directamente al próximo escritor en espera
+/// o, si no hay, a todos los lectores en espera. Devuelve `EINVAL` si el
+/// hilo actual no es el escritor y no hay lectores.
+pub fn my_rwlock_unlock(l: &mut MyRwLock) -> c_int {
+    let curr = current_thread("rwlock_unlock");
+    if l.writer == Some(curr) {
+        l.writer = None;
+    } else if l.writer.is_none() && l.readers > 0 {
+        l.readers -= 1;
+        if l.readers > 0 {
+            return 0;
+        }
+    } else {
+        return EINVAL;
+    }
+
+    with_scheduler(|sched| {
+        if let Some(next) = l.write_waiters.pop_front() {
+            l.writer = Some(next);
+            sched.unblock(next);
+        } else {
+            for tid in mem::take(&mut l.read_waiters) {
+                l.readers += 1;
+                sched.unblock(tid);
+            }
+        }
+    });
+    0
+}
+
+// ============ Semáforo contador (mysem) ============ //
+
+/// Semáforo contador clásico.
+///
+/// Como `MyMutex`, se usa por referencia compartida: el hilo que espera un
+/// permiso y el que lo devuelve usan el mismo `&MySemaphore`.
+#[derive(Debug, Default)]
+pub struct MySemaphore {
+    value: Cell<u32>,
+    waiters: RefCell<VecDeque<MyThreadId>>,
+}
+
+impl MySemaphore {
+    /// Crea un semáforo con `value` permisos. Es `const` para poder usarlo
+    /// en un `static`.
+    pub const fn new(value: u32) -> Self {
+        MySemaphore { value: Cell::new(value), waiters: RefCell::new(VecDeque::new()) }
+    }
+
+    /// Permisos disponibles.
+    pub fn value(&self) -> u32 {
+        self.value.get()
+    }
+}
+
+/// Inicializa un semáforo con `value` permisos.
+pub fn my_sem_init(sem: &mut MySemaphore, value: u32) -> c_int {
+    *sem = MySemaphore::new(value);
+    0
+}
+
+/// Destruye un semáforo; `EBUSY` si hay hilos esperándolo.
+pub fn my_sem_destroy(sem: &mut MySemaphore) -> c_int {
+    if sem.waiters.borrow().is_empty() { 0 } else { EBUSY }
+}
+
+/// Toma un permiso; si no hay, bloquea hasta que otro hilo haga
+/// `my_sem_post`.
+pub fn my_sem_wait(sem: &MySemaphore) -> c_int {
+    if my_sem_trywait(sem) == 0 {
+        return 0;
+    }
+    let curr = current_thread("sem_wait");
+    sem.waiters.borrow_mut().push_back(curr);
+    // `my_sem_post` nos pasa el permiso directamente.
+    block_and_switch(BlockReason::Semaphore { sem: sem as *const MySemaphore });
+    0
+}
+
+/// Toma un permiso si hay alguno; si no, retorna EAGAIN.
+pub fn my_sem_trywait(sem: &MySemaphore) -> c_int {
+    match sem.value.get() {
+        0 => EAGAIN,
+        value => {
+            sem.value.set(value - 1);
+            0
+        }
+    }
+}
+
+/// Devuelve un permiso. Si hay hilos esperando, el permiso pasa al que lleva
+/// más tiempo en la cola y el contador no cambia.
+pub fn my_sem_post(sem: &MySemaphore) -> c_int {
+    let next = sem.waiters.borrow_mut().pop_front();
+    match next {
+        Some(tid) => with_scheduler(|sched| sched.unblock(tid)),
+        None => sem.value.set(sem.value.get() + 1),
+    }
+    0
+}
+
+// ============ Barreras (mybarrier) ============ //
+
+/// Valor que `my_barrier_wait` devuelve a un único hilo de cada grupo (el
+/// último en llegar), para que haga el trabajo posterior a la barrera.
+pub const BARRIER_SERIAL_THREAD: c_int = -1;
+
+/// Barrera que retiene a los hilos hasta que llegan `count`.
+#[derive(Debug, Default)]
+pub struct MyBarrier {
+    count: usize,
+    waiters: Vec<MyThreadId>,
+}
+
+impl MyBarrier {
+    pub fn new(count: usize) -> Self {
+        MyBarrier { count, waiters: Vec::new() }
+    }
+}
+
+/// Inicializa una barrera para grupos de `count` hilos; `EINVAL` si
+/// `count` es 0.
+pub fn my_barrier_init(b: &mut MyBarrier, count: usize) -> c_int {
+    if count == 0 {
+        return EINVAL;
+    }
+    *b = MyBarrier::new(count);
+    0
+}
+
+/// Destruye una barrera; `EBUSY` si hay hilos esperando en ella.
+pub fn my_barrier_destroy(b: &mut MyBarrier) -> c_int {
+    if b.waiters.is_empty() { 0 } else { EBUSY }
+}
+
+/// Vuelve a armar la barrera para grupos de `count` hilos sin destruirla.
+/// Devuelve `EBUSY` si hay hilos esperando y `EINVAL` si `count` es 0.
+pub fn my_barrier_reset(b: &mut MyBarrier, count: usize) -> c_int {
+    if !b.waiters.is_empty() {
+        return EBUSY;
+    }
+    my_barrier_init(b, count)
+}
+
+/// Bloquea hasta que `count` hilos hayan llamado a `my_barrier_wait` y
+/// luego los libera a todos. El último en llegar no se bloquea y recibe
+/// `BARRIER_SERIAL_THREAD`; los demás reciben `0`. La barrera queda lista
+/// para el grupo siguiente.
+pub fn my_barrier_wait(b: &mut MyBarrier) -> c_int {
+    if b.count == 0 {
+        return EINVAL;
+    }
+    let curr = current_thread("barrier_wait");
+    if b.waiters.len() + 1 < b.count {
+        b.waiters.push(curr);
+        block_and_switch(BlockReason::Barrier { barrier: b as *const MyBarrier });
+        return 0;
+    }
+
+    let waiters = mem::take(&mut b.waiters);
+    with_scheduler(|sched| {
+        for tid in waiters {
+            sched.unblock(tid);
+        }
+    });
+    BARRIER_SERIAL_THREAD
+}
+
+// ============ Grupos de espera (mywaitgroup) ============ //
+
+/// Contador de trabajos pendientes: `add` los suma, cada trabajo llama a
+/// `done` al terminar y `wait` bloquea hasta que no quede ninguno. A
+/// diferencia de hacer join, sirve también para hilos detached.
+///
+/// Como `MyMutex`, se usa por referencia compartida.
+#[derive(Debug, Default)]
+pub struct MyWaitGroup {
+    pending: Cell<usize>,
+    waiters: RefCell<Vec<MyThreadId>>,
+}
+
+impl MyWaitGroup {
+    /// Es `const` para poder usarlo en un `static`.
+    pub const fn new() -> Self {
+        MyWaitGroup { pending: Cell::new(0), waiters: RefCell::new(Vec::new()) }
+    }
+
+    /// Trabajos que todavía no llamaron a `done`.
+    pub fn pending(&self) -> usize {
+        self.pending.get()
+    }
+
+    /// Suma `n` trabajos pendientes.
+    pub fn add(&self, n: usize) {
+        self.pending.set(self.pending.get() + n);
+    }
+
+    /// Marca un trabajo como terminado. Si era el último, despierta a todos
+    /// los que esperan. Devuelve `EINVAL` si no había trabajos pendientes.
+    pub fn done(&self) -> c_int {
+        let pending = match self.pending.get().checked_sub(1) {
+            Some(pending) => pending,
+            None => return EINVAL,
+        };
+        self.pending.set(pending);
+        if pending == 0 {
+            let waiters = mem::take(&mut *self.waiters.borrow_mut());
+            with_scheduler(|sched| {
+                for tid in waiters {
+                    sched.unblock(tid);
+                }
+            });
+        }
+        0
+    }
+
+    /// Bloquea al hilo actual hasta que no queden trabajos pendientes.
+    pub fn wait(&self) {
+        if self.pending.get() == 0 {
+            return;
+        }
+        self.waiters.borrow_mut().push(current_thread("wait_group"));
+        block_and_switch(BlockReason::WaitGroup { group: self as *const MyWaitGroup });
+    }
+}
+
+// ============ Spinlock cooperativo (myspinlock) ============ //
+
+/// Lock para secciones críticas muy cortas. En vez de bloquear al hilo en el
+/// scheduler, quien lo espera cede la CPU con `my_thread_yield` y reintenta,
+/// así que no necesita cola de espera.
+///
+/// Solo conviene si la sección crítica dura menos que un quantum y no cede
+/// la CPU por dentro (`my_thread_yield`, `my_thread_sleep`, esperar un mutex,
+/// etc.): mientras el dueño no corre, cada waiter gasta un dispatch por
+/// vuelta sin avanzar. Para secciones largas o que pueden bloquearse, usar
+/// `MyMutex`.
+///
+/// No necesita atómicos: los hilos son cooperativos y corren de a uno sobre
+/// el mismo hilo del sistema, así que la prueba y la toma del lock no se
+/// pueden intercalar con otro hilo.
+#[derive(Debug, Default)]
+pub struct MySpinLock {
+    owner: Option<MyThreadId>,
+}
+
+impl MySpinLock {
+    pub fn new() -> Self {
+        MySpinLock { owner: None }
+    }
+}
+
+/// Inicializa un spinlock.
+pub fn my_spin_init(s: &mut MySpinLock) -> c_int {
+    *s = MySpinLock::new();
+    0
+}
+
+/// Intenta tomar el spinlock; si está ocupado, retorna EBUSY.
+pub fn my_spin_trylock(s: &mut MySpinLock) -> c_int {
+    let curr = current_thread("trylock");
+    if s.owner.is_some() {
+        return EBUSY;
+    }
+    s.owner = Some(curr);
+    0
+}
+
+/// Cede la CPU hasta adquirir el spinlock. Devuelve `EDEADLK` si el hilo
+/// actual ya lo tiene.
+pub fn my_spin_lock(s: &mut MySpinLock) -> c_int {
+    let curr = current_thread("lock");
+
+    loop {
+        match s.owner {
+            None => {
+                s.owner = Some(curr);
+                return 0;
+            }
+            Some(owner) if owner == curr => return EDEADLK,
+            Some(_) => my_thread_yield(),
+        }
+    }
+}
+
+/// Libera el spinlock. Devuelve `EINVAL` si el hilo actual no es el dueño.
+pub fn my_spin_unlock(s: &mut MySpinLock) -> c_int {
+    let curr = current_thread("unlock");
+    if s.owner != Some(curr) {
+        return EINVAL;
+    }
+    s.owner = None;
+    0
+}
+
+/// ============ Inicialización única (myonce) ============ ///
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum OnceState {
+    Incomplete,
+    Running { initializer: MyThreadId },
+    Done,
+    /// El inicializador terminó su hilo sin completar.
+    Poisoned,
+}
+
+/// Equivalente a `pthread_once_t`: garantiza que una función de
+/// inicialización corra una sola vez.
+#[derive(Debug)]
+pub struct MyOnce {
+    state: OnceState,
+    waiters: VecDeque<MyThreadId>,
+}
+
+impl MyOnce {
+    /// Constante, para poder declarar un `static`.
+    pub const fn new() -> Self {
+        MyOnce {
+            state: OnceState::Incomplete,
+            waiters: VecDeque::new(),
+        }
+    }
+}
+
+impl Default for MyOnce {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Ejecuta `init_fn` si ningún hilo lo hizo antes con este `once`.
+///
+/// Los hilos que llegan mientras otro ejecuta el inicializador se bloquean
+/// hasta que termine y vuelven sin ejecutarlo. Devuelve `0` si la
+/// inicialización está completa, o `ENOTRECOVERABLE` si el hilo inicializador
+/// llamó a `my_thread_end` antes de terminarla (a partir de ahí, todas las
+/// llamadas con este `once` fallan).
+pub fn my_once(once: &mut MyOnce, init_fn: extern "C" fn()) -> c_int {
+    once_call(once, || init_fn())
+}
+
+/// Versión de `my_once` que recibe una closure, útil para inicializar
+/// globales de forma perezosa junto a un `static MyOnce` (`MyOnce::new` es
+/// constante).
+///
+/// Devuelve lo mismo que `my_once`. Si `f` entra en pánico, el `once` queda
+/// envenenado (los que esperaban reciben `ENOTRECOVERABLE`) y el pánico
+/// sigue su curso.
+pub fn my_once_call<F: FnOnce()>(once: &mut MyOnce, f: F) -> c_int {
+    once_call(once, f)
+}
+
+fn once_call(once: &mut MyOnce, f: impl FnOnce()) -> c_int {
+    let curr = current_thread("my_once");
+
+    match once.state {
+        OnceState::Done => return 0,
+        OnceState::Poisoned => return ENOTRECOVERABLE,
+        OnceState::Running { initializer } => {
+            let reason = BlockReason::Once { once: once as *const MyOnce, initializer };
+            let err = with_scheduler(|s| s.check_deadlock(curr, reason));
+            if err != 0 {
+                return err;
+            }
+            once.waiters.push_back(curr);
+            block_and_switch(reason);
+            return if once.state == OnceState::Done { 0 } else { ENOTRECOVERABLE };
+        }
+        OnceState::Incomplete => {}
+    }
+
+    once.state = OnceState::Running { initializer: curr };
+    let once_ptr = once as *mut MyOnce;
+    with_scheduler(|s| s.get_thread_mut(curr).unwrap().running_onces.push(once_ptr));
+
+    let outcome = panic::catch_unwind(AssertUnwindSafe(f));
+
+    let waiters = mem::take(&mut once.waiters);
+    once.state = if outcome.is_ok() { OnceState::Done } else { OnceState::Poisoned };
+    with_scheduler(|s| {
+        s.get_thread_mut(curr).unwrap().running_onces.retain(|&o| o != once_ptr);
+        for waiter in waiters {
+            s.unblock(waiter);
+        }
+    });
+    if let Err(payload) = outcome {
+        panic::resume_unwind(payload);
+    }
+    0
+}
+
+// ============ Canales (mychannel) ============ //
+
+/// Error de `MyChannel::send` y `MyChannel::try_send`; devuelve el mensaje
+/// que no se pudo enviar.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SendError<T> {
+    /// El canal está lleno (solo `try_send`).
+    Full(T),
+    /// El canal se cerró.
+    Closed(T),
+}
+
+/// Error de `MyChannel::recv` y `MyChannel::try_recv`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RecvError {
+    /// No hay mensajes (solo `try_recv`).
+    Empty,
+    /// El canal se cerró y ya no quedan mensajes.
+    Closed,
+}
+
+struct ChannelState<T> {
+    buf: VecDeque<T>,
+    capacity: usize,
+    closed: bool,
+    send_waiters: VecDeque<MyThreadId>,
+    recv_waiters: VecDeque<MyThreadId>,
+}
+
+/// Canal de capacidad fija entre hilos de usuario. Es un handle: los clones
+/// comparten el mismo canal, así que cada hilo puede recibir el suyo.
+///
+/// `send` bloquea al hilo mientras el canal está lleno y `recv` mientras
+/// está vacío; cada operación despierta a un hilo del otro lado.
+pub struct MyChannel<T> {
+    state: Rc<RefCell<ChannelState<T>>>,
+}
+
+impl<T> Clone for MyChannel<T> {
+    fn clone(&self) -> Self {
+        MyChannel { state: Rc::clone(&self.state) }
+    }
+}
+
+impl<T> MyChannel<T> {
+    /// Crea un canal con lugar para `capacity` mensajes.
+    ///
+    /// # Panics
+    /// Si `capacity` es 0.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "la capacidad del canal debe ser al menos 1");
+        MyChannel {
+            state: Rc::new(RefCell::new(ChannelState {
+                buf: VecDeque::with_capacity(capacity),
+                capacity,
+                closed: false,
+                send_waiters: VecDeque::new(),
+                recv_waiters: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Envía `value`, bloqueándose mientras el canal esté lleno. Falla con
+    /// `SendError::Closed` si el canal se cierra antes de que haya lugar.
+    pub fn send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut value = value;
+        loop {
+            match self.try_send(value) {
+                Err(SendError::Full(v)) => {
+                    value = v;
+                    self.wait(true);
+                }
+                other => return other,
+            }
+        }
+    }
+
+    /// Envía `value` sin bloquearse.
+    pub fn try_send(&self, value: T) -> Result<(), SendError<T>> {
+        let mut st = self.state.borrow_mut();
+        if st.closed {
+            return Err(SendError::Closed(value));
+        }
+        if st.buf.len() >= st.capacity {
+            return Err(SendError::Full(value));
+        }
+        st.buf.push_back(value);
+        if let Some(tid) = st.recv_waiters.pop_front() {
+            with_scheduler(|s| s.unblock(tid));
+        }
+        Ok(())
+    }
+
+    /// Recibe el próximo mensaje, bloqueándose mientras el canal esté vacío.
+    /// Los mensajes pendientes se siguen entregando después de cerrar el
+    /// canal; cuando se acaban, falla con `RecvError::Closed`.
+    pub fn recv(&self) -> Result<T, RecvError> {
+        loop {
+            match self.try_recv() {
+                Err(RecvError::Empty) => self.wait(false),
+                other => return other,
+            }
+        }
+    }
+
+    /// Recibe un mensaje sin bloquearse.
+    pub fn try_recv(&self) -> Result<T, RecvError> {
+        let mut st = self.state.borrow_mut();
+        match st.buf.pop_front() {
+            Some(value) => {
+                if let Some(tid) = st.send_waiters.pop_front() {
+                    with_scheduler(|s| s.unblock(tid));
+                }
+                Ok(value)
+            }
+            None if st.closed => Err(RecvError::Closed),
+            None => Err(RecvError::Empty),
+        }
+    }
+
+    /// Cierra el canal y despierta a todos los hilos bloqueados en él. Cerrar
+    /// dos veces no tiene efecto.
+    pub fn close(&self) {
+        let st = &mut *self.state.borrow_mut();
+        st.closed = true;
+        let waiters: Vec<_> = st.send_waiters.drain(..).chain(st.recv_waiters.drain(..)).collect();
+        with_scheduler(|s| {
+            for tid in waiters {
+                s.unblock(tid);
+            }
+        });
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.borrow().closed
+    }
+
+    /// Cantidad de mensajes en espera de ser recibidos.
+    pub fn len(&self) -> usize {
+        self.state.borrow().buf.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Bloquea al hilo actual hasta que el otro lado lo despierte. El estado
+    /// del canal no queda prestado mientras tanto.
+    fn wait(&self, sending: bool) {
+        let curr = current_thread("channel");
+        {
+            let mut st = self.state.borrow_mut();
+            let queue = if sending { &mut st.send_waiters } else { &mut st.recv_waiters };
+            queue.push_back(curr);
+        }
+        let chan = Rc::as_ptr(&self.state) as *const c_void;
+        block_and_switch(BlockReason::Channel { chan, sending, forget: forget_channel_waiter::<T> });
+    }
+}
+
+/// Saca a `tid` de las colas del canal `chan`, que apunta al estado de un
+/// `MyChannel<T>` (ver `BlockReason::Channel`).
+fn forget_channel_waiter<T>(chan: *const c_void, tid: MyThreadId) {
+    let state = unsafe { &*(chan as *const RefCell<ChannelState<T>>) };
+    let mut st = state.borrow_mut();
+    st.send_waiters.retain(|&w| w != tid);
+    st.recv_waiters.retain(|&w| w != tid);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    extern "C" fn counting_worker(arg: *mut c_void) -> *mut c_void {
+        let counter = arg as *mut u32;
+        for _ in 0..10 {
+            unsafe { *counter += 1 };
+            my_thread_yield();
+        }
+        arg
+    }
+
+    #[test]
+    fn test_join_reclaims_threads() {
+        let mut counter = 0u32;
+        let counter_ptr = &mut counter as *mut u32;
+        let arg = counter_ptr as *mut c_void;
+
+        let tids: Vec<MyThreadId> = (0..3)
+            .map(|_| my_thread_create(counting_worker, arg, SchedPolicy::RoundRobin))
+            .collect();
+        assert_eq!(my_sched_stats().live_threads, 4);
+
+        for tid in tids {
+            assert_eq!(my_thread_join(tid), arg);
+        }
+
+        assert_eq!(unsafe { *counter_ptr }, 30);
+        assert_eq!(my_sched_stats().live_threads, 1);
+    }
+
+    #[test]
+    fn test_round_robin_stats_are_balanced() {
+        let mut counter = 0u32;
+        let arg = &mut counter as *mut u32 as *mut c_void;
+
+        let a = my_thread_create(counting_worker, arg, SchedPolicy::RoundRobin);
+        let b = my_thread_create(counting_worker, arg, SchedPolicy::RoundRobin);
+
+        // Main, A y B rotan en la cola RR: cada vuelta de main es una de A y B.
+        for _ in 0..5 {
+            my_thread_yield();
+        }
+        let stats_a = my_thread_stats(a).unwrap();
+        let stats_b = my_thread_stats(b).unwrap();
+        assert_eq!(stats_a.run_count, 5);
+        assert_eq!(stats_a.run_count, stats_b.run_count);
+        assert_eq!(stats_a.ready_ticks, stats_b.ready_ticks);
+
+        my_thread_join(a);
+        let stats_b = my_thread_stats(b).unwrap();
+        my_thread_join(b);
+
+        // 10 yields + la ejecución final de cada hilo.
+        assert_eq!(stats_b.run_count, 11);
+        assert_eq!(stats_b.blocked_ticks, 0);
+        assert!(stats_b.ready_ticks > 0);
+        assert!(my_thread_stats(a).is_none());
+
+        let sched = my_sched_stats();
+        assert!(sched.context_switches >= 22);
+        assert_eq!(sched.dispatches, sched.ticks);
+        assert_eq!(
+            sched.dispatches_by_policy[&SchedPolicyKind::RoundRobin],
+            sched.dispatches
+        );
+    }
+
+    struct LogArgs {
+        log: *mut Vec<u32>,
+        id: u32,
+        iterations: u32,
+    }
+
+    extern "C" fn logging_worker(arg: *mut c_void) -> *mut c_void {
+        let args = unsafe { &*(arg as *const LogArgs) };
+        for _ in 0..args.iterations {
+            unsafe { (*args.log).push(args.id) };
+            my_thread_yield();
+        }
+        ptr::null_mut()
+    }
+
+    /// Posición (en dispatches) en que el hilo de 1 ticket corre por primera
+    /// vez compitiendo contra uno de 1000 tickets.
+    fn first_light_dispatch(aging: u32) -> usize {
+        my_sched_set_lottery_aging(aging);
+
+        let mut log = Vec::new();
+        let log_ptr = &mut log as *mut Vec<u32>;
+        let mut heavy = LogArgs { log: log_ptr, id: 1, iterations: 300 };
+        let mut light = LogArgs { log: log_ptr, id: 2, iterations: 1 };
+
+        let h = my_thread_create(
+            logging_worker,
+            &mut heavy as *mut LogArgs as *mut c_void,
+            SchedPolicy::Lottery { tickets: 1000 },
+        );
+        let l = my_thread_create(
+            logging_worker,
+            &mut light as *mut LogArgs as *mut c_void,
+            SchedPolicy::Lottery { tickets: 1 },
+        );
+        my_thread_join(h);
+        my_thread_join(l);
+        my_sched_set_lottery_aging(0);
+
+        let log = unsafe { &*log_ptr };
+        log.iter().position(|&id| id == 2).unwrap()
+    }
+
+    #[test]
+    fn test_lottery_aging_prevents_starvation() {
+        let without_aging = first_light_dispatch(0);
+        let with_aging = first_light_dispatch(100);
+
+        assert!(with_aging <= 30, "con aging corrió en el dispatch {with_aging}");
+        assert!(with_aging < without_aging);
+    }
+
+    #[test]
+    fn test_lottery_aging_resets_to_base_tickets() {
+        my_sched_set_lottery_aging(10);
+        let rounds = Rc::new(Cell::new(0u32));
+        let heavy = {
+            let rounds = Rc::clone(&rounds);
+            my_thread_spawn(
+                move || {
+                    for _ in 0..200 {
+                        rounds.set(rounds.get() + 1);
+                        my_thread_yield();
+                    }
+                },
+                SchedPolicy::Lottery { tickets: 100 },
+            )
+        };
+        let seen = Rc::new(Cell::new(None));
+        let light = {
+            let seen = Rc::clone(&seen);
+            my_thread_spawn(
+                move || {
+                    let me = my_thread_self();
+                    let bonus = with_scheduler(|s| s.threads[&me].bonus_tickets);
+                    seen.set(Some((rounds.get(), bonus, my_thread_getsched(me))));
+                },
+                SchedPolicy::Lottery { tickets: 1 },
+            )
+        };
+        my_thread_join(heavy);
+        my_thread_join(light);
+        my_sched_set_lottery_aging(0);
+
+        // Cada sorteo perdido suma 10 tickets: el hilo de 1 ticket no puede
+        // esperar mucho más que 100 / 10 rondas.
+        let (rounds, bonus, policy) = seen.get().unwrap();
+        assert!(rounds <= 30, "el hilo de 1 ticket corrió tras {rounds} rondas");
+        assert_eq!(bonus, 0);
+        assert_eq!(policy, Ok(SchedPolicy::Lottery { tickets: 1 }));
+    }
+
+    struct MlfqArgs {
+        mutex: *mut MyMutex,
+        tid: MyThreadId,
+        iterations: u32,
+        levels: Vec<usize>,
+    }
+
+    fn record_level(args: &mut MlfqArgs) {
+        let level = my_thread_stats(args.tid).unwrap().mlfq_level.unwrap();
+        args.levels.push(level);
+    }
+
+    /// Nunca se bloquea: toma el mutex y cede la CPU mientras lo tiene.
+    extern "C" fn cpu_bound_worker(arg: *mut c_void) -> *mut c_void {
+        let args = unsafe { &mut *(arg as *mut MlfqArgs) };
+        for _ in 0..args.iterations {
+            my_mutex_lock(unsafe { &mut *args.mutex });
+            my_thread_yield();
+            my_mutex_unlock(unsafe { &mut *args.mutex });
+            record_level(args);
+            my_thread_yield();
+        }
+        ptr::null_mut()
+    }
+
+    /// Se bloquea en el mutex casi en cada iteración.
+    extern "C" fn interactive_worker(arg: *mut c_void) -> *mut c_void {
+        let args = unsafe { &mut *(arg as *mut MlfqArgs) };
+        for _ in 0..args.iterations {
+            my_mutex_lock(unsafe { &mut *args.mutex });
+            my_mutex_unlock(unsafe { &mut *args.mutex });
+            record_level(args);
+            my_thread_yield();
+        }
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_mlfq_demotes_cpu_bound_and_keeps_interactive_on_top() {
+        let mut mutex = MyMutex::new();
+        let mutex_ptr = &mut mutex as *mut MyMutex;
+        let mut cpu = MlfqArgs { mutex: mutex_ptr, tid: 0, iterations: 60, levels: Vec::new() };
+        let mut interactive = MlfqArgs { mutex: mutex_ptr, tid: 0, iterations: 10, levels: Vec::new() };
+
+        cpu.tid = my_thread_create(
+            cpu_bound_worker,
+            &mut cpu as *mut MlfqArgs as *mut c_void,
+            SchedPolicy::Mlfq,
+        );
+        interactive.tid = my_thread_create(
+            interactive_worker,
+            &mut interactive as *mut MlfqArgs as *mut c_void,
+            SchedPolicy::Mlfq,
+        );
+        my_thread_join(cpu.tid);
+        my_thread_join(interactive.tid);
+
+        assert!(interactive.levels.iter().all(|&level| level == 0));
+
+        // El hilo CPU-bound se hunde hasta el último nivel...
+        let bottom = cpu.levels.iter().position(|&level| level == MLFQ_LEVELS - 1);
+        let bottom = bottom.expect("el hilo CPU-bound nunca llegó al último nivel");
+        // ...hasta que el boost periódico lo devuelve al nivel 0.
+        assert!(cpu.levels[bottom..].contains(&0));
+    }
+
+    #[test]
+    fn test_fifo_runs_in_strict_priority_order() {
+        let mut log = Vec::new();
+        let log_ptr = &mut log as *mut Vec<u32>;
+        let mut args: Vec<LogArgs> = (1..=3)
+            .map(|id| LogArgs { log: log_ptr, id, iterations: 5 })
+            .collect();
+
+        let mut tids = Vec::new();
+        for (arg, priority) in args.iter_mut().zip([1, 9, 5]) {
+            tids.push(my_thread_create(
+                logging_worker,
+                arg as *mut LogArgs as *mut c_void,
+                SchedPolicy::Fifo { priority },
+            ));
+        }
+        for tid in tids {
+            my_thread_join(tid);
+        }
+
+        let log = unsafe { &*log_ptr };
+        assert_eq!(log, &[2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 1, 1, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_fifo_equal_priority_runs_in_creation_order() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let tids: Vec<_> = (1..=5)
+            .map(|id| {
+                let log = Rc::clone(&log);
+                my_thread_spawn(
+                    move || {
+                        log.borrow_mut().push(id);
+                        // Ceder lo pasa al final de los de su prioridad.
+                        my_thread_yield();
+                        log.borrow_mut().push(id);
+                    },
+                    SchedPolicy::Fifo { priority: 0 },
+                )
+            })
+            .collect();
+        for tid in tids {
+            my_thread_join(tid);
+        }
+        assert_eq!(*log.borrow(), [1, 2, 3, 4, 5, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_policy_precedence() {
+        // Todos los hilos solo ceden la CPU: RealTime > Fifo > Lottery > RR.
+        let mut log = Vec::new();
+        let log_ptr = &mut log as *mut Vec<u32>;
+        let mut args: Vec<LogArgs> = (1..=4)
+            .map(|id| LogArgs { log: log_ptr, id, iterations: 2 })
+            .collect();
+        let policies = [
+            SchedPolicy::RoundRobin,
+            SchedPolicy::Lottery { tickets: 10 },
+            SchedPolicy::Fifo { priority: 0 },
+            SchedPolicy::RealTime { deadline: 100 },
+        ];
+
+        let mut tids = Vec::new();
+        for (arg, policy) in args.iter_mut().zip(policies) {
+            tids.push(my_thread_create(
+                logging_worker,
+                arg as *mut LogArgs as *mut c_void,
+                policy,
+            ));
+        }
+        for tid in tids {
+            my_thread_join(tid);
+        }
+
+        let log = unsafe { &*log_ptr };
+        assert_eq!(log, &[4, 4, 3, 3, 2, 2, 1, 1]);
+    }
+
+    thread_local! {
+        static MISSES: std::cell::RefCell<Vec<(MyThreadId, u64)>> =
+            const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    fn record_miss(tid: MyThreadId, lateness: u64) {
+        MISSES.with(|m| m.borrow_mut().push((tid, lateness)));
+    }
+
+    /// Toma el mutex y lo retiene durante una ráfaga de yields.
+    extern "C" fn mutex_hog_worker(arg: *mut c_void) -> *mut c_void {
+        let mutex = unsafe { &mut *(arg as *mut MyMutex) };
+        my_mutex_lock(mutex);
+        for _ in 0..20 {
+            my_thread_yield();
+        }
+        my_mutex_unlock(mutex);
+        ptr::null_mut()
+    }
+
+    extern "C" fn lock_once_worker(arg: *mut c_void) -> *mut c_void {
+        let mutex = unsafe { &mut *(arg as *mut MyMutex) };
+        my_mutex_lock(mutex);
+        my_mutex_unlock(mutex);
+        my_thread_yield();
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_realtime_equal_deadlines_run_in_arrival_order() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let tids: Vec<_> = (0..3)
+            .map(|id| {
+                let log = Rc::clone(&log);
+                my_thread_spawn(
+                    move || {
+                        for _ in 0..3 {
+                            log.borrow_mut().push(id);
+                            my_thread_yield();
+                        }
+                    },
+                    SchedPolicy::RealTime { deadline: 5 },
+                )
+            })
+            .collect();
+        // Uno con deadline menor llega último pero pasa adelante.
+        let urgent = {
+            let log = Rc::clone(&log);
+            my_thread_spawn(move || log.borrow_mut().push(9), SchedPolicy::RealTime { deadline: 1 })
+        };
+        // Bajar a 1 el del hilo 2 lo reencola detrás de `urgent`.
+        assert_eq!(my_thread_set_realtime_deadline(tids[2], 1), 0);
+        my_thread_yield();
+        for tid in tids.into_iter().chain([urgent]) {
+            my_thread_join(tid);
+        }
+
+        // Cada yield reencola al hilo detrás de los de igual deadline.
+        assert_eq!(*log.borrow(), [9, 2, 2, 2, 0, 1, 0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_realtime_deadline_miss_fires_once() {
+        my_sched_on_deadline_miss(record_miss);
+        let mut mutex = MyMutex::new();
+        let mutex_ptr = &mut mutex as *mut MyMutex as *mut c_void;
+
+        let hog = my_thread_create(mutex_hog_worker, mutex_ptr, SchedPolicy::RoundRobin);
+        // Main cede para que el hilo RR tome el mutex antes de crear el RT.
+        my_thread_yield();
+        let rt = my_thread_create(
+            lock_once_worker,
+            mutex_ptr,
+            SchedPolicy::RealTime { deadline: 5 },
+        );
+        let created_at = my_sched_stats().ticks;
+
+        my_thread_join(hog);
+        assert_eq!(my_thread_deadline_misses(rt), Some(1));
+        my_thread_join(rt);
+
+        let misses = MISSES.with(|m| m.borrow().clone());
+        assert_eq!(misses.len(), 1);
+        let (tid, lateness) = misses[0];
+        assert_eq!(tid, rt);
+        // El RT estuvo bloqueado durante toda la ráfaga del hilo RR.
+        assert!(lateness > 10);
+        assert!(created_at + 5 + lateness <= my_sched_stats().ticks);
+    }
+
+    struct TicketArgs {
+        log: *mut Vec<u32>,
+        id: u32,
+        tid: MyThreadId,
+        boost_at: Option<u32>,
+    }
+
+    extern "C" fn ticket_worker(arg: *mut c_void) -> *mut c_void {
+        let args = unsafe { &*(arg as *const TicketArgs) };
+        for i in 0..400 {
+            if args.boost_at == Some(i) {
+                assert_eq!(my_thread_set_tickets(args.tid, 20), 0);
+            }
+            unsafe { (*args.log).push(args.id) };
+            my_thread_yield();
+        }
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_lottery_tree_matches_linear_scan() {
+        let mut class = LotteryClass::new(DEFAULT_SEED);
+        for tid in 1..=200 {
+            class.push(tid, (tid % 7) as u64);
+        }
+        // Sacar la mayoría fuerza una compactación a mitad de camino.
+        for tid in (1..=200).filter(|tid| tid % 3 != 0) {
+            class.remove(tid);
+        }
+        class.update(3, 50);
+
+        let live: Vec<(MyThreadId, u64)> =
+            class.ready().map(|tid| (tid, if tid == 3 { 50 } else { (tid % 7) as u64 })).collect();
+        assert!(class.slots.len() < 200);
+        assert_eq!(class.total, live.iter().map(|&(_, w)| w).sum::<u64>());
+        for r in 0..class.total {
+            let mut rest = r;
+            let expected = live
+                .iter()
+                .find(|&&(_, w)| {
+                    if rest < w {
+                        return true;
+                    }
+                    rest -= w;
+                    false
+                })
+                .unwrap()
+                .0;
+            assert_eq!(class.slots[class.tree.find(r)], Some(expected), "r={r}");
+        }
+    }
+
+    struct ShareArgs {
+        counts: *mut [u64; 4],
+        index: usize,
+    }
+
+    extern "C" fn share_worker(arg: *mut c_void) -> *mut c_void {
+        let args = unsafe { &*(arg as *const ShareArgs) };
+        let counts = args.counts;
+        while unsafe { (*counts).iter().sum::<u64>() } < 20_000 {
+            unsafe { (*counts)[args.index] += 1 };
+            my_thread_yield();
+        }
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_lottery_wins_proportional_to_tickets() {
+        let mut counts = [0u64; 4];
+        let mut args: Vec<ShareArgs> =
+            (0..4).map(|index| ShareArgs { counts: &mut counts, index }).collect();
+        let tids: Vec<_> = args
+            .iter_mut()
+            .enumerate()
+            .map(|(i, a)| {
+                let arg = a as *mut ShareArgs as *mut c_void;
+                my_thread_create(share_worker, arg, SchedPolicy::Lottery { tickets: i as u32 + 1 })
+            })
+            .collect();
+        for tid in tids {
+            my_thread_join(tid);
+        }
+
+        // Chi-cuadrado con 3 grados de libertad: 16.27 es el valor crítico
+        // para p = 0.001.
+        let total: u64 = counts.iter().sum();
+        let chi2: f64 = counts
+            .iter()
+            .enumerate()
+            .map(|(i, &observed)| {
+                let expected = total as f64 * (i + 1) as f64 / 10.0;
+                (observed as f64 - expected).powi(2) / expected
+            })
+            .sum();
+        assert!(chi2 < 16.27, "chi2={chi2:.2} counts={counts:?}");
+    }
+
+    #[test]
+    fn test_groups_split_dispatches_by_weight() {
+        let heavy = my_sched_group_create(3);
+        let light = my_sched_group_create(1);
+        assert_eq!(my_thread_set_group(0, 99), EINVAL);
+        assert_eq!(my_thread_set_group(999, heavy), EINVAL);
+        assert_eq!(my_sched_group_set_weight(99, 1), EINVAL);
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut tids = Vec::new();
+        for group in [heavy, light] {
+            // Distintas políticas dentro de cada grupo: el reparto lo decide
+            // el peso, no la cantidad de hilos ni su clase.
+            for policy in [SchedPolicy::RoundRobin, SchedPolicy::Lottery { tickets: 5 }] {
+                for _ in 0..2 {
+                    let log = Rc::clone(&log);
+                    let tid = my_thread_spawn(
+                        move || {
+                            while log.borrow().len() < 2000 {
+                                log.borrow_mut().push(group);
+                                my_thread_yield();
+                            }
+                        },
+                        policy,
+                    );
+                    assert_eq!(my_thread_set_group(tid, group), 0);
+                    tids.push(tid);
+                }
+            }
+        }
+        let before = my_sched_stats().dispatches_by_group;
+        for tid in tids {
+            my_thread_join(tid);
+        }
+        let after = my_sched_stats().dispatches_by_group;
+
+        let log = log.borrow();
+        let share = log.iter().filter(|&&g| g == heavy).count() as f64 / log.len() as f64;
+        assert!((0.70..0.80).contains(&share), "share={share:.3}");
+        let dispatched = |g| after[&g] - before.get(&g).copied().unwrap_or(0);
+        let share = dispatched(heavy) as f64 / (dispatched(heavy) + dispatched(light)) as f64;
+        assert!((0.70..0.80).contains(&share), "dispatches={share:.3}");
+    }
+
+    #[test]
+    fn test_set_tickets_changes_share() {
+        let mut log = Vec::new();
+        let log_ptr = &mut log as *mut Vec<u32>;
+        let mut boosted = TicketArgs { log: log_ptr, id: 1, tid: 0, boost_at: Some(100) };
+        let mut other = TicketArgs { log: log_ptr, id: 2, tid: 0, boost_at: None };
+
+        boosted.tid = my_thread_create(
+            ticket_worker,
+            &mut boosted as *mut TicketArgs as *mut c_void,
+            SchedPolicy::Lottery { tickets: 10 },
+        );
+        other.tid = my_thread_create(
+            ticket_worker,
+            &mut other as *mut TicketArgs as *mut c_void,
+            SchedPolicy::Lottery { tickets: 10 },
+        );
+        my_thread_join(boosted.tid);
+        my_thread_join(other.tid);
+
+        let log = unsafe { &*log_ptr };
+        let share = |window: &[u32]| {
+            window.iter().filter(|&&id| id == 1).count() as f64 / window.len() as f64
+        };
+        let before = share(&log[..150]);
+        let after = share(&log[250..450]);
+        assert!(after > before + 0.1, "antes={before:.2} después={after:.2}");
+
+        assert_eq!(my_thread_set_tickets(0, 5), EINVAL);
+        assert_eq!(my_thread_set_tickets(999, 5), EINVAL);
+    }
+
+    #[test]
+    fn test_donate_tickets_moves_base_tickets() {
+        let a = my_thread_spawn(|| {}, SchedPolicy::Lottery { tickets: 10 });
+        let b = my_thread_spawn(|| {}, SchedPolicy::Lottery { tickets: 2 });
+        let rr = my_thread_spawn(|| {}, SchedPolicy::RoundRobin);
+        let tickets = |tid| match my_thread_getsched(tid) {
+            Ok(SchedPolicy::Lottery { tickets }) => tickets,
+            other => panic!("política inesperada: {other:?}"),
+        };
+
+        assert_eq!(my_thread_donate_tickets(a, b, 4), 0);
+        assert_eq!((tickets(a), tickets(b)), (6, 6));
+
+        // `a` conserva un ticket y `b` recibe solo lo que se le quitó.
+        assert_eq!(my_thread_donate_tickets(a, b, 100), 0);
+        assert_eq!((tickets(a), tickets(b)), (1, 11));
+
+        assert_eq!(my_thread_donate_tickets(b, rr, 1), EINVAL);
+        assert_eq!(my_thread_donate_tickets(0, b, 1), EINVAL);
+        assert_eq!(my_thread_donate_tickets(b, 999, 1), EINVAL);
+        assert_eq!(tickets(b), 11);
+
+        for tid in [a, b, rr] {
+            my_thread_join(tid);
+        }
+    }
+
+    struct DonationShared {
+        log: Vec<u32>,
+        mutex: MyMutex,
+        held: bool,
+        owner_done: bool,
+        owner_tid: MyThreadId,
+        donated_while_held: u32,
+    }
+
+    /// Dueño del mutex con 1 ticket: registra 1 mientras lo tiene y 3 después.
+    extern "C" fn donation_owner(arg: *mut c_void) -> *mut c_void {
+        let shared = arg as *mut DonationShared;
+        unsafe {
+            my_mutex_lock(&(*shared).mutex);
+            (*shared).held = true;
+            for _ in 0..200 {
+                (*shared).log.push(1);
+                my_thread_yield();
+            }
+            let owner_tid = (*shared).owner_tid;
+            (*shared).donated_while_held =
+                with_scheduler(|s| s.threads[&owner_tid].donated_tickets);
+            my_mutex_unlock(&(*shared).mutex);
+            for _ in 0..200 {
+                (*shared).log.push(3);
+                my_thread_yield();
+            }
+            (*shared).owner_done = true;
+        }
+        ptr::null_mut()
+    }
+
+    /// Hilo pesado que espera el mutex del dueño.
+    extern "C" fn donation_waiter(arg: *mut c_void) -> *mut c_void {
+        let shared = arg as *mut DonationShared;
+        unsafe {
+            // Esperamos a que el dueño tome el mutex.
+            while !(*shared).held {
+                (*shared).log.push(0);
+                my_thread_yield();
+            }
+            my_mutex_lock(&(*shared).mutex);
+            my_mutex_unlock(&(*shared).mutex);
+        }
+        ptr::null_mut()
+    }
+
+    /// Competidor que corre mientras el dueño siga vivo.
+    extern "C" fn donation_competitor(arg: *mut c_void) -> *mut c_void {
+        let shared = arg as *mut DonationShared;
+        unsafe {
+            while !(*shared).owner_done {
+                (*shared).log.push(2);
+                my_thread_yield();
+            }
+        }
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_ticket_donation_boosts_mutex_owner() {
+        let mut shared = DonationShared {
+            log: Vec::new(),
+            mutex: MyMutex::new(),
+            held: false,
+            owner_done: false,
+            owner_tid: 0,
+            donated_while_held: 0,
+        };
+        let arg = &mut shared as *mut DonationShared as *mut c_void;
+
+        let owner = my_thread_create(donation_owner, arg, SchedPolicy::Lottery { tickets: 1 });
+        unsafe { (*(arg as *mut DonationShared)).owner_tid = owner };
+        let waiter = my_thread_create(donation_waiter, arg, SchedPolicy::Lottery { tickets: 100 });
+        let competitor =
+            my_thread_create(donation_competitor, arg, SchedPolicy::Lottery { tickets: 10 });
+        my_thread_join(owner);
+        my_thread_join(waiter);
+        my_thread_join(competitor);
+
+        let shared = unsafe { &*(arg as *const DonationShared) };
+        // Fracción de dispatches del dueño en la ventana en que registró `id`.
+        let share = |id: u32| {
+            let first = shared.log.iter().position(|&x| x == id).unwrap();
+            let last = shared.log.iter().rposition(|&x| x == id).unwrap();
+            let window = &shared.log[first..=last];
+            window.iter().filter(|&&x| x == id).count() as f64 / window.len() as f64
+        };
+
+        assert_eq!(shared.donated_while_held, 100);
+        assert!(share(1) > 0.5, "con donación: {:.2}", share(1));
+        assert!(share(3) < 0.3, "sin donación: {:.2}", share(3));
+    }
+
+    #[test]
+    fn test_detached_threads_are_reclaimed() {
+        let mut counter = 0u32;
+        let counter_ptr = &mut counter as *mut u32;
+        let arg = counter_ptr as *mut c_void;
+
+        for _ in 0..5 {
+            let tid = my_thread_create(counting_worker, arg, SchedPolicy::RoundRobin);
+            assert_eq!(my_thread_detach(tid), 0);
+        }
+
+        // Nadie hace join: main cede la CPU hasta que todos terminan.
+        for _ in 0..20 {
+            my_thread_yield();
+        }
+
+        assert_eq!(unsafe { *counter_ptr }, 50);
+        assert_eq!(my_sched_stats().live_threads, 1);
+    }
+
+    struct DeadlockShared {
+        a: MyMutex,
+        b: MyMutex,
+        second_lock: [c_int; 2],
+    }
+
+    /// Toma `a` y luego `b`.
+    extern "C" fn lock_a_then_b(arg: *mut c_void) -> *mut c_void {
+        let shared = arg as *mut DeadlockShared;
+        unsafe {
+            my_mutex_lock(&(*shared).a);
+            my_thread_yield();
+            (*shared).second_lock[0] = my_mutex_lock(&(*shared).b);
+            my_mutex_unlock(&(*shared).b);
+            my_mutex_unlock(&(*shared).a);
+        }
+        ptr::null_mut()
+    }
+
+    /// Toma `b` y luego `a`: el orden opuesto.
+    extern "C" fn lock_b_then_a(arg: *mut c_void) -> *mut c_void {
+        let shared = arg as *mut DeadlockShared;
+        unsafe {
+            my_mutex_lock(&(*shared).b);
+            my_thread_yield();
+            (*shared).second_lock[1] = my_mutex_lock(&(*shared).a);
+            if (*shared).second_lock[1] == 0 {
+                my_mutex_unlock(&(*shared).a);
+            }
+            my_mutex_unlock(&(*shared).b);
+        }
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_opposite_lock_order_reports_edeadlk() {
+        my_sched_set_deadlock_errors(true);
+
+        let mut shared = DeadlockShared {
+            a: MyMutex::new(),
+            b: MyMutex::new(),
+            second_lock: [-1, -1],
+        };
+        let arg = &mut shared as *mut DeadlockShared as *mut c_void;
+
+        let t1 = my_thread_create(lock_a_then_b, arg, SchedPolicy::RoundRobin);
+        let t2 = my_thread_create(lock_b_then_a, arg, SchedPolicy::RoundRobin);
+        my_thread_join(t1);
+        my_thread_join(t2);
+
+        // El primero se bloquea esperando a `b`; el segundo cerraría el ciclo.
+        let shared = unsafe { &*(arg as *const DeadlockShared) };
+        assert_eq!(shared.second_lock, [0, EDEADLK]);
+
+        // Tomar dos veces el mismo mutex también es un ciclo.
+        let m = MyMutex::new();
+        assert_eq!(my_mutex_lock(&m), 0);
+        assert_eq!(my_mutex_lock(&m), EDEADLK);
+        assert_eq!(my_mutex_unlock(&m), 0);
+    }
+
+    const LOCK_BENCH_THREADS: usize = 4;
+    const LOCK_BENCH_PAIRS: usize = 10_000;
+
+    struct LockBench {
+        spin: MySpinLock,
+        mutex: MyMutex,
+        counter: usize,
+    }
+
+    extern "C" fn spin_bench_worker(arg: *mut c_void) -> *mut c_void {
+        let bench = arg as *mut LockBench;
+        for _ in 0..LOCK_BENCH_PAIRS / LOCK_BENCH_THREADS {
+            unsafe {
+                my_spin_lock(&mut (*bench).spin);
+                (*bench).counter += 1;
+                my_spin_unlock(&mut (*bench).spin);
+            }
+            my_thread_yield();
+        }
+        ptr::null_mut()
+    }
+
+    extern "C" fn mutex_bench_worker(arg: *mut c_void) -> *mut c_void {
+        let bench = arg as *mut LockBench;
+        for _ in 0..LOCK_BENCH_PAIRS / LOCK_BENCH_THREADS {
+            unsafe {
+                my_mutex_lock(&(*bench).mutex);
+                (*bench).counter += 1;
+                my_mutex_unlock(&(*bench).mutex);
+            }
+            my_thread_yield();
+        }
+        ptr::null_mut()
+    }
+
+    /// Corre `worker` en 4 hilos RR y devuelve el tiempo total.
+    fn run_lock_bench(worker: ThreadFunc) -> std::time::Duration {
+        let mut bench = LockBench { spin: MySpinLock::new(), mutex: MyMutex::new(), counter: 0 };
+        let arg = &mut bench as *mut LockBench as *mut c_void;
+
+        let start = std::time::Instant::now();
+        let tids: Vec<MyThreadId> = (0..LOCK_BENCH_THREADS)
+            .map(|_| my_thread_create(worker, arg, SchedPolicy::RoundRobin))
+            .collect();
+        for tid in tids {
+            my_thread_join(tid);
+        }
+        let elapsed = start.elapsed();
+
+        assert_eq!(unsafe { (*(arg as *mut LockBench)).counter }, LOCK_BENCH_PAIRS);
+        elapsed
+    }
+
+    #[test]
+    fn test_spinlock_vs_mutex_bench() {
+        let spin = run_lock_bench(spin_bench_worker);
+        let mutex = run_lock_bench(mutex_bench_worker);
+        println!(
+            "{LOCK_BENCH_PAIRS} pares lock/unlock con {LOCK_BENCH_THREADS} hilos RR: \
+             spinlock {:?} ({:?}/par), mutex {:?} ({:?}/par)",
+            spin,
+            spin / LOCK_BENCH_PAIRS as u32,
+            mutex,
+            mutex / LOCK_BENCH_PAIRS as u32,
+        );
+
+        let mut s = MySpinLock::new();
+        assert_eq!(my_spin_unlock(&mut s), EINVAL);
+        assert_eq!(my_spin_lock(&mut s), 0);
+        assert_eq!(my_spin_lock(&mut s), EDEADLK);
+        assert_eq!(my_spin_trylock(&mut s), EBUSY);
+        assert_eq!(my_spin_unlock(&mut s), 0);
+    }
+
+    #[test]
+    fn test_bench_helpers_join_their_threads() {
+        let live = my_sched_live_threads();
+        for _ in 0..3 {
+            bench::measure_switch_latency(100);
+            bench::measure_mutex_roundtrip(100);
+            bench::measure_realtime_dispatch(50, 10);
+        }
+        assert_eq!(my_sched_live_threads(), live);
+        assert_eq!(bench::measure_switch_latency(0), std::time::Duration::ZERO);
+    }
+
+    fn log_cleanup(arg: *mut c_void) {
+        let args = arg as *mut LogArgs;
+        unsafe { (*(*args).log).push((*args).id) };
+    }
+
+    /// Registra tres handlers, descarta uno con pop y muere con `my_thread_end`.
+    extern "C" fn cleanup_worker(arg: *mut c_void) -> *mut c_void {
+        let handlers = arg as *mut [LogArgs; 3];
+        unsafe {
+            my_cleanup_push(log_cleanup, &mut (*handlers)[0] as *mut LogArgs as *mut c_void);
+            my_cleanup_push(log_cleanup, &mut (*handlers)[1] as *mut LogArgs as *mut c_void);
+            my_cleanup_push(log_cleanup, &mut (*handlers)[2] as *mut LogArgs as *mut c_void);
+        }
+        my_thread_yield();
+        my_cleanup_pop(false);
+        my_thread_end(ptr::null_mut());
+    }
+
+    #[test]
+    fn test_cleanup_handlers_run_in_reverse_order() {
+        let mut log: Vec<u32> = Vec::new();
+        let log_ptr = &mut log as *mut Vec<u32>;
+        let mut handlers = [1, 2, 3].map(|id| LogArgs { log: log_ptr, id, iterations: 0 });
+        let arg = &mut handlers as *mut [LogArgs; 3] as *mut c_void;
+
+        let tid = my_thread_create(cleanup_worker, arg, SchedPolicy::RoundRobin);
+        my_thread_join(tid);
+        assert_eq!(unsafe { &*log_ptr }, &[2, 1]);
+
+        // pop(true) ejecuta el handler en el momento.
+        my_cleanup_push(log_cleanup, arg);
+        my_cleanup_pop(true);
+        assert_eq!(unsafe { &*log_ptr }, &[2, 1, 1]);
+    }
+
+    const OVERFLOW_CHILD_ENV: &str = "MYPTHREADS_OVERFLOW_CHILD";
+
+    /// Recursión con marcos grandes hasta entrar en la zona del canario.
+    #[inline(never)]
+    fn recurse_into_canary(stack_bottom: usize) {
+        let frame = std::hint::black_box([0xFFu8; 256]);
+        if (frame.as_ptr() as usize) > stack_bottom + STACK_CANARY_LEN / 2 {
+            recurse_into_canary(stack_bottom);
+        }
+        std::hint::black_box(&frame);
+    }
+
+    extern "C" fn overflowing_worker(_arg: *mut c_void) -> *mut c_void {
+        let bottom = with_scheduler(|s| {
+            let tid = s.current_thread_id().unwrap();
+            s.threads[&tid].stack.as_ptr() as usize
+        });
+        recurse_into_canary(bottom);
+        my_thread_yield();
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_stack_overflow_is_detected() {
+        if std::env::var_os(OVERFLOW_CHILD_ENV).is_some() {
+            assert_eq!(my_sched_set_stack_size(MIN_STACK_SIZE), 0);
+            let tid = my_thread_create(overflowing_worker, ptr::null_mut(), SchedPolicy::RoundRobin);
+            my_thread_join(tid);
+            return;
+        }
+
+        assert_eq!(my_sched_set_stack_size(1024), EINVAL);
+
+        // El desbordamiento aborta el proceso: lo corremos en un hijo.
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "tests::test_stack_overflow_is_detected", "--nocapture"])
+            .env(OVERFLOW_CHILD_ENV, "1")
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success());
+        assert!(
+            stderr.contains("desbordamiento de pila en el hilo 1 (pila de 16384 bytes)"),
+            "{stderr}"
+        );
+    }
+
+    #[test]
+    fn test_max_threads_limit() {
+        let mut counter = 0u32;
+        let arg = &mut counter as *mut u32 as *mut c_void;
+
+        assert_eq!(my_sched_set_max_threads(0), EINVAL);
+        assert_eq!(my_sched_set_max_threads(3), 0);
+        assert_eq!(my_sched_live_threads(), 1);
+
+        let a = my_thread_try_create(counting_worker, arg, SchedPolicy::RoundRobin).unwrap();
+        let b = my_thread_try_create(counting_worker, arg, SchedPolicy::RoundRobin).unwrap();
+        let err = my_thread_try_create(counting_worker, arg, SchedPolicy::RoundRobin).unwrap_err();
+        assert_eq!(err, ThreadError::ResourceLimit { max_threads: 3 });
+        assert_eq!(err.errno(), EAGAIN);
+        assert_eq!(my_sched_live_threads(), 3);
+
+        // El join libera el TCB y deja lugar para otro hilo.
+        my_thread_join(a);
+        assert_eq!(my_sched_live_threads(), 2);
+        let c = my_thread_try_create(counting_worker, arg, SchedPolicy::RoundRobin).unwrap();
+        my_thread_join(b);
+        my_thread_join(c);
+        assert_eq!(counter, 30);
+    }
+
+    #[test]
+    fn test_try_create_rejects_without_leaking() {
+        let mut counter = 0u32;
+        let arg = &mut counter as *mut u32 as *mut c_void;
+        let live = my_sched_live_threads();
+
+        for policy in [SchedPolicy::Lottery { tickets: 0 }, SchedPolicy::RealTime { deadline: 0 }] {
+            let err = my_thread_try_create(counting_worker, arg, policy).unwrap_err();
+            assert_eq!(err, ThreadError::InvalidPolicy(policy));
+            assert_eq!(err.errno(), EINVAL);
+        }
+
+        assert_eq!(my_sched_set_stack_size(usize::MAX / 2), 0);
+        let err = my_thread_try_create(counting_worker, arg, SchedPolicy::RoundRobin).unwrap_err();
+        assert_eq!(err, ThreadError::StackAllocation { size: usize::MAX / 2 });
+        assert_eq!(my_sched_set_stack_size(DEFAULT_STACK_SIZE), 0);
+
+        assert_eq!(my_sched_live_threads(), live);
+        assert_eq!(with_scheduler(|s| s.threads.len()), live);
+
+        // El wrapper de compatibilidad conserva el redondeo de 0 tickets a 1.
+        let tid = my_thread_create(counting_worker, arg, SchedPolicy::Lottery { tickets: 0 });
+        assert_eq!(my_thread_stats(tid).unwrap().policy, SchedPolicy::Lottery { tickets: 1 });
+        my_thread_join(tid);
+        assert_eq!(counter, 10);
+    }
+
+    #[test]
+    fn test_chsched_on_finished_thread_fails() {
+        let mut counter = 0u32;
+        let arg = &mut counter as *mut u32 as *mut c_void;
+
+        let tid = my_thread_create(counting_worker, arg, SchedPolicy::RoundRobin);
+        for _ in 0..20 {
+            my_thread_yield();
+        }
+        assert_eq!(unsafe { *(arg as *mut u32) }, 10);
+
+        // Antes esto lo volvía a encolar y reanudaba un contexto terminado.
+        assert_eq!(my_thread_chsched(tid, SchedPolicy::RoundRobin), EINVAL);
+        for _ in 0..5 {
+            my_thread_yield();
+        }
+        assert_eq!(my_thread_join(tid), arg);
+    }
+
+    extern "C" fn detached_last_worker(arg: *mut c_void) -> *mut c_void {
+        let counter = arg as *mut u32;
+        for _ in 0..50 {
+            unsafe { *counter += 1 };
+            my_thread_yield();
+        }
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_main_resumes_after_last_detached_thread() {
+        let mut counter = 0u32;
+        let arg = &mut counter as *mut u32 as *mut c_void;
+
+        let short = my_thread_create(counting_worker, arg, SchedPolicy::RoundRobin);
+        let long = my_thread_create(detached_last_worker, arg, SchedPolicy::RoundRobin);
+        assert_eq!(my_thread_detach(long), 0);
+        my_thread_join(short);
+
+        // El detached termina último: main debe retomar la CPU y llegar a su
+        // "resumen" en vez de que el proceso termine.
+        while my_sched_live_threads() > 1 {
+            my_thread_yield();
+        }
+        assert_eq!(counter, 60);
+    }
+
+    struct SleepShared {
+        mutex: MyMutex,
+        /// Orden en que cada hilo pasó por la sección crítica.
+        order: Vec<&'static str>,
+    }
+
+    extern "C" fn sleeping_owner(arg: *mut c_void) -> *mut c_void {
+        let shared = arg as *mut SleepShared;
+        unsafe {
+            my_mutex_lock(&(*shared).mutex);
+            my_thread_sleep(50);
+            (*shared).order.push("owner");
+            my_mutex_unlock(&(*shared).mutex);
+        }
+        ptr::null_mut()
+    }
+
+    extern "C" fn mutex_waiter(arg: *mut c_void) -> *mut c_void {
+        let shared = arg as *mut SleepShared;
+        unsafe {
+            my_mutex_lock(&(*shared).mutex);
+            (*shared).order.push("waiter");
+            my_mutex_unlock(&(*shared).mutex);
+        }
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_idle_thread_runs_while_everyone_sleeps() {
+        let mut shared = SleepShared { mutex: MyMutex::new(), order: Vec::new() };
+        my_mutex_init(&mut shared.mutex);
+        let arg = &mut shared as *mut SleepShared as *mut c_void;
+
+        let owner = my_thread_create(sleeping_owner, arg, SchedPolicy::RoundRobin);
+        my_thread_yield();
+        let waiters: Vec<_> = (0..3)
+            .map(|_| my_thread_create(mutex_waiter, arg, SchedPolicy::RoundRobin))
+            .collect();
+
+        // Main y los waiters quedan bloqueados mientras el dueño duerme: solo
+        // el idle puede avanzar el reloj.
+        let start = my_sched_stats().ticks;
+        for tid in waiters {
+            my_thread_join(tid);
+        }
+        my_thread_join(owner);
+        assert!(my_sched_stats().ticks - start >= 50);
+        assert_eq!(shared.order, ["owner", "waiter", "waiter", "waiter"]);
+
+        // El idle no es un hilo del usuario.
+        let idle = with_scheduler(|s| s.idle_tid).expect("el idle debió crearse");
+        assert!(my_thread_join(idle).is_null());
+        assert_eq!(my_thread_detach(idle), EINVAL);
+        assert_eq!(my_thread_chsched(idle, SchedPolicy::RoundRobin), EINVAL);
+        assert!(my_thread_stats(idle).is_none());
+        assert_eq!(my_sched_live_threads(), 1);
+        assert_eq!(my_sched_stats().live_threads, 1);
+
+        // Con un hilo listo, el idle no se elige aunque haya alguien durmiendo.
+        let idle_runs = || with_scheduler(|s| s.threads[&idle].acct.run_count);
+        let before = idle_runs();
+        assert!(before > 0);
+        let mut counter = 0u32;
+        let arg = &mut counter as *mut u32 as *mut c_void;
+        let worker = my_thread_create(counting_worker, arg, SchedPolicy::RoundRobin);
+        my_thread_sleep(5);
+        assert_eq!(idle_runs(), before);
+        my_thread_join(worker);
+        assert_eq!(counter, 10);
+    }
+
+    /// Clase de prueba: corre primero el hilo listo con mayor `param`.
+    #[derive(Default)]
+    struct HighestParamClass {
+        ready: Vec<(u64, MyThreadId)>,
+    }
+
+    impl SchedClass for HighestParamClass {
+        fn enqueue(&mut self, tid: MyThreadId, thread: &Thread) {
+            if let SchedPolicy::Custom { param, .. } = thread.policy() {
+                self.ready.push((param, tid));
+            }
+        }
+
+        fn remove(&mut self, tid: MyThreadId) {
+            self.ready.retain(|&(_, id)| id != tid);
+        }
+
+        fn pick(&mut self, _threads: &HashMap<MyThreadId, Thread>) -> Option<MyThreadId> {
+            let best = (0..self.ready.len()).max_by_key(|&i| self.ready[i].0)?;
+            Some(self.ready.remove(best).1)
+        }
+    }
+
+    extern "C" fn record_param_worker(arg: *mut c_void) -> *mut c_void {
+        let order = arg as *mut Vec<u64>;
+        let tid = with_scheduler(|s| s.current_thread_id().unwrap());
+        if let Some(SchedPolicy::Custom { param, .. }) = my_thread_stats(tid).map(|st| st.policy) {
+            unsafe { (*order).push(param) };
+        }
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_custom_sched_class() {
+        let mut order: Vec<u64> = Vec::new();
+        let arg = &mut order as *mut Vec<u64> as *mut c_void;
+
+        let unknown = SchedPolicy::Custom { class_id: 0, param: 0 };
+        assert_eq!(
+            my_thread_try_create(record_param_worker, arg, unknown),
+            Err(ThreadError::InvalidPolicy(unknown))
+        );
+
+        // Entre Lottery y Round Robin: main (RR) no corre mientras haya
+        // hilos de la clase listos.
+        let class_id = my_sched_register_class(Box::<HighestParamClass>::default(), 150);
+        let tids: Vec<_> = [1, 3, 2]
+            .into_iter()
+            .map(|param| {
+                my_thread_create(record_param_worker, arg, SchedPolicy::Custom { class_id, param })
+            })
+            .collect();
+        assert_eq!(my_thread_chsched(tids[0], SchedPolicy::Custom { class_id: 7, param: 0 }), EINVAL);
+
+        my_thread_yield();
+        assert_eq!(order, [3, 2, 1]);
+        let stats = my_sched_stats();
+        assert_eq!(stats.dispatches_by_policy[&SchedPolicyKind::Custom { class_id }], 3);
+        for tid in tids {
+            my_thread_join(tid);
+        }
+    }
+
+    extern "C" fn yielding_worker(_arg: *mut c_void) -> *mut c_void {
+        for _ in 0..40 {
+            my_thread_yield();
+        }
+        ptr::null_mut()
+    }
+
+    /// Cambios de contexto que generan tres hilos que ceden la CPU 40 veces.
+    fn switches_for_workload(policy: SchedPolicy) -> u64 {
+        let before = my_sched_stats().context_switches;
+        let tids: Vec<_> = (0..3)
+            .map(|_| my_thread_create(yielding_worker, ptr::null_mut(), policy))
+            .collect();
+        for tid in tids {
+            my_thread_join(tid);
+        }
+        my_sched_stats().context_switches - before
+    }
+
+    #[test]
+    fn test_quantum_reduces_context_switches() {
+        assert_eq!(my_sched_set_quantum(SchedPolicyKind::RoundRobin, 0), EINVAL);
+        assert_eq!(my_sched_set_quantum(SchedPolicyKind::Mlfq, 4), EINVAL);
+
+        let rr_q1 = switches_for_workload(SchedPolicy::RoundRobin);
+        assert_eq!(my_sched_set_quantum(SchedPolicyKind::RoundRobin, 8), 0);
+        let rr_q8 = switches_for_workload(SchedPolicy::RoundRobin);
+        assert!(rr_q8 * 4 < rr_q1, "quantum 1: {rr_q1}, quantum 8: {rr_q8}");
+
+        // Lottery repite al ganador hasta agotar su quantum.
+        let lottery = SchedPolicy::Lottery { tickets: 5 };
+        let lot_q1 = switches_for_workload(lottery);
+        assert_eq!(my_sched_set_quantum(SchedPolicyKind::Lottery, 8), 0);
+        let lot_q8 = switches_for_workload(lottery);
+        assert!(lot_q8 * 4 < lot_q1, "quantum 1: {lot_q1}, quantum 8: {lot_q8}");
+    }
+
+    /// Latencia promedio de despertar de un hilo que duerme a cada rato,
+    /// compitiendo con cuatro hilos RR que solo ceden la CPU.
+    fn wakeup_latency_with(policy: UnblockPolicy) -> f64 {
+        my_sched_set_unblock_policy(policy);
+        let spinners: Vec<_> = (0..4)
+            .map(|_| {
+                my_thread_spawn(
+                    || {
+                        for _ in 0..200 {
+                            my_thread_yield();
+                        }
+                    },
+                    SchedPolicy::RoundRobin,
+                )
+            })
+            .collect();
+        let sleeper = my_thread_spawn(
+            || {
+                for _ in 0..20 {
+                    my_thread_sleep(1);
+                }
+            },
+            SchedPolicy::RoundRobin,
+        );
+        while my_thread_stats(sleeper).unwrap().finished_at.is_none() {
+            my_thread_yield();
+        }
+        let stats = my_thread_stats(sleeper).unwrap();
+        assert_eq!(stats.wakeups, 20);
+        my_thread_join(sleeper);
+        for tid in spinners {
+            my_thread_join(tid);
+        }
+        stats.avg_wakeup_latency
+    }
+
+    #[test]
+    fn test_front_of_queue_lowers_wakeup_latency() {
+        let back = wakeup_latency_with(UnblockPolicy::BackOfQueue);
+        let front = wakeup_latency_with(UnblockPolicy::FrontOfQueue);
+        assert!(back >= 4.0, "al fondo espera a los cuatro: {back}");
+        // Uno de cada MAX_FRONT_WAKEUPS + 1 despertares va al fondo.
+        assert!(front < back / 2.0, "frente={front} fondo={back}");
+    }
+
+    #[test]
+    fn test_yield_to_alternates_between_two_threads() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let peers = Rc::new(Cell::new((0, 0)));
+        let switches = Rc::new(Cell::new(0));
+        let player = |me: u8| {
+            let log = Rc::clone(&log);
+            let peers = Rc::clone(&peers);
+            let switches = Rc::clone(&switches);
+            move || {
+                for _ in 0..50 {
+                    log.borrow_mut().push(me);
+                    let (a, b) = peers.get();
+                    let rc = my_thread_yield_to(if me == 0 { b } else { a });
+                    // El último yield de `b` encuentra a `a` terminado.
+                    assert!(rc == 0 || rc == EAGAIN, "rc={rc}");
+                }
+                if me == 1 {
+                    switches.set(my_thread_stats(my_thread_self()).unwrap().switch_count);
+                }
+            }
+        };
+        let spinners: Vec<_> = (0..5)
+            .map(|_| {
+                let log = Rc::clone(&log);
+                my_thread_spawn(
+                    move || {
+                        for _ in 0..50 {
+                            log.borrow_mut().push(9);
+                            my_thread_yield();
+                        }
+                    },
+                    SchedPolicy::RoundRobin,
+                )
+            })
+            .collect();
+        let a = my_thread_spawn(player(0), SchedPolicy::RoundRobin);
+        let b = my_thread_spawn(player(1), SchedPolicy::RoundRobin);
+        peers.set((a, b));
+
+        for tid in spinners.into_iter().chain([a, b]) {
+            my_thread_join(tid);
+        }
+
+        // Una vez que arrancan, ningún otro hilo corre entre medio.
+        let log = log.borrow();
+        let start = log.iter().position(|&id| id != 9).unwrap();
+        let pair = &log[start..start + 100];
+        assert!(pair.iter().all(|&id| id != 9), "{log:?}");
+        assert!(pair.windows(2).all(|w| w[0] != w[1]), "{pair:?}");
+        // Los traspasos de `a` a `b` cuentan como cambios de contexto.
+        assert!(switches.get() >= 50, "switches={}", switches.get());
+
+        let sleeper = my_thread_spawn(|| my_thread_sleep(100), SchedPolicy::RoundRobin);
+        my_thread_yield();
+        assert_eq!(my_thread_yield_to(sleeper), EAGAIN);
+        my_thread_join(sleeper);
+        assert_eq!(my_thread_yield_to(sleeper), EINVAL);
+        assert_eq!(my_thread_yield_to(999), EINVAL);
+        assert_eq!(my_thread_yield_to(0), EINVAL);
+    }
+
+    #[test]
+    fn test_front_of_queue_does_not_starve_spinners() {
+        const ROUNDS_LIMIT: u32 = 100_000;
+        my_sched_set_unblock_policy(UnblockPolicy::FrontOfQueue);
+        let sems = Box::into_raw(Box::new([MySemaphore::new(0), MySemaphore::new(0)]));
+        let spinner_done = Rc::new(Cell::new(false));
+        let stop = Rc::new(Cell::new(false));
+        let rounds = Rc::new(Cell::new(0u32));
+
+        let spinner = {
+            let spinner_done = Rc::clone(&spinner_done);
+            my_thread_spawn(
+                move || {
+                    for _ in 0..50 {
+                        my_thread_yield();
+                    }
+                    spinner_done.set(true);
+                },
+                SchedPolicy::RoundRobin,
+            )
+        };
+        // Dos hilos que se despiertan mutuamente sin parar: sin el tope, cada
+        // uno pasaría siempre al frente y el spinner nunca correría.
+        let ping = {
+            let (spinner_done, stop, rounds) =
+                (Rc::clone(&spinner_done), Rc::clone(&stop), Rc::clone(&rounds));
+            my_thread_spawn(
+                move || loop {
+                    if spinner_done.get() || rounds.get() >= ROUNDS_LIMIT {
+                        stop.set(true);
+                        my_sem_post(unsafe { &(*sems)[1] });
+                        break;
+                    }
+                    rounds.set(rounds.get() + 1);
+                    my_sem_post(unsafe { &(*sems)[1] });
+                    my_sem_wait(unsafe { &(*sems)[0] });
+                },
+                SchedPolicy::RoundRobin,
+            )
+        };
+        let pong = {
+            let stop = Rc::clone(&stop);
+            my_thread_spawn(
+                move || loop {
+                    my_sem_wait(unsafe { &(*sems)[1] });
+                    if stop.get() {
+                        break;
+                    }
+                    my_sem_post(unsafe { &(*sems)[0] });
+                },
+                SchedPolicy::RoundRobin,
+            )
+        };
+        for tid in [spinner, ping, pong] {
+            my_thread_join(tid);
+        }
+        drop(unsafe { Box::from_raw(sems) });
+
+        assert!(spinner_done.get());
+        assert!(rounds.get() < 1000, "rondas hasta que terminó el spinner: {}", rounds.get());
+    }
+
+    #[test]
+    fn test_slices_count_dispatches_and_continuations() {
+        assert_eq!(my_sched_set_quantum(SchedPolicyKind::RoundRobin, 4), 0);
+        let tid = my_thread_create(yielding_worker, ptr::null_mut(), SchedPolicy::RoundRobin);
+        while my_thread_stats(tid).is_some_and(|st| st.slices < 20) {
+            my_thread_yield();
+        }
+        let st = my_thread_stats(tid).unwrap();
+        // Cada dispatch le da 4 slots: uno el propio dispatch y tres yields más.
+        assert_eq!(st.slices, 4 * st.run_count);
+        // Solo los dispatches cambian de contexto: main y el hilo se turnan.
+        assert_eq!(st.switch_count, st.run_count);
+        assert_eq!(st.state, MyThreadState::Ready);
+        my_thread_join(tid);
+    }
+
+    extern "C" fn long_counting_worker(arg: *mut c_void) -> *mut c_void {
+        let counter = arg as *mut u32;
+        for _ in 0..200 {
+            unsafe { *counter += 1 };
+            my_thread_yield();
+        }
+        arg
+    }
+
+    fn thread_state(tid: MyThreadId) -> ThreadState {
+        with_scheduler(|s| s.threads[&tid].state)
+    }
+
+    #[test]
+    fn test_suspend_and_resume() {
+        let mut counter = 0u32;
+        let arg = &mut counter as *mut u32 as *mut c_void;
+        let read = |arg: *mut c_void| unsafe { *(arg as *mut u32) };
+
+        let tid = my_thread_create(long_counting_worker, arg, SchedPolicy::RoundRobin);
+        while read(arg) < 10 {
+            my_thread_yield();
+        }
+        assert_eq!(my_thread_suspend(tid), 0);
+        assert_eq!(thread_state(tid), ThreadState::Suspended);
+        let frozen = read(arg);
+        for _ in 0..500 {
+            my_thread_yield();
+        }
+        assert_eq!(read(arg), frozen);
+
+        assert_eq!(my_thread_resume(tid), 0);
+        assert_eq!(my_thread_join(tid), arg);
+        assert_eq!(counter, 200);
+        assert_eq!(my_thread_suspend(999), EINVAL);
+        assert_eq!(my_thread_resume(999), EINVAL);
+    }
+
+    struct SuspendShared {
+        mutex: MyMutex,
+        counter: u32,
+    }
+
+    extern "C" fn self_suspending_worker(arg: *mut c_void) -> *mut c_void {
+        let shared = arg as *mut SuspendShared;
+        unsafe {
+            (*shared).counter += 1;
+            my_thread_suspend(current_thread("test"));
+            (*shared).counter += 1;
+            my_mutex_lock(&(*shared).mutex);
+            (*shared).counter += 1;
+            my_mutex_unlock(&(*shared).mutex);
+        }
+        ptr::null_mut()
+    }
+
+    #[test]
+    fn test_suspend_self_and_while_blocked() {
+        let mut shared = SuspendShared { mutex: MyMutex::new(), counter: 0 };
+        my_mutex_init(&mut shared.mutex);
+        let arg = &mut shared as *mut SuspendShared as *mut c_void;
+
+        let tid = my_thread_create(self_suspending_worker, arg, SchedPolicy::RoundRobin);
+        my_thread_yield();
+        assert_eq!(thread_state(tid), ThreadState::Suspended);
+        assert_eq!(shared.counter, 1);
+
+        // Se bloquea en el mutex de main; la suspensión queda pendiente y se
+        // aplica cuando el unlock lo despierta.
+        my_mutex_lock(&shared.mutex);
+        assert_eq!(my_thread_resume(tid), 0);
+        my_thread_yield();
+        assert_eq!(thread_state(tid), ThreadState::Blocked);
+        assert_eq!(my_thread_suspend(tid), 0);
+        my_mutex_unlock(&shared.mutex);
+        for _ in 0..10 {
+            my_thread_yield();
+        }
+        assert_eq!(thread_state(tid), ThreadState::Suspended);
+        assert_eq!(shared.counter, 2);
+
+        assert_eq!(my_thread_resume(tid), 0);
+        my_thread_join(tid);
+        assert_eq!(shared.counter, 3);
+
+        let mut counter = 0u32;
+        let arg = &mut counter as *mut u32 as *mut c_void;
+        let finished = my_thread_create(counting_worker, arg, SchedPolicy::RoundRobin);
+        while thread_state(finished) != ThreadState::Finished {
+            my_thread_yield();
+        }
+        assert_eq!(my_thread_suspend(finished), EINVAL);
+        my_thread_join(finished);
+    }
+
+    #[test]
+    fn test_getsched_round_trips_chsched() {
+        let mut counter = 0u32;
+        let arg = &mut counter as *mut u32 as *mut c_void;
+        let tid = my_thread_create(counting_worker, arg, SchedPolicy::RoundRobin);
+        assert_eq!(my_thread_getsched(tid), Ok(SchedPolicy::RoundRobin));
+        assert_eq!(my_thread_getsched(0), Ok(SchedPolicy::RoundRobin));
+
+        for policy in [
+            SchedPolicy::Lottery { tickets: 7 },
+            SchedPolicy::RealTime { deadline: 15 },
+            SchedPolicy::RoundRobin,
+        ] {
+            assert_eq!(my_thread_chsched(tid, policy), 0);
+            assert_eq!(my_thread_getsched(tid), Ok(policy));
+        }
+
+        // Parámetros vigentes: tickets redondeados y deadline actualizado.
+        assert_eq!(my_thread_chsched(tid, SchedPolicy::Lottery { tickets: 0 }), 0);
+        assert_eq!(my_thread_getsched(tid), Ok(SchedPolicy::Lottery { tickets: 1 }));
+        assert_eq!(my_thread_chsched(tid, SchedPolicy::RealTime { deadline: 15 }), 0);
+        assert_eq!(my_thread_set_realtime_deadline(tid, 40), 0);
+        assert_eq!(my_thread_getsched(tid), Ok(SchedPolicy::RealTime { deadline: 40 }));
+
+        my_thread_join(tid);
+        let err = my_thread_getsched(tid).unwrap_err();
+        assert_eq!(err, ThreadError::NoSuchThread { tid });
+        assert_eq!(err.errno(), ESRCH);
+
+        // Un hilo consulta su propia política.
+        let seen = Rc::new(Cell::new(None));
+        let tid = {
+            let seen = Rc::clone(&seen);
+            my_thread_spawn(
+                move || {
+                    let me = my_thread_self();
+                    seen.set(Some((me, my_thread_getsched(me))));
+                },
+                SchedPolicy::Lottery { tickets: 3 },
+            )
+        };
+        my_thread_join(tid);
+        assert_eq!(seen.get(), Some((tid, Ok(SchedPolicy::Lottery { tickets: 3 }))));
+        assert_eq!(my_thread_self(), 0);
+    }
+
+    struct CondShared {
+        mutex: MyMutex,
+        cond: MyCondVar,
+        /// `(timeout, resultado)` de cada espera que terminó.
+        results: Vec<(Option<u64>, c_int)>,
+    }
+
+    impl CondShared {
+        fn new() -> Self {
+            CondShared { mutex: MyMutex::new(), cond: MyCondVar::new(), results: Vec::new() }
+        }
+    }
+
+    struct CondWaiterArgs {
+        shared: *mut CondShared,
+        timeout: Option<u64>,
+    }
+
+    extern "C" fn cond_waiter(arg: *mut c_void) -> *mut c_void {
+        let args = unsafe { Box::from_raw(arg as *mut CondWaiterArgs) };
+        let shared = args.shared;
+        unsafe {
+            my_mutex_lock(&(*shared).mutex);
+            let rc = match args.timeout {
+                Some(ticks) => my_cond_timedwait(&mut (*shared).cond, &(*shared).mutex, ticks),
+                None => my_cond_wait(&mut (*shared).cond, &(*shared).mutex),
+            };
+            // Siempre vuelve con el mutex tomado.
+            assert_eq!((*shared).mutex.owner(), Some(current_thread("test")));
+            (*shared).results.push((args.timeout, rc));
+            my_mutex_unlock(&(*shared).mutex);
+        }
+        ptr::null_mut()
+    }
+
+    fn spawn_cond_waiter(shared: &mut CondShared, timeout: Option<u64>) -> MyThreadId {
+        let args = Box::new(CondWaiterArgs { shared, timeout });
+        let tid = my_thread_create(cond_waiter, Box::into_raw(args) as *mut c_void, SchedPolicy::RoundRobin);
+        while with_scheduler(|s| s.threads[&tid].state) != ThreadState::Blocked {
+            my_thread_yield();
+        }
+        tid
+    }
+
+    #[test]
+    fn test_cond_timedwait_times_out() {
+        let mut shared = CondShared::new();
+        my_mutex_lock(&shared.mutex);
+        assert_eq!(my_cond_timedwait(&mut shared.cond, &shared.mutex, 0), ETIMEDOUT);
+
+        // Nadie más puede correr: el idle avanza el reloj hasta el plazo.
+        let start = my_sched_stats().ticks;
+        assert_eq!(my_cond_timedwait(&mut shared.cond, &shared.mutex, 10), ETIMEDOUT);
+        assert!(my_sched_stats().ticks - start >= 10);
+        assert!(shared.cond.waiters.is_empty());
+        assert_eq!(my_mutex_unlock(&shared.mutex), 0);
+        assert_eq!(my_cond_wait(&mut shared.cond, &shared.mutex), EINVAL);
+    }
+
+    #[test]
+    fn test_cond_signal_before_timeout() {
+        let mut shared = CondShared::new();
+        let start = my_sched_stats().ticks;
+        let tid = spawn_cond_waiter(&mut shared, Some(1000));
+
+        my_mutex_lock(&shared.mutex);
+        my_cond_signal(&mut shared.cond);
+        my_mutex_unlock(&shared.mutex);
+        my_thread_join(tid);
+        assert_eq!(shared.results, [(Some(1000), 0)]);
+        assert!(my_sched_stats().ticks - start < 1000);
+    }
+
+    #[test]
+    fn test_cond_broadcast_mixed_waiters() {
+        let mut shared = CondShared::new();
+        let mut tids = vec![
+            spawn_cond_waiter(&mut shared, None),
+            spawn_cond_waiter(&mut shared, Some(5)),
+            spawn_cond_waiter(&mut shared, Some(1000)),
+        ];
+
+        // El de plazo corto vence y deja la cola.
+        while shared.results.is_empty() {
+            my_thread_yield();
+        }
+        assert_eq!(shared.results, [(Some(5), ETIMEDOUT)]);
+        assert_eq!(shared.cond.waiters.len(), 2);
+
+        // Una señal no se la lleva el hilo que ya se fue.
+        my_cond_signal(&mut shared.cond);
+        my_thread_join(tids.remove(0));
+        assert_eq!(shared.results[1], (None, 0));
+
+        tids.push(spawn_cond_waiter(&mut shared, None));
+        my_cond_broadcast(&mut shared.cond);
+        for tid in tids {
+            my_thread_join(tid);
+        }
+        assert_eq!(shared.results.len(), 4);
+        assert!(shared.results[2..].iter().all(|&(_, rc)| rc == 0));
+    }
+
+    #[test]
+    fn test_cond_signal_wins_over_expired_timeout() {
+        let mut shared = CondShared::new();
+        let tid = spawn_cond_waiter(&mut shared, Some(3));
+
+        // El plazo vence mientras está suspendido, y la señal llega antes de
+        // que vuelva a correr: cuenta como señalado.
+        assert_eq!(my_thread_suspend(tid), 0);
+        let start = my_sched_stats().ticks;
+        while my_sched_stats().ticks - start < 10 {
+            my_thread_yield();
+        }
+        assert_eq!(with_scheduler(|s| s.threads[&tid].state), ThreadState::Suspended);
+        my_cond_signal(&mut shared.cond);
+        assert_eq!(my_thread_resume(tid), 0);
+        my_thread_join(tid);
+        assert_eq!(shared.results, [(Some(3), 0)]);
+    }
+
+    /// Buffer acotado clásico: un mutex y dos variables de condición.
+    struct BoundedBuffer {
+        mutex: MyMutex,
+        not_full: MyCondVar,
+        not_empty: MyCondVar,
+        items: VecDeque<u32>,
+        consumed: Vec<u32>,
+    }
+
+    const BUFFER_CAPACITY: usize = 2;
+
+    #[test]
+    fn test_cond_producer_consumer() {
+        let buf = Box::into_raw(Box::new(BoundedBuffer {
+            mutex: MyMutex::new(),
+            not_full: MyCondVar::new(),
+            not_empty: MyCondVar::new(),
+            items: VecDeque::new(),
+            consumed: Vec::new(),
+        }));
+
+        let mut tids = Vec::new();
+        for p in 0..2u32 {
+            tids.push(my_thread_spawn(
+                move || unsafe {
+                    for i in 0..20 {
+                        my_mutex_lock(&(*buf).mutex);
+                        while (*buf).items.len() == BUFFER_CAPACITY {
+                            my_cond_wait(&mut (*buf).not_full, &(*buf).mutex);
+                        }
+                        (*buf).items.push_back(p * 100 + i);
+                        my_cond_signal(&mut (*buf).not_empty);
+                        my_mutex_unlock(&(*buf).mutex);
+                    }
+                },
+                SchedPolicy::RoundRobin,
+            ));
+        }
+        for _ in 0..2 {
+            tids.push(my_thread_spawn(
+                move || unsafe {
+                    for _ in 0..20 {
+                        my_mutex_lock(&(*buf).mutex);
+                        while (*buf).items.is_empty() {
+                            my_cond_wait(&mut (*buf).not_empty, &(*buf).mutex);
+                        }
+                        let item = (*buf).items.pop_front().unwrap();
+                        (*buf).consumed.push(item);
+                        my_cond_signal(&mut (*buf).not_full);
+                        my_mutex_unlock(&(*buf).mutex);
+                    }
+                },
+                SchedPolicy::RoundRobin,
+            ));
+        }
+        for tid in tids {
+            my_thread_join(tid);
+        }
+
+        let buf = unsafe { Box::from_raw(buf) };
+        let mut consumed = buf.consumed.clone();
+        consumed.sort_unstable();
+        let expected: Vec<u32> = (0..20).chain(100..120).collect();
+        assert_eq!(consumed, expected);
+        assert!(buf.items.is_empty());
+        assert!(buf.not_full.waiters.is_empty() && buf.not_empty.waiters.is_empty());
+    }
+
+    #[test]
+    fn test_mutex_shared_between_threads() {
+        // Los dos hilos usan el mismo mutex por referencia compartida, sin
+        // punteros crudos.
+        let mutex = Rc::new(MyMutex::new());
+        let in_critical = Rc::new(Cell::new(false));
+        let entries = Rc::new(Cell::new(0));
+
+        let tids: Vec<_> = (0..2)
+            .map(|_| {
+                let (mutex, in_critical, entries) =
+                    (Rc::clone(&mutex), Rc::clone(&in_critical), Rc::clone(&entries));
+                my_thread_spawn(
+                    move || {
+                        for _ in 0..10 {
+                            assert_eq!(my_mutex_lock(&mutex), 0);
+                            assert!(!in_critical.replace(true));
+                            entries.set(entries.get() + 1);
+                            my_thread_yield();
+                            in_critical.set(false);
+                            assert_eq!(my_mutex_unlock(&mutex), 0);
+                        }
+                    },
+                    SchedPolicy::RoundRobin,
+                )
+            })
+            .collect();
+        for tid in tids {
+            my_thread_join(tid);
+        }
+        assert_eq!(entries.get(), 20);
+        assert_eq!(mutex.owner(), None);
+        assert_eq!(my_mutex_trylock(&mutex), 0);
+        assert_eq!(my_mutex_unlock(&mutex), 0);
+    }
+
+    #[test]
+    fn test_mutex_guard_releases_on_scope_end_thread_end_and_panic() {
+        let m = Box::into_raw(Box::new(MyMutex::new()));
+        let held_by = |m: *mut MyMutex| unsafe { (*m).owner() };
+
+        {
+            let _guard = unsafe { (*m).lock_guard() };
+            assert_eq!(held_by(m), Some(0));
+            let tid = my_thread_spawn(
+                move || assert!(unsafe { (*m).try_lock_guard() }.is_none()),
+                SchedPolicy::RoundRobin,
+            );
+            my_thread_join(tid);
+        }
+        assert_eq!(held_by(m), None);
+
+        // Termina con my_thread_end sin soltar el guard.
+        let tid = my_thread_spawn(
+            move || {
+                let _guard = unsafe { (*m).lock_guard() };
+                my_thread_end(ptr::null_mut());
+            },
+            SchedPolicy::RoundRobin,
+        );
+        my_thread_join(tid);
+        assert_eq!(held_by(m), None);
+
+        // Un pánico dentro del alcance del guard lo libera para el siguiente
+        // hilo que espera el mutex.
+        let got_lock = Rc::new(Cell::new(false));
+        let panicker = my_thread_spawn(
+            move || {
+                let _guard = unsafe { (*m).lock_guard() };
+                my_thread_yield();
+                panic!("pánico con el mutex tomado");
+            },
+            SchedPolicy::RoundRobin,
+        );
+        let flag = Rc::clone(&got_lock);
+        let waiter = my_thread_spawn(
+            move || {
+                let _guard = unsafe { (*m).lock_guard() };
+                flag.set(true);
+            },
+            SchedPolicy::RoundRobin,
+        );
+        let payload = panic::catch_unwind(|| my_thread_join(panicker)).unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"pánico con el mutex tomado"));
+        my_thread_join(waiter);
+        assert!(got_lock.get());
+        assert_eq!(held_by(m), None);
+        assert!(with_scheduler(|s| s.threads[&0].cleanup_handlers.is_empty()));
+        drop(unsafe { Box::from_raw(m) });
+    }
+
+    #[test]
+    fn test_forgotten_mutex_guard_outlives_its_mutex() {
+        // El guard se olvida y el mutex se destruye antes de que el hilo
+        // termine: el handler de limpieza no debe tocar el `MyMutex` muerto.
+        let core = Rc::new(Cell::new(None));
+        let slot = Rc::clone(&core);
+        let tid = my_thread_spawn(
+            move || {
+                let m = MyMutex::new();
+                slot.set(Some(Rc::downgrade(&m.core)));
+                mem::forget(m.lock_guard());
+                drop(m);
+                my_thread_end(ptr::null_mut());
+            },
+            SchedPolicy::RoundRobin,
+        );
+        my_thread_join(tid);
+        let core = core.take().unwrap();
+        assert!(core.upgrade().is_none(), "el handler debe soltar su Rc<MutexCore>");
+
+        // Si el mutex solo se mueve, el handler lo sigue liberando.
+        let moved = Rc::new(RefCell::new(None));
+        let slot = Rc::clone(&moved);
+        let tid = my_thread_spawn(
+            move || {
+                let m = MyMutex::new();
+                mem::forget(m.lock_guard());
+                *slot.borrow_mut() = Some(Box::new(m));
+                my_thread_end(ptr::null_mut());
+            },
+            SchedPolicy::RoundRobin,
+        );
+        my_thread_join(tid);
+        let m = moved.borrow_mut().take().unwrap();
+        assert_eq!(m.owner(), None);
+        assert_eq!(my_mutex_trylock(&m), 0);
+        assert_eq!(my_mutex_unlock(&m), 0);
+    }
+
+    /// Cuatro hilos incrementan un contador con un yield entre la lectura y
+    /// la escritura, y vuelven a pedir el mutex apenas lo sueltan. Devuelve
+    /// el contador, el máximo de hilos adentro y las estadísticas del mutex.
+    fn contend_mutex(handoff: MutexHandoff) -> (u32, u32, MyMutexStats) {
+        let m = Box::into_raw(Box::new(MyMutex::new()));
+        assert_eq!(my_mutex_init_with_handoff(unsafe { &mut *m }, handoff), 0);
+        let counter = Rc::new(Cell::new(0));
+        let inside = Rc::new(Cell::new(0));
+        let max_inside = Rc::new(Cell::new(0));
+        let tids: Vec<_> = (0..4)
+            .map(|_| {
+                let (counter, inside, max_inside) =
+                    (Rc::clone(&counter), Rc::clone(&inside), Rc::clone(&max_inside));
+                my_thread_spawn(
+                    move || {
+                        for _ in 0..50 {
+                            my_mutex_lock(unsafe { &*m });
+                            inside.set(inside.get() + 1);
+                            max_inside.set(max_inside.get().max(inside.get()));
+                            let seen = counter.get();
+                            my_thread_yield();
+                            counter.set(seen + 1);
+                            inside.set(inside.get() - 1);
+                            my_mutex_unlock(unsafe { &*m });
+                        }
+                    },
+                    SchedPolicy::RoundRobin,
+                )
+            })
+            .collect();
+        for tid in tids {
+            my_thread_join(tid);
+        }
+        let stats = my_mutex_stats(unsafe { &*m });
+        assert_eq!(my_mutex_destroy(unsafe { &mut *m }), 0);
+        drop(unsafe { Box::from_raw(m) });
+        (counter.get(), max_inside.get(), stats)
+    }
+
+    #[test]
+    fn test_mutex_handoff_policies_keep_mutual_exclusion() {
+        let (count, max_inside, direct) = contend_mutex(MutexHandoff::Direct);
+        assert_eq!((count, max_inside), (200, 1));
+        assert_eq!(direct.acquisitions, 200);
+        assert!(direct.handoffs > 0);
+        assert_eq!(direct.steals, 0);
+
+        let (count, max_inside, barging) = contend_mutex(MutexHandoff::Barging);
+        assert_eq!((count, max_inside), (200, 1));
+        assert_eq!(barging.acquisitions, 200);
+        assert_eq!(barging.handoffs, 0);
+        // El que suelta el mutex lo vuelve a pedir antes de que corra el
+        // despertado.
+        assert!(barging.steals > 0);
+    }
+
+    #[test]
+    fn test_barging_wakes_the_next_waiter_if_the_woken_one_dies() {
+        let m = Box::into_raw(Box::new(MyMutex::with_handoff(MutexHandoff::Barging)));
+        let got = Rc::new(Cell::new(0));
+        let locker = |got: &Rc<Cell<u32>>| {
+            let got = Rc::clone(got);
+            move || {
+                my_mutex_lock(unsafe { &*m });
+                got.set(got.get() + 1);
+                my_mutex_unlock(unsafe { &*m });
+            }
+        };
+        assert_eq!(my_mutex_lock(unsafe { &*m }), 0);
+        let doomed = my_thread_spawn(locker(&got), SchedPolicy::RoundRobin);
+        let patient = my_thread_spawn(locker(&got), SchedPolicy::RoundRobin);
+        while my_thread_block_reason(patient).is_none() {
+            my_thread_yield();
+        }
+        // Despierta a `doomed`, que muere sin reintentar.
+        assert_eq!(my_mutex_unlock(unsafe { &*m }), 0);
+        assert_eq!(my_thread_kill(doomed), 0);
+        my_thread_join(doomed);
+        my_thread_join(patient);
+        assert_eq!(got.get(), 1);
+        drop(unsafe { Box::from_raw(m) });
+    }
+
+    #[test]
+    fn test_spurious_wakeup_does_not_grant_the_mutex() {
+        let m = Box::into_raw(Box::new(MyMutex::new()));
+        let inside = Rc::new(Cell::new(0));
+        let max_inside = Rc::new(Cell::new(0));
+        let worker = |yields: u32| {
+            let (inside, max_inside) = (Rc::clone(&inside), Rc::clone(&max_inside));
+            move || {
+                assert_eq!(my_mutex_lock(unsafe { &*m }), 0);
+                inside.set(inside.get() + 1);
+                max_inside.set(max_inside.get().max(inside.get()));
+                for _ in 0..yields {
+                    my_thread_yield();
+                }
+                inside.set(inside.get() - 1);
+                assert_eq!(my_mutex_unlock(unsafe { &*m }), 0);
+            }
+        };
+        let owner = my_thread_spawn(worker(10), SchedPolicy::RoundRobin);
+        let waiter = my_thread_spawn(worker(0), SchedPolicy::RoundRobin);
+        while my_thread_block_reason(waiter).is_none() {
+            my_thread_yield();
+        }
+
+        // Despertar al waiter sin pasarle el mutex: vuelve a la cola.
+        with_scheduler(|s| s.unblock(waiter));
+        my_thread_yield();
+        my_thread_yield();
+        assert!(matches!(
+            my_thread_block_reason(waiter),
+            Some(MyBlockReason::Mutex { owner: o, .. }) if o == owner
+        ));
+        assert_eq!(unsafe { &*m }.core.waiters.borrow().iter().filter(|&&t| t == waiter).count(), 1);
+
+        my_thread_join(owner);
+        my_thread_join(waiter);
+        assert_eq!(max_inside.get(), 1);
+        assert_eq!(unsafe { &*m }.owner(), None);
+        drop(unsafe { Box::from_raw(m) });
+    }
+
+    #[test]
+    fn test_semaphore_caps_concurrency() {
+        let sem = Box::into_raw(Box::new(MySemaphore::new(2)));
+        let inside = Rc::new(Cell::new(0));
+        let max_inside = Rc::new(Cell::new(0));
+
+        let tids: Vec<_> = (0..5)
+            .map(|_| {
+                let (inside, max_inside) = (Rc::clone(&inside), Rc::clone(&max_inside));
+                my_thread_spawn(
+                    move || unsafe {
+                        assert_eq!(my_sem_wait(&*sem), 0);
+                        inside.set(inside.get() + 1);
+                        max_inside.set(max_inside.get().max(inside.get()));
+                        for _ in 0..3 {
+                            my_thread_yield();
+                        }
+                        inside.set(inside.get() - 1);
+                        assert_eq!(my_sem_post(&*sem), 0);
+                    },
+                    SchedPolicy::RoundRobin,
+                )
+            })
+            .collect();
+
+        my_thread_yield();
+        unsafe {
+            assert_eq!((*sem).value(), 0);
+            assert_eq!(my_sem_trywait(&*sem), EAGAIN);
+            assert_eq!(my_sem_destroy(&mut *sem), EBUSY);
+        }
+        for tid in tids {
+            my_thread_join(tid);
+        }
+        assert_eq!(max_inside.get(), 2);
+        unsafe {
+            assert_eq!((*sem).value(), 2);
+            assert_eq!(my_sem_trywait(&*sem), 0);
+            assert_eq!((*sem).value(), 1);
+            assert_eq!(my_sem_destroy(&mut *sem), 0);
+            drop(Box::from_raw(sem));
+        }
+    }
+
+    #[test]
+    fn test_barrier_separates_phases() {
+        const WORKERS: usize = 4;
+        const PHASES: usize = 3;
+        let barrier = Box::into_raw(Box::new(MyBarrier::default()));
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let serials = Rc::new(Cell::new(0));
+        unsafe {
+            assert_eq!(my_barrier_init(&mut *barrier, 0), EINVAL);
+            assert_eq!(my_barrier_init(&mut *barrier, WORKERS), 0);
+        }
+
+        let tids: Vec<_> = (0..WORKERS)
+            .map(|w| {
+                let (log, serials) = (Rc::clone(&log), Rc::clone(&serials));
+                my_thread_spawn(
+                    move || {
+                        for phase in 0..PHASES {
+                            // Trabajos de distinto largo en cada fase
+                            for _ in 0..w {
+                                my_thread_yield();
+                            }
+                            log.borrow_mut().push(phase);
+                            match unsafe { my_barrier_wait(&mut *barrier) } {
+                                BARRIER_SERIAL_THREAD => serials.set(serials.get() + 1),
+                                rc => assert_eq!(rc, 0),
+                            }
+                        }
+                    },
+                    SchedPolicy::RoundRobin,
+                )
+            })
+            .collect();
+
+        my_thread_yield();
+        unsafe {
+            assert_eq!(my_barrier_destroy(&mut *barrier), EBUSY);
+            assert_eq!(my_barrier_reset(&mut *barrier, 2), EBUSY);
+        }
+        for tid in tids {
+            my_thread_join(tid);
+        }
+
+        // Nadie empieza una fase antes de que todos terminen la anterior.
+        let expected: Vec<usize> = (0..PHASES).flat_map(|p| [p; WORKERS]).collect();
+        assert_eq!(*log.borrow(), expected);
+        assert_eq!(serials.get(), PHASES);
+        unsafe {
+            assert_eq!(my_barrier_reset(&mut *barrier, 1), 0);
+            assert_eq!(my_barrier_wait(&mut *barrier), BARRIER_SERIAL_THREAD);
+            assert_eq!(my_barrier_destroy(&mut *barrier), 0);
+            drop(Box::from_raw(barrier));
+        }
+    }
+
+    thread_local! {
+        static TLS_FREED: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn free_tls_value(value: *mut c_void) {
+        let value = unsafe { Box::from_raw(value as *mut u32) };
+        TLS_FREED.with(|freed| freed.borrow_mut().push(*value));
+    }
+
+    #[test]
+    fn test_tls_values_are_per_thread_and_destroyed_on_exit() {
+        let key = my_tls_key_create(Some(free_tls_value));
+        let plain = my_tls_key_create(None);
+        assert!(my_tls_get(key).is_null());
+        assert_eq!(my_tls_set(plain + 1, ptr::null_mut()), EINVAL);
+
+        let mut main_value = 7u32;
+        assert_eq!(my_tls_set(plain, &mut main_value as *mut u32 as *mut c_void), 0);
+
+        let tids: Vec<_> = (1..=3u32)
+            .map(|n| {
+                my_thread_spawn(
+                    move || {
+                        assert!(my_tls_get(key).is_null());
+                        my_tls_set(key, Box::into_raw(Box::new(n * 10)) as *mut c_void);
+                        my_thread_yield();
+                        // Los demás hilos no pisan nuestro valor.
+                        assert_eq!(unsafe { *(my_tls_get(key) as *mut u32) }, n * 10);
+                        if n == 3 {
+                            // Un valor nulo no llama al destructor.
+                            let value = my_tls_get(key);
+                            my_tls_set(key, ptr::null_mut());
+                            drop(unsafe { Box::from_raw(value as *mut u32) });
+                        }
+                    },
+                    SchedPolicy::RoundRobin,
+                )
+            })
+            .collect();
+        for tid in tids {
+            my_thread_join(tid);
+        }
+
+        let mut freed = TLS_FREED.with(|freed| freed.take());
+        freed.sort_unstable();
+        assert_eq!(freed, [10, 20]);
+        assert_eq!(my_tls_get(plain), &mut main_value as *mut u32 as *mut c_void);
+        assert!(my_tls_get(key).is_null());
+    }
+
+    /// Cede la CPU tantas veces como indique `arg` y lo devuelve.
+    extern "C" fn counted_yield_worker(arg: *mut c_void) -> *mut c_void {
+        for _ in 0..arg as usize {
+            my_thread_yield();
+        }
+        arg
+    }
+
+    #[test]
+    fn test_join_any_returns_threads_as_they_finish() {
+        let spawn = |yields: usize| my_thread_create(counted_yield_worker, yields as *mut c_void, SchedPolicy::RoundRobin);
+        let (long, short, medium) = (spawn(30), spawn(5), spawn(15));
+        let mut pending = vec![long, short, medium];
+
+        let mut finished = Vec::new();
+        while !pending.is_empty() {
+            let (tid, result) = my_thread_join_any(&pending).unwrap();
+            finished.push((tid, result as usize));
+            pending.retain(|&t| t != tid);
+            // Los que no ganaron siguen sin nadie que los espere.
+            assert!(with_scheduler(|s| pending.iter().all(|t| s.threads[t].joined_by.is_none())));
+        }
+        assert_eq!(finished, [(short, 5), (medium, 15), (long, 30)]);
+
+        // Uno que ya terminó se devuelve sin bloquear, aunque no sea el primero.
+        let (a, b) = (spawn(40), spawn(0));
+        while with_scheduler(|s| s.threads[&b].state) != ThreadState::Finished {
+            my_thread_yield();
+        }
+        assert_eq!(my_thread_join_any(&[a, b]).unwrap(), (b, ptr::null_mut()));
+        assert_eq!(my_thread_join_any(&[a, b]), Err(ThreadError::NoSuchThread { tid: b }));
+        assert_eq!(my_thread_join_any(&[a, 0]), Err(ThreadError::NotJoinable { tid: 0 }));
+        assert_eq!(my_thread_join(a) as usize, 40);
+    }
+
+    #[test]
+    fn test_thread_names_show_up_in_reports() {
+        let tid = my_thread_spawn(|| {}, SchedPolicy::RoundRobin);
+        assert_eq!(my_thread_getname(tid), None);
+        assert_eq!(my_thread_setname(tid, "Ambulance-3"), 0);
+        assert_eq!(my_thread_getname(tid).as_deref(), Some("Ambulance-3"));
+        assert_eq!(with_scheduler(|s| s.label(tid)), format!("hilo {tid} (Ambulance-3)"));
+        let reason = BlockReason::Join { target: 0, until: None };
+        assert_eq!(
+            reason.describe(&with_scheduler(|s| s.label(tid))),
+            format!("hilo {tid} (Ambulance-3) espera el join del hilo 0")
+        );
+        assert_eq!(with_scheduler(|s| s.label(0)), "hilo 0");
+
+        my_thread_join(tid);
+        assert_eq!(my_thread_getname(tid), None);
+        assert_eq!(my_thread_setname(tid, "x"), EINVAL);
+    }
+
+    #[test]
+    fn test_thread_list_reports_every_state() {
+        let mutex = Rc::new(MyMutex::new());
+        my_mutex_lock(&mutex);
+
+        let m = Rc::clone(&mutex);
+        let on_mutex = my_thread_spawn(
+            move || {
+                my_mutex_lock(&m);
+                my_mutex_unlock(&m);
+            },
+            SchedPolicy::Lottery { tickets: 4 },
+        );
+        let finished = my_thread_spawn(|| {}, SchedPolicy::RoundRobin);
+        let on_join = my_thread_spawn(move || { my_thread_join(on_mutex); }, SchedPolicy::RoundRobin);
+        my_thread_yield();
+        let ready = my_thread_spawn(|| {}, SchedPolicy::RealTime { deadline: 50 });
+        my_thread_setname(ready, "listo");
+        my_thread_detach(ready);
+
+        let list = my_thread_list();
+        let info = |tid| list.iter().find(|i| i.tid == tid).unwrap().clone();
+        assert!(list.windows(2).all(|w| w[0].tid < w[1].tid));
+        assert_eq!(info(0).state, MyThreadState::Running);
+
+        let blocked = info(on_mutex);
+        assert_eq!(blocked.state, MyThreadState::Blocked);
+        assert_eq!(blocked.block_reason, Some(MyBlockReason::Mutex { mutex: mutex.id(), owner: 0 }));
+        assert_eq!(blocked.policy, SchedPolicy::Lottery { tickets: 4 });
+        assert_eq!(blocked.joined_by, Some(on_join));
+
+        assert_eq!(info(on_join).block_reason, Some(MyBlockReason::Join { target: on_mutex }));
+        assert_eq!(info(finished).state, MyThreadState::Finished);
+        assert_eq!(info(finished).block_reason, None);
+
+        let ready_info = info(ready);
+        assert_eq!(ready_info.state, MyThreadState::Ready);
+        assert_eq!(ready_info.name.as_deref(), Some("listo"));
+        assert_eq!(ready_info.policy, SchedPolicy::RealTime { deadline: 50 });
+        assert!(ready_info.detached);
+
+        let counts = my_thread_count_by_state();
+        assert_eq!(counts[&MyThreadState::Blocked], 2);
+        assert_eq!(counts[&MyThreadState::Running], 1);
+        assert_eq!(counts.values().sum::<usize>(), list.len());
+
+        my_mutex_unlock(&mutex);
+        my_thread_join(on_join);
+        my_thread_join(finished);
+    }
+
+    #[test]
+    fn test_cancel_releases_held_mutexes() {
+        let a = Rc::new(MyMutex::new());
+        let b = Rc::new(MyMutex::new());
+        let steps = Rc::new(Cell::new(0));
+
+        let (ma, mb, st) = (Rc::clone(&a), Rc::clone(&b), Rc::clone(&steps));
+        let looping = my_thread_spawn(
+            move || {
+                my_mutex_lock(&ma);
+                let _guard = mb.lock_guard();
+                loop {
+                    st.set(st.get() + 1);
+                    my_thread_yield();
+                }
+            },
+            SchedPolicy::RoundRobin,
+        );
+        let ma = Rc::clone(&a);
+        let waiter = my_thread_spawn_with_result(
+            move || {
+                my_mutex_lock(&ma);
+                let owner = ma.owner();
+                my_mutex_unlock(&ma);
+                owner
+            },
+            SchedPolicy::RoundRobin,
+        );
+        while steps.get() < 3 {
+            my_thread_yield();
+        }
+        assert_eq!(a.owner(), Some(looping));
+
+        assert_eq!(my_thread_cancel(looping), 0);
+        assert!(my_thread_join(looping).is_null());
+        // El guard se soltó como handler de limpieza y el lock crudo, al
+        // terminar; ambos quedaron libres o en manos del que esperaba.
+        assert_eq!(b.owner(), None);
+        let waiter_tid = waiter.tid();
+        assert_eq!(waiter.join(), Some(waiter_tid));
+        assert_eq!(a.owner(), None);
+
+        assert_eq!(my_thread_cancel(looping), EINVAL);
+        assert_eq!(my_thread_cancel(0), EINVAL);
+    }
+
+    #[test]
+    fn test_thread_state_transitions() {
+        assert_eq!(my_thread_state(0), MyThreadState::Running);
+        assert_eq!(my_thread_state(usize::MAX), MyThreadState::Unknown);
+        assert_eq!(my_thread_block_reason(0), None);
+
+        let mutex = Rc::new(MyMutex::new());
+        my_mutex_lock(&mutex);
+        let m = Rc::clone(&mutex);
+        let worker = my_thread_spawn(
+            move || {
+                assert_eq!(my_thread_state(current_thread("test")), MyThreadState::Running);
+                my_mutex_lock(&m);
+                my_mutex_unlock(&m);
+            },
+            SchedPolicy::RoundRobin,
+        );
+        let joiner = my_thread_spawn(move || { my_thread_join(worker); }, SchedPolicy::RoundRobin);
+        assert_eq!(my_thread_state(worker), MyThreadState::Ready);
+
+        my_thread_yield();
+        assert_eq!(my_thread_state(worker), MyThreadState::Blocked);
+        assert_eq!(my_thread_state(joiner), MyThreadState::Blocked);
+        assert_eq!(
+            my_thread_block_reason(worker),
+            Some(MyBlockReason::Mutex { mutex: mutex.id(), owner: 0 })
+        );
+        assert_eq!(my_thread_block_reason(joiner), Some(MyBlockReason::Join { target: worker }));
+
+        assert_eq!(my_thread_suspend(worker), 0);
+        my_mutex_unlock(&mutex);
+        assert_eq!(my_thread_state(worker), MyThreadState::Suspended);
+        assert_eq!(my_thread_block_reason(worker), None);
+        assert_eq!(my_thread_resume(worker), 0);
+        assert_eq!(my_thread_state(worker), MyThreadState::Ready);
+
+        my_thread_join(joiner);
+        // El join del otro hilo ya liberó al worker.
+        assert_eq!(my_thread_state(worker), MyThreadState::Unknown);
+
+        // Un hilo terminado queda Finished hasta el join...
+        let finished = my_thread_spawn(|| {}, SchedPolicy::RoundRobin);
+        my_thread_yield();
+        assert_eq!(my_thread_state(finished), MyThreadState::Finished);
+        my_thread_join(finished);
+        assert_eq!(my_thread_state(finished), MyThreadState::Unknown);
+
+        // ...y uno detached se libera apenas otro hilo toma la CPU.
+        let detached = my_thread_spawn(|| {}, SchedPolicy::RoundRobin);
+        my_thread_detach(detached);
+        my_thread_yield();
+        assert_eq!(my_thread_state(detached), MyThreadState::Unknown);
+
+        let idle = with_scheduler(|s| s.idle_tid);
+        if let Some(idle) = idle {
+            assert_eq!(my_thread_state(idle), MyThreadState::Unknown);
+        }
+    }
+
+    #[test]
+    fn test_recursive_mutex_releases_at_depth_zero() {
+        let m = Rc::new(MyMutexRecursive::new());
+        assert_eq!(my_mutex_recursive_lock(&m), 0);
+        assert_eq!(my_mutex_recursive_lock(&m), 0);
+        assert_eq!(my_mutex_recursive_trylock(&m), 0);
+        assert_eq!(m.depth(), 3);
+
+        let other = Rc::clone(&m);
+        let waiter = my_thread_spawn_with_result(
+            move || {
+                let busy = my_mutex_recursive_trylock(&other);
+                assert_eq!(my_mutex_recursive_unlock(&other), EINVAL);
+                my_mutex_recursive_lock(&other);
+                let depth = other.depth();
+                my_mutex_recursive_unlock(&other);
+                (busy, depth)
+            },
+            SchedPolicy::RoundRobin,
+        );
+        my_thread_yield();
+
+        assert_eq!(my_mutex_recursive_unlock(&m), 0);
+        assert_eq!(my_mutex_recursive_unlock(&m), 0);
+        // Todavía queda un nivel: el otro hilo sigue esperando.
+        my_thread_yield();
+        assert_eq!(m.owner(), Some(0));
+        assert_eq!(my_mutex_recursive_unlock(&m), 0);
+        assert_eq!(waiter.join(), (EBUSY, 1));
+        assert_eq!((m.owner(), m.depth()), (None, 0));
+        assert_eq!(my_mutex_recursive_unlock(&m), EINVAL);
+    }
+
+    extern "C" fn tick_logging_worker(arg: *mut c_void) -> *mut c_void {
+        let log = unsafe { &mut *(arg as *mut Vec<u64>) };
+        for _ in 0..10 {
+            log.push(my_sched_ticks());
+            my_thread_yield();
+        }
+        log.push(my_sched_ticks());
+        ptr::null_mut()
+    }
 
-        // Elegir siguiente
-        if let Some(next_id) = self.pick_next() {
-            let (curr_ctx_ptr, next_ctx_ptr) = {
-                let curr_ctx: *mut ucontext_t =
-                    &mut self.threads.get_mut(&curr_id).unwrap().context;
-                let next_ctx: *mut ucontext_t =
-                    &mut self.threads.get_mut(&next_id).unwrap().context;
-                (curr_ctx, next_ctx)
-            };
-            self.current = Some(next_id);
+    #[test]
+    fn test_sched_ticks_count_dispatches_and_turnaround() {
+        let before = my_sched_stats();
+        let mut logs: Vec<Vec<u64>> = vec![Vec::new(); 3];
+        let tids: Vec<_> = logs
+            .iter_mut()
+            .map(|log| {
+                let arg = log as *mut Vec<u64> as *mut c_void;
+                my_thread_create(tick_logging_worker, arg, SchedPolicy::RoundRobin)
+            })
+            .collect();
 
-            unsafe {
-                swapcontext(curr_ctx_ptr, next_ctx_ptr);
+        let mut stats = Vec::new();
+        for &tid in &tids {
+            while my_thread_stats(tid).unwrap().finished_at.is_none() {
+                my_thread_yield();
             }
-        } else {
-            // No hay nadie más: deadlock o todos bloqueados.
-            // En un sistema real habría que manejar esto mejor.
+            stats.push(my_thread_stats(tid).unwrap());
+            my_thread_join(tid);
         }
-    }
 
-    /// Marca un hilo como Ready y lo encola en su scheduler.
-    fn unblock(&mut self, tid: MyThreadId) {
-        if let Some(thr) = self.threads.get_mut(&tid) {
-            thr.state = ThreadState::Ready;
-            thr.block_reason = None;
-            self.enqueue_ready(tid);
+        // Con quantum 1 cada tick del reloj es exactamente un dispatch.
+        let after = my_sched_stats();
+        assert_eq!(my_sched_ticks(), after.ticks);
+        assert_eq!(
+            after.ticks - before.ticks,
+            after.dispatches - before.dispatches
+        );
+
+        for (st, log) in stats.iter().zip(&logs) {
+            let first_run = st.first_run_at.unwrap();
+            let finished = st.finished_at.unwrap();
+            assert!(st.created_at < first_run);
+            assert_eq!(log[0], first_run);
+            assert_eq!(*log.last().unwrap(), finished);
+            assert!(log.windows(2).all(|w| w[0] < w[1]));
+            assert!(finished - st.created_at >= st.run_count);
         }
     }
 
-    /// Finaliza el hilo actual y pasa a otro.
-    fn finish_current(&mut self, retval: *mut c_void) -> ! {
-        self.ensure_main_thread();
+    #[test]
+    fn test_mutex_timedlock_times_out_and_breaks_cycles() {
+        let (a, b) = (Rc::new(MyMutex::new()), Rc::new(MyMutex::new()));
+        assert_eq!(my_mutex_lock(&a), 0);
+        assert_eq!(my_mutex_timedlock(&a, 5), EDEADLK);
 
-        let curr_id = self.current.expect("no hay hilo actual en finish_current");
+        // Main retiene `a` más que el plazo del hilo.
+        let rc = Rc::new(Cell::new(0));
+        let waiter = {
+            let (a, rc) = (Rc::clone(&a), Rc::clone(&rc));
+            my_thread_spawn(move || rc.set(my_mutex_timedlock(&a, 5)), SchedPolicy::RoundRobin)
+        };
+        let start = my_sched_ticks();
+        my_thread_sleep(20);
+        my_thread_join(waiter);
+        assert_eq!(rc.get(), ETIMEDOUT);
+        assert!(a.core.waiters.borrow().is_empty());
+        assert_eq!(a.owner(), Some(0));
+        assert!(my_sched_ticks() - start >= 5);
 
-        let joined_by = {
-            let thr = self.threads.get_mut(&curr_id).unwrap();
-            thr.state = ThreadState::Finished;
-            thr.result = retval;
-            thr.joined_by
+        // Si el dueño suelta antes del plazo, el mutex pasa al que espera.
+        let waiter = {
+            let (a, rc) = (Rc::clone(&a), Rc::clone(&rc));
+            my_thread_spawn(
+                move || {
+                    rc.set(my_mutex_timedlock(&a, 50));
+                    my_mutex_unlock(&a);
+                },
+                SchedPolicy::RoundRobin,
+            )
         };
+        my_thread_yield();
+        assert_eq!(my_mutex_unlock(&a), 0);
+        my_thread_join(waiter);
+        assert_eq!(rc.get(), 0);
 
-        // Despertar al que hizo join, si existe
-        if let Some(jid) = joined_by {
-            self.unblock(jid);
+        // Ciclo a -> b -> a: el lado con plazo se rinde y el otro avanza.
+        let worker = {
+            let (a, b, rc) = (Rc::clone(&a), Rc::clone(&b), Rc::clone(&rc));
+            my_thread_spawn(
+                move || {
+                    my_mutex_lock(&b);
+                    my_thread_yield();
+                    rc.set(my_mutex_timedlock(&a, 3));
+                    my_mutex_unlock(&b);
+                },
+                SchedPolicy::RoundRobin,
+            )
+        };
+        my_mutex_lock(&a);
+        my_thread_yield();
+        assert_eq!(my_mutex_lock(&b), 0);
+        assert_eq!(rc.get(), ETIMEDOUT);
+        my_mutex_unlock(&b);
+        my_mutex_unlock(&a);
+        my_thread_join(worker);
+        assert_eq!(my_mutex_timedlock(&a, 0), 0);
+        my_mutex_unlock(&a);
+    }
+
+    #[test]
+    fn test_rt_stats_track_misses_lateness_and_response() {
+        let wakeups = Rc::new(RefCell::new(Vec::new()));
+        let rt = {
+            let wakeups = Rc::clone(&wakeups);
+            my_thread_spawn(
+                move || {
+                    for _ in 0..2 {
+                        my_thread_sleep(5);
+                        wakeups.borrow_mut().push(my_sched_ticks());
+                    }
+                    my_thread_sleep(10);
+                },
+                SchedPolicy::RealTime { deadline: 2 },
+            )
+        };
+        let first_deadline = my_thread_stats(rt).unwrap().created_at + 2;
+        assert_eq!(my_thread_rt_stats(rt).unwrap().activations, 1);
+
+        // Mientras el RT duerme por segunda vez, main le fija otro deadline
+        // absoluto que también va a incumplir.
+        while wakeups.borrow().is_empty() {
+            my_thread_yield();
+        }
+        let second_deadline = my_sched_ticks() + 1;
+        assert_eq!(my_thread_set_realtime_deadline(rt, second_deadline), 0);
+        while wakeups.borrow().len() < 2 {
+            my_thread_yield();
         }
 
-        // No lo encolamos de nuevo (ya terminó)
-        self.remove_from_ready_lists(curr_id);
+        // Tras el cambio de política las métricas quedan congeladas.
+        assert_eq!(my_thread_chsched(rt, SchedPolicy::RoundRobin), 0);
+        let frozen = my_thread_rt_stats(rt).unwrap();
+        while my_thread_stats(rt).unwrap().finished_at.is_none() {
+            my_thread_yield();
+        }
+        let stats = my_thread_rt_stats(rt).unwrap();
+        assert_eq!(stats, frozen);
+        my_thread_join(rt);
 
-        // Elegir siguiente
-        if let Some(next_id) = self.pick_next() {
-            let curr_ctx_ptr: *mut ucontext_t =
-                &mut self.threads.get_mut(&curr_id).unwrap().context;
-            let next_ctx_ptr: *mut ucontext_t =
-                &mut self.threads.get_mut(&next_id).unwrap().context;
+        let lateness = [wakeups.borrow()[0] - first_deadline, wakeups.borrow()[1] - second_deadline];
+        assert!(lateness.iter().all(|&l| l > 0));
+        assert_eq!(stats.deadline_misses, 2);
+        assert_eq!(stats.worst_lateness, lateness[0].max(lateness[1]));
+        // Creación más dos despertares; el RT siempre corre en el tick siguiente.
+        assert_eq!(stats.activations, 3);
+        assert_eq!(stats.avg_response_ticks, 1.0);
+        assert!(my_thread_rt_stats(0).is_none());
+    }
 
-            self.current = Some(next_id);
+    thread_local! {
+        static PAYLOAD_DROPS: Cell<usize> = const { Cell::new(0) };
+    }
 
-            unsafe {
-                swapcontext(curr_ctx_ptr, next_ctx_ptr);
-            }
+    /// Argumento y resultado de los hilos detached del test: cuenta sus drops.
+    struct Payload;
 
-            // Si volvemos aquí es que algo salió muy raro
-            unsafe { core::hint::unreachable_unchecked() }
-        } else {
-            // No hay más hilos ready: podemos volver a main si se manejara aparte,
-            // o terminar el proceso.
-            std::process::exit(0);
+    impl Drop for Payload {
+        fn drop(&mut self) {
+            PAYLOAD_DROPS.with(|d| d.set(d.get() + 1));
         }
     }
 
-    /// Intenta hacer join inmediato; si el hilo ya terminó, retorna Some(result).
-    fn try_join_immediate(&self, target: MyThreadId) -> Option<*mut c_void> {
-        let t = self.threads.get(&target)?;
-        if t.state == ThreadState::Finished {
-            Some(t.result)
-        } else {
-            None
-        }
+    extern "C" fn payload_worker(arg: *mut c_void) -> *mut c_void {
+        my_thread_yield();
+        arg
     }
 
-    /// Cambia la política de scheduling de un hilo.
-    fn change_scheduler(&mut self, tid: MyThreadId, policy: SchedPolicy) -> c_int {
-        if !self.threads.contains_key(&tid) {
-            return EINVAL;
+    fn drop_payload(result: *mut c_void) {
+        drop(unsafe { Box::from_raw(result as *mut Payload) });
+    }
+
+    #[test]
+    fn test_detached_finalizers_reclaim_results() {
+        let live = my_sched_live_threads();
+        for _ in 0..100 {
+            let arg = Box::into_raw(Box::new(Payload)) as *mut c_void;
+            let tid = my_thread_create(payload_worker, arg, SchedPolicy::RoundRobin);
+            assert_eq!(my_thread_set_finalizer(tid, drop_payload), 0);
+            assert_eq!(my_thread_detach(tid), 0);
         }
+        while my_sched_live_threads() > live {
+            my_thread_yield();
+        }
+        assert_eq!(PAYLOAD_DROPS.with(Cell::get), 100);
 
-        self.remove_from_ready_lists(tid);
+        // Detach de un hilo que ya terminó: se libera y finaliza en el acto.
+        let arg = Box::into_raw(Box::new(Payload)) as *mut c_void;
+        let tid = my_thread_create(payload_worker, arg, SchedPolicy::RoundRobin);
+        my_thread_set_finalizer(tid, drop_payload);
+        while my_thread_state(tid) != MyThreadState::Finished {
+            my_thread_yield();
+        }
+        assert_eq!(with_scheduler(|s| s.try_join_immediate(tid)), Some(arg));
+        assert_eq!(my_thread_detach(tid), 0);
+        assert_eq!(PAYLOAD_DROPS.with(Cell::get), 101);
+        assert_eq!(with_scheduler(|s| s.try_join_immediate(tid)), None);
+        assert_eq!(my_thread_set_finalizer(tid, drop_payload), EINVAL);
 
-        {
-            let thr = self.threads.get_mut(&tid).unwrap();
-            thr.scheduler = policy;
-            thr.tickets = 0;
-            thr.rt_params = None;
+        // Con join, el resultado es de quien hace join y el finalizador no corre.
+        let arg = Box::into_raw(Box::new(Payload)) as *mut c_void;
+        let tid = my_thread_create(payload_worker, arg, SchedPolicy::RoundRobin);
+        my_thread_set_finalizer(tid, drop_payload);
+        drop_payload(my_thread_join(tid));
+        assert_eq!(PAYLOAD_DROPS.with(Cell::get), 102);
+    }
 
-            match policy {
-                SchedPolicy::RoundRobin => {}
-                SchedPolicy::Lottery { tickets } => {
-                    thr.tickets = if tickets == 0 { 1 } else { tickets };
-                }
-                SchedPolicy::RealTime { deadline } => {
-                    thr.rt_params = Some(RealTimeParams { deadline });
-                }
+    #[test]
+    fn test_detect_deadlock_follows_block_reasons() {
+        let sleeper = || my_thread_spawn(|| my_thread_sleep(5), SchedPolicy::RoundRobin);
+        let (a, b, c) = (sleeper(), sleeper(), sleeper());
+        my_thread_yield();
+        assert_eq!(with_scheduler(|s| s.detect_deadlock()), None);
+
+        // Se arma a mano un ciclo b -> c -> b (con `a` esperando afuera) que
+        // la verificación al bloquearse no habría dejado formar.
+        let saved = with_scheduler(|s| {
+            let saved: Vec<_> = [a, b, c].iter().map(|t| s.threads[t].block_reason).collect();
+            s.threads.get_mut(&a).unwrap().block_reason = Some(BlockReason::Join { target: b, until: None });
+            s.threads.get_mut(&b).unwrap().block_reason = Some(BlockReason::Join { target: c, until: None });
+            s.threads.get_mut(&c).unwrap().block_reason =
+                Some(BlockReason::Mutex { mutex: ptr::null(), id: 0, owner: b, until: None });
+            saved
+        });
+        assert_eq!(with_scheduler(|s| s.detect_deadlock()), Some(vec![b, c]));
+
+        with_scheduler(|s| {
+            for (t, reason) in [a, b, c].iter().zip(saved) {
+                s.threads.get_mut(t).unwrap().block_reason = reason;
             }
+        });
+        for tid in [a, b, c] {
+            my_thread_join(tid);
         }
+    }
 
-        // Si el hilo está listo, re-encolar según nueva política.
-        if self.threads.get(&tid).unwrap().state == ThreadState::Ready {
-            self.enqueue_ready(tid);
-        }
+    #[test]
+    fn test_kill_hands_held_mutexes_to_waiters() {
+        let (a, b) = (Rc::new(MyMutex::new()), Rc::new(MyMutex::new()));
+        let looping = {
+            let (a, b) = (Rc::clone(&a), Rc::clone(&b));
+            my_thread_spawn(
+                move || {
+                    my_mutex_lock(&a);
+                    let _guard = b.lock_guard();
+                    loop {
+                        my_thread_yield();
+                    }
+                },
+                SchedPolicy::RoundRobin,
+            )
+        };
+        let lock_and_report = |m: &Rc<MyMutex>| {
+            let m = Rc::clone(m);
+            my_thread_spawn_with_result(
+                move || {
+                    let rc = my_mutex_lock(&m);
+                    let owner = m.owner();
+                    my_mutex_unlock(&m);
+                    (rc, owner)
+                },
+                SchedPolicy::RoundRobin,
+            )
+        };
+        let doomed = lock_and_report(&a);
+        let waiter = lock_and_report(&a);
+        my_thread_yield();
+        assert_eq!(*a.core.waiters.borrow(), vec![doomed.tid(), waiter.tid()]);
 
-        0
+        // Un waiter muerto deja la cola; el dueño muerto suelta sus mutexes.
+        assert_eq!(my_thread_kill(doomed.tid()), 0);
+        assert_eq!(*a.core.waiters.borrow(), vec![waiter.tid()]);
+        assert_eq!(my_thread_kill(looping), 0);
+        assert_eq!(a.owner(), Some(waiter.tid()));
+        assert_eq!(b.owner(), None);
+
+        let waiter_tid = waiter.tid();
+        assert_eq!(waiter.join(), (0, Some(waiter_tid)));
+        assert_eq!(my_thread_join(looping), MY_THREAD_KILLED);
+        assert_eq!(my_thread_join(doomed.tid()), MY_THREAD_KILLED);
+
+        assert_eq!(my_thread_kill(looping), EINVAL);
+        assert_eq!(my_thread_kill(0), EINVAL);
+        let me = my_thread_spawn_with_result(
+            || my_thread_kill(with_scheduler(|s| s.current.unwrap())),
+            SchedPolicy::RoundRobin,
+        );
+        assert_eq!(me.join(), EINVAL);
     }
 
-    /// Actualiza el deadline de un hilo en el scheduler de Tiempo Real.
-    ///
-    /// El `deadline` se interpreta como un tiempo absoluto en las mismas
-    /// unidades que use la simulación (por ejemplo, ticks). Solo es válido
-    /// para hilos cuya política actual sea `SchedPolicy::RealTime`, en caso
-    /// contrario se devuelve `EINVAL`.
-    fn set_realtime_deadline(&mut self, tid: MyThreadId, deadline: u64) -> c_int {
-        // Verificamos que el hilo exista.
-        let thr = match self.threads.get_mut(&tid) {
-            None => return EINVAL,
-            Some(t) => t,
+    #[test]
+    fn test_kill_removes_the_thread_from_wait_queues() {
+        // Semáforo: el permiso va al que sigue vivo.
+        let sem = Rc::new(MySemaphore::new(0));
+        let waiter = || {
+            let sem = Rc::clone(&sem);
+            my_thread_spawn(move || assert_eq!(my_sem_wait(&sem), 0), SchedPolicy::RoundRobin)
         };
+        let (doomed, survivor) = (waiter(), waiter());
+        my_thread_yield();
+        assert_eq!(my_thread_kill(doomed), 0);
+        assert_eq!(my_sem_post(&sem), 0);
+        my_thread_join(survivor);
+        assert_eq!(sem.value(), 0);
+        my_thread_join(doomed);
 
-        // Solo tiene sentido actualizar el deadline de hilos Tiempo Real.
-        match thr.scheduler {
-            SchedPolicy::RealTime { .. } => {
-                thr.rt_params = Some(RealTimeParams { deadline });
-            }
-            _ => return EINVAL,
-        }
+        // Canal: el mensaje va al que sigue vivo.
+        let chan = MyChannel::new(1);
+        let receiver = || {
+            let chan = chan.clone();
+            my_thread_spawn_with_result(move || chan.recv(), SchedPolicy::RoundRobin)
+        };
+        let (doomed, survivor) = (receiver(), receiver());
+        my_thread_yield();
+        assert_eq!(my_thread_kill(doomed.tid()), 0);
+        chan.send(7).unwrap();
+        assert_eq!(survivor.join(), Ok(7));
+        my_thread_join(doomed.tid());
 
-        // Si estaba listo, lo reinsertamos en la lista Tiempo Real
-        // para que el orden por deadline se actualice.
-        if thr.state == ThreadState::Ready {
-            self.remove_from_ready_lists(tid);
-            self.realtime_list.push(tid);
-        }
+        // RwLock: al soltarlo no queda tomado por un escritor muerto.
+        let lock = Box::into_raw(Box::new(MyRwLock::new()));
+        let spawn_on = move |write: bool| {
+            my_thread_spawn(
+                move || unsafe {
+                    let rc = if write {
+                        my_rwlock_wrlock(&mut *lock)
+                    } else {
+                        my_rwlock_rdlock(&mut *lock)
+                    };
+                    assert_eq!(rc, 0);
+                    assert_eq!(my_rwlock_unlock(&mut *lock), 0);
+                },
+                SchedPolicy::RoundRobin,
+            )
+        };
+        unsafe {
+            assert_eq!(my_rwlock_wrlock(&mut *lock), 0);
+            let (doomed, reader) = (spawn_on(true), spawn_on(false));
+            my_thread_yield();
+            assert_eq!(my_thread_kill(doomed), 0);
+            assert_eq!(my_rwlock_unlock(&mut *lock), 0);
+            my_thread_join(reader);
+            my_thread_join(doomed);
 
-        0
+            // Si el escritor muerto frenaba a lectores, ahora entran.
+            assert_eq!(my_rwlock_rdlock(&mut *lock), 0);
+            let (doomed, reader) = (spawn_on(true), spawn_on(false));
+            my_thread_yield();
+            assert_eq!(my_thread_kill(doomed), 0);
+            my_thread_join(reader);
+            my_thread_join(doomed);
+            assert_eq!(my_rwlock_unlock(&mut *lock), 0);
+            assert_eq!(my_rwlock_destroy(&mut *lock), 0);
+            drop(Box::from_raw(lock));
+        }
     }
 
-    /// Obtiene el deadline actual de un hilo de Tiempo Real, si lo tiene.
-    fn get_realtime_deadline(&self, tid: MyThreadId) -> Option<u64> {
-        let thr = self.threads.get(&tid)?;
-        thr.rt_params.as_ref().map(|p| p.deadline)
+    #[test]
+    fn test_kill_and_cancel_skip_dropped_and_moved_mutexes() {
+        // Toma un mutex que después mueve a la heap y otro que destruye.
+        let holder = |ids: Rc<Cell<(MyMutexId, MyMutexId)>>| {
+            move || {
+                let kept = MyMutex::new();
+                let dropped = MyMutex::new();
+                my_mutex_lock(&kept);
+                my_mutex_lock(&dropped);
+                ids.set((kept.id(), dropped.id()));
+                let kept = Box::new(kept);
+                drop(dropped);
+                loop {
+                    assert_eq!(kept.owner(), Some(my_thread_self()));
+                    my_thread_yield();
+                }
+            }
+        };
+
+        for kill in [true, false] {
+            let ids = Rc::new(Cell::new((0, 0)));
+            let tid = my_thread_spawn(holder(Rc::clone(&ids)), SchedPolicy::RoundRobin);
+            my_thread_yield();
+            let (kept, _) = ids.get();
+            assert_eq!(my_sched_lock_report().held, vec![(tid, vec![kept])]);
+
+            if kill {
+                assert_eq!(my_thread_kill(tid), 0);
+            } else {
+                assert_eq!(my_thread_cancel(tid), 0);
+                my_thread_yield();
+            }
+            assert!(my_sched_lock_report().held.is_empty());
+            my_thread_join(tid);
+        }
     }
-}
 
+    #[test]
+    fn test_lock_report_lists_owners_waiters_and_held_sets() {
+        let (a, b) = (Rc::new(MyMutex::new()), Rc::new(MyMutex::new()));
+        assert_ne!(a.id(), b.id());
+        my_mutex_lock(&a);
+        assert_eq!(my_mutex_trylock(&b), 0);
+        let waiter = {
+            let a = Rc::clone(&a);
+            my_thread_spawn(
+                move || {
+                    my_mutex_lock(&a);
+                    my_mutex_unlock(&a);
+                },
+                SchedPolicy::RoundRobin,
+            )
+        };
+        my_thread_yield();
 
-/// Scheduler global en espacio de usuario.
-static mut SCHEDULER: *mut Scheduler = std::ptr::null_mut();
+        let report = my_sched_lock_report();
+        assert_eq!(
+            report.mutexes,
+            vec![
+                MyMutexInfo { id: a.id(), owner: 0, waiters: vec![waiter] },
+                MyMutexInfo { id: b.id(), owner: 0, waiters: vec![] },
+            ]
+        );
+        assert_eq!(report.held, vec![(0, vec![a.id(), b.id()])]);
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "mutex #{0}: dueño hilo 0, esperan hilo {2}\nmutex #{1}: dueño hilo 0\nhilo 0: tiene #{0}, #{1}\n",
+                a.id(),
+                b.id(),
+                waiter
+            )
+        );
+        assert_eq!(
+            my_thread_block_reason(waiter),
+            Some(MyBlockReason::Mutex { mutex: a.id(), owner: 0 })
+        );
 
-/// Acceso global al scheduler (lazy-init).
-fn scheduler() -> &'static mut Scheduler {
-    unsafe {
-        if SCHEDULER.is_null() {
-            let boxed = Box::new(Scheduler::new());
-            let leaked: &'static mut Scheduler = Box::leak(boxed);
-            SCHEDULER = leaked as *mut Scheduler;
-        }
-        &mut *SCHEDULER
+        my_mutex_unlock(&b);
+        my_mutex_unlock(&a);
+        assert_eq!(my_sched_lock_report().held, vec![(waiter, vec![a.id()])]);
+        my_thread_join(waiter);
+        assert!(my_sched_lock_report().mutexes.is_empty());
     }
-}
 
-/// Trampolín: es la función que todos los hilos nuevos ejecutan primero.
-extern "C" fn thread_trampoline() {
-    unsafe {
-        let sched = scheduler();
-        let tid = sched.current_thread_id().expect("no current thread in trampoline");
+    #[test]
+    fn test_lock_report_forgets_dropped_mutexes() {
+        let m = Box::new(MyMutex::new());
+        assert_eq!(my_mutex_lock(&m), 0);
+        drop(m);
+        assert_eq!(my_sched_lock_report(), MyLockReport { mutexes: vec![], held: vec![] });
 
-        // Obtenemos función y argumento del TCB
-        let (func, arg) = {
-            let t = sched.get_thread(tid).expect("thread not found in trampoline");
-            (t.start_routine.expect("no start_routine"), t.arg)
-        };
+        // Un hilo que termina con un mutex local tomado.
+        let tid = my_thread_spawn(
+            || {
+                let m = MyMutex::new();
+                my_mutex_lock(&m);
+            },
+            SchedPolicy::RoundRobin,
+        );
+        my_thread_yield();
+        assert_eq!(my_thread_state(tid), MyThreadState::Finished);
+        assert!(my_sched_lock_report().held.is_empty());
+        my_thread_join(tid);
 
-        let result = func(arg);
-        my_thread_end(result);
+        // Mover un mutex tomado no lo suelta ni lo pierde.
+        let m = MyMutex::new();
+        my_mutex_lock(&m);
+        let moved = Box::new(m);
+        assert_eq!(my_sched_lock_report().held, vec![(0, vec![moved.id()])]);
+        assert_eq!(my_mutex_unlock(&moved), 0);
+        assert!(my_sched_lock_report().held.is_empty());
     }
-}
 
-// ============ API pública estilo mypthreads ============ //
+    #[test]
+    fn test_rwlock_readers_share_and_writers_go_first() {
+        let lock = Box::into_raw(Box::new(MyRwLock::new()));
+        let log = Box::into_raw(Box::new(Vec::<&'static str>::new()));
+        let max_readers = Rc::new(Cell::new(0));
 
-/// Crea un hilo de usuario con la política indicada.
-/// Devuelve el id del hilo (MyThreadId).
-pub fn my_thread_create(
-    start_routine: ThreadFunc,
-    arg: *mut c_void,
-    policy: SchedPolicy,
-) -> MyThreadId {
-    unsafe { scheduler().create_thread(start_routine, arg, policy) }
-}
+        let seen = Rc::clone(&max_readers);
+        let reader = move |name: &'static str| {
+            let max_readers = Rc::clone(&seen);
+            move || unsafe {
+                assert_eq!(my_rwlock_rdlock(&mut *lock), 0);
+                (*log).push(name);
+                for _ in 0..3 {
+                    max_readers.set(max_readers.get().max((*lock).readers));
+                    my_thread_yield();
+                }
+                assert_eq!(my_rwlock_unlock(&mut *lock), 0);
+            }
+        };
+        let writer = move |name: &'static str| {
+            move || unsafe {
+                assert_eq!(my_rwlock_wrlock(&mut *lock), 0);
+                assert_eq!((*lock).readers, 0);
+                (*log).push(name);
+                my_thread_yield();
+                assert_eq!(my_rwlock_unlock(&mut *lock), 0);
+            }
+        };
 
-/// Finaliza el hilo actual, devolviendo `retval` a quien haga join.
-/// No debería regresar.
-pub fn my_thread_end(retval: *mut c_void) -> ! {
-    unsafe { scheduler().finish_current(retval) }
-}
+        // Dos lectores comparten el lock; el escritor espera a ambos.
+        let mut tids = vec![
+            my_thread_spawn(reader("r1"), SchedPolicy::RoundRobin),
+            my_thread_spawn(reader("r2"), SchedPolicy::RoundRobin),
+        ];
+        my_thread_yield();
+        tids.push(my_thread_spawn(writer("w1"), SchedPolicy::RoundRobin));
+        for tid in tids.drain(..) {
+            my_thread_join(tid);
+        }
+        assert_eq!(max_readers.get(), 2);
+        assert_eq!(unsafe { &*log }, &["r1", "r2", "w1"]);
 
-/// El hilo actual cede la CPU.
-pub fn my_thread_yield() {
-    unsafe {
-        scheduler().yield_current();
+        // Con el lock tomado para escribir, al soltarlo pasa primero al otro
+        // escritor aunque el lector haya llegado antes.
+        unsafe {
+            (*log).clear();
+            assert_eq!(my_rwlock_wrlock(&mut *lock), 0);
+            assert_eq!(my_rwlock_rdlock(&mut *lock), EDEADLK);
+        }
+        tids.push(my_thread_spawn(reader("r3"), SchedPolicy::RoundRobin));
+        tids.push(my_thread_spawn(writer("w2"), SchedPolicy::RoundRobin));
+        my_thread_yield();
+        unsafe {
+            assert_eq!(my_rwlock_destroy(&mut *lock), EBUSY);
+            assert_eq!(my_rwlock_unlock(&mut *lock), 0);
+        }
+        for tid in tids {
+            my_thread_join(tid);
+        }
+        unsafe {
+            assert_eq!(&*log, &["w2", "r3"]);
+            assert_eq!(my_rwlock_unlock(&mut *lock), EINVAL);
+            assert_eq!(my_rwlock_destroy(&mut *lock), 0);
+            drop(Box::from_raw(lock));
+            drop(Box::from_raw(log));
+        }
     }
-}
 
-/// Bloquea hasta que el hilo `target` termine y devuelve su resultado.
-pub fn my_thread_join(target: MyThreadId) -> *mut c_void {
-    unsafe {
-        let sched = scheduler();
-        let curr = sched.current_thread_id().expect("join sin hilo actual");
+    #[test]
+    fn test_rwlock_overlapping_readers_do_not_starve_a_writer() {
+        let lock = Box::into_raw(Box::new(MyRwLock::new()));
+        let log = Box::into_raw(Box::new(Vec::<&'static str>::new()));
 
-        if curr == target {
-            // No tiene sentido hacer join a uno mismo.
-            return ptr::null_mut();
+        // Cada lector vuelve a entrar mientras el otro todavía lee, así que
+        // el lock nunca quedaría libre si siguieran entrando lectores.
+        let reader = move |name: &'static str| {
+            move || unsafe {
+                for _ in 0..5 {
+                    assert_eq!(my_rwlock_rdlock(&mut *lock), 0);
+                    (*log).push(name);
+                    my_thread_yield();
+                    my_thread_yield();
+                    assert_eq!(my_rwlock_unlock(&mut *lock), 0);
+                }
+            }
+        };
+        let mut tids = vec![
+            my_thread_spawn(reader("r1"), SchedPolicy::RoundRobin),
+            my_thread_spawn(reader("r2"), SchedPolicy::RoundRobin),
+        ];
+        my_thread_yield();
+        tids.push(my_thread_spawn(
+            move || unsafe {
+                assert_eq!(my_rwlock_wrlock(&mut *lock), 0);
+                (*log).push("w");
+                assert_eq!(my_rwlock_unlock(&mut *lock), 0);
+            },
+            SchedPolicy::RoundRobin,
+        ));
+        for tid in tids {
+            my_thread_join(tid);
         }
 
-        if let Some(res) = sched.try_join_immediate(target) {
-            return res;
+        unsafe {
+            let log = &*log;
+            let w = log.iter().position(|&name| name == "w").unwrap();
+            assert!(w <= 3, "{log:?}");
+            assert_eq!(log.len(), 11);
+            drop(Box::from_raw(lock));
         }
+        drop(unsafe { Box::from_raw(log) });
+    }
 
-        // Bloqueamos el hilo actual en espera del target
-        {
-            let t = sched.get_thread_mut(target).expect("target de join no encontrado");
-            t.joined_by = Some(curr);
-        }
+    struct ChannelArgs {
+        chan: MyChannel<u64>,
+        /// Productor: primer valor a enviar. Consumidor: sin uso.
+        base: u64,
+        received: *mut Vec<u64>,
+    }
 
-        scheduler().block_current(BlockReason::Join { target });
+    const MESSAGES_PER_PRODUCER: u64 = 50;
 
-        // Cuando despertamos, ya terminó
-        let res = scheduler()
-            .get_thread(target)
-            .expect("thread desapareció durante join")
-            .result;
-        res
+    extern "C" fn channel_producer(arg: *mut c_void) -> *mut c_void {
+        let args = unsafe { Box::from_raw(arg as *mut ChannelArgs) };
+        for i in 0..MESSAGES_PER_PRODUCER {
+            args.chan.send(args.base + i).unwrap();
+            if i % 7 == 0 {
+                my_thread_yield();
+            }
+        }
+        ptr::null_mut()
     }
-}
 
-/// Marca un hilo como detached (no se espera join).
-pub fn my_thread_detach(tid: MyThreadId) -> c_int {
-    unsafe {
-        let sched = scheduler();
-        if let Some(t) = sched.get_thread_mut(tid) {
-            t.detached = true;
-            0
-        } else {
-            EINVAL
+    extern "C" fn channel_consumer(arg: *mut c_void) -> *mut c_void {
+        let args = unsafe { Box::from_raw(arg as *mut ChannelArgs) };
+        while let Ok(value) = args.chan.recv() {
+            unsafe { (*args.received).push(value) };
         }
+        ptr::null_mut()
     }
-}
 
-/// Cambia la política de scheduling de un hilo.
-pub fn my_thread_chsched(tid: MyThreadId, policy: SchedPolicy) -> c_int {
-    unsafe { scheduler().change_scheduler(tid, policy) }
-}
+    #[test]
+    fn test_channel_producers_consumers() {
+        let chan = MyChannel::new(4);
+        let mut received: Vec<u64> = Vec::new();
+        let spawn = |routine: ThreadFunc, base: u64, policy: SchedPolicy, received: *mut Vec<u64>| {
+            let args = Box::new(ChannelArgs { chan: chan.clone(), base, received });
+            my_thread_create(routine, Box::into_raw(args) as *mut c_void, policy)
+        };
 
-/// Actualiza el `deadline` de un hilo de Tiempo Real.
-///
-/// El valor de `deadline` es un tiempo absoluto expresado en las mismas
-/// unidades que utilice la simulación (por ejemplo, ticks). Este llamado
-/// solo es válido si el hilo fue configurado con `SchedPolicy::RealTime`.
-/// En caso contrario, devuelve `EINVAL`.
+        let out = &mut received as *mut Vec<u64>;
+        let consumers = [
+            spawn(channel_consumer, 0, SchedPolicy::Mlfq, out),
+            spawn(channel_consumer, 0, SchedPolicy::Lottery { tickets: 3 }, out),
+        ];
+        let producers = [
+            spawn(channel_producer, 0, SchedPolicy::RoundRobin, out),
+            spawn(channel_producer, 1000, SchedPolicy::Lottery { tickets: 5 }, out),
+            spawn(channel_producer, 2000, SchedPolicy::RealTime { deadline: 20 }, out),
+        ];
+        for tid in producers {
+            my_thread_join(tid);
+        }
+        // Cerrar despierta a los consumidores bloqueados con el canal vacío.
+        chan.close();
+        for tid in consumers {
+            my_thread_join(tid);
+        }
 
-pub fn my_thread_set_realtime_deadline(tid: MyThreadId, deadline: u64) -> c_int {
-    unsafe { scheduler().set_realtime_deadline(tid, deadline) }
-}
+        received.sort_unstable();
+        let expected: Vec<u64> = [0, 1000, 2000]
+            .iter()
+            .flat_map(|base| (0..MESSAGES_PER_PRODUCER).map(move |i| base + i))
+            .collect();
+        assert_eq!(received, expected);
+    }
 
-/// Devuelve el `deadline` actual de un hilo de Tiempo Real, si existe.
-pub fn my_thread_get_realtime_deadline(tid: MyThreadId) -> Option<u64> {
-    unsafe { scheduler().get_realtime_deadline(tid) }
-}
+    #[test]
+    fn test_channel_try_ops_and_close() {
+        let chan = MyChannel::new(2);
+        assert_eq!(chan.try_recv(), Err(RecvError::Empty));
+        assert_eq!(chan.try_send(1), Ok(()));
+        assert_eq!(chan.try_send(2), Ok(()));
+        assert_eq!(chan.try_send(3), Err(SendError::Full(3)));
+        assert_eq!(chan.len(), 2);
 
-/// ============ Implementación del mutex propio (mymutex) ============ ///
+        // Lo pendiente se sigue entregando después de cerrar.
+        chan.close();
+        assert!(chan.is_closed());
+        assert_eq!(chan.send(4), Err(SendError::Closed(4)));
+        assert_eq!(chan.recv(), Ok(1));
+        assert_eq!(chan.try_recv(), Ok(2));
+        assert_eq!(chan.recv(), Err(RecvError::Closed));
+        assert_eq!(chan.try_recv(), Err(RecvError::Closed));
+    }
 
-#[derive(Debug)]
-pub struct MyMutex {
-    locked: bool,
-    owner: Option<MyThreadId>,
-    waiters: VecDeque<MyThreadId>,
-}
+    struct LotteryOrderArgs {
+        index: usize,
+        order: *mut Vec<usize>,
+    }
 
-impl MyMutex {
-    pub fn new() -> Self {
-        MyMutex {
-            locked: false,
-            owner: None,
-            waiters: VecDeque::new(),
+    extern "C" fn lottery_order_worker(arg: *mut c_void) -> *mut c_void {
+        let args = unsafe { Box::from_raw(arg as *mut LotteryOrderArgs) };
+        for _ in 0..20 {
+            unsafe { (*args.order).push(args.index) };
+            my_thread_yield();
         }
+        ptr::null_mut()
     }
-}
 
-/// Inicializa un mutex.
-pub fn my_mutex_init(m: &mut MyMutex) -> c_int {
-    *m = MyMutex::new();
-    0
-}
+    /// Orden en que corren cuatro hilos Lottery, en un scheduler nuevo (cada
+    /// hilo del sistema tiene el suyo).
+    fn lottery_order(seed: Option<u64>) -> Vec<usize> {
+        std::thread::spawn(move || {
+            assert_eq!(my_sched_get_seed(), DEFAULT_SEED);
+            if let Some(seed) = seed {
+                my_sched_set_seed(seed);
+                assert_eq!(my_sched_get_seed(), seed);
+            }
+            let mut order = Vec::new();
+            let tids: Vec<_> = (0..4)
+                .map(|index| {
+                    let args = Box::new(LotteryOrderArgs { index, order: &mut order });
+                    let arg = Box::into_raw(args) as *mut c_void;
+                    let tickets = 1 + index as u32 * 2;
+                    my_thread_create(lottery_order_worker, arg, SchedPolicy::Lottery { tickets })
+                })
+                .collect();
+            for tid in tids {
+                my_thread_join(tid);
+            }
+            order
+        })
+        .join()
+        .unwrap()
+    }
 
-/// Destruye un mutex (simple, sin liberar recursos extra).
-pub fn my_mutex_destroy(m: &mut MyMutex) -> c_int {
-    if m.locked || !m.waiters.is_empty() {
-        // Semántica aproximada a pthread: no destruir si está bloqueado.
-        EBUSY
-    } else {
-        // Nada especial que hacer.
-        0
+    #[test]
+    fn test_seed_makes_lottery_reproducible() {
+        let a = lottery_order(Some(42));
+        assert_eq!(a.len(), 80);
+        assert_eq!(lottery_order(Some(42)), a);
+        assert_ne!(lottery_order(Some(7)), a);
+        assert_eq!(lottery_order(Some(DEFAULT_SEED)), lottery_order(None));
     }
-}
 
-/// Intenta tomar el lock; si está ocupado, retorna EBUSY.
-pub fn my_mutex_trylock(m: &mut MyMutex) -> c_int {
-    unsafe {
-        let sched = scheduler();
-        let curr = sched.current_thread_id().expect("trylock sin hilo actual");
+    thread_local! {
+        static ONCE_RUNS: Cell<u32> = const { Cell::new(0) };
+    }
 
-        if !m.locked {
-            m.locked = true;
-            m.owner = Some(curr);
-            0
-        } else {
-            EBUSY
+    /// Inicializador lento: cede la CPU para que los demás lleguen mientras corre.
+    extern "C" fn slow_init() {
+        ONCE_RUNS.with(|c| c.set(c.get() + 1));
+        for _ in 0..5 {
+            my_thread_yield();
         }
     }
-}
 
-/// Bloquea hasta adquirir el mutex.
-pub fn my_mutex_lock(m: &mut MyMutex) -> c_int {
-    unsafe {
-        let sched = scheduler();
-        let curr = sched.current_thread_id().expect("lock sin hilo actual");
+    extern "C" fn dying_init() {
+        my_thread_yield();
+        my_thread_end(ptr::null_mut());
+    }
 
-        if !m.locked {
-            m.locked = true;
-            m.owner = Some(curr);
-            return 0;
+    struct OnceArgs {
+        once: MyOnce,
+        init: extern "C" fn(),
+        results: Vec<c_int>,
+    }
+
+    extern "C" fn once_worker(arg: *mut c_void) -> *mut c_void {
+        let args = arg as *mut OnceArgs;
+        unsafe {
+            let rc = my_once(&mut (*args).once, (*args).init);
+            (*args).results.push(rc);
         }
+        ptr::null_mut()
+    }
+
+    fn race_once(init: extern "C" fn()) -> Vec<c_int> {
+        let mut args = OnceArgs { once: MyOnce::new(), init, results: Vec::new() };
+        let arg = &mut args as *mut OnceArgs as *mut c_void;
+        let tids: Vec<MyThreadId> = (0..10)
+            .map(|_| my_thread_create(once_worker, arg, SchedPolicy::RoundRobin))
+            .collect();
+        for tid in tids {
+            my_thread_join(tid);
+        }
+        unsafe { (*(arg as *mut OnceArgs)).results.clone() }
+    }
 
-        // Si ya está tomado, nos encolamos y bloqueamos
-        m.waiters.push_back(curr);
-        scheduler().block_current(BlockReason::Mutex);
+    #[test]
+    fn test_join_timeout_gives_up_on_stuck_threads() {
+        let release = Rc::new(Cell::new(false));
+        let stuck = {
+            let release = Rc::clone(&release);
+            my_thread_spawn(
+                move || {
+                    while !release.get() {
+                        my_thread_yield();
+                    }
+                    my_thread_end(7 as *mut c_void);
+                },
+                SchedPolicy::RoundRobin,
+            )
+        };
 
-        // Cuando el hilo despierte, debe ser el dueño del mutex
-        debug_assert!(m.locked);
-        debug_assert_eq!(m.owner, Some(curr));
+        let before = my_sched_ticks();
+        assert_eq!(my_thread_join_timeout(stuck, 10), Err(ThreadError::JoinTimeout { tid: stuck }));
+        assert!(my_sched_ticks() >= before + 10);
+        assert_eq!(my_thread_join_timeout(stuck, 0), Err(ThreadError::JoinTimeout { tid: stuck }));
+        // El hilo sigue vivo y ya nadie lo espera.
+        assert_eq!(my_thread_stats(stuck).unwrap().state, MyThreadState::Ready);
+        assert!(with_scheduler(|s| s.threads[&stuck].joined_by.is_none()));
 
-        0
+        release.set(true);
+        assert_eq!(my_thread_join_timeout(stuck, 100), Ok(7 as *mut c_void));
+        assert_eq!(my_thread_join_timeout(stuck, 1), Err(ThreadError::NoSuchThread { tid: stuck }));
+        assert_eq!(my_thread_join_timeout(0, 1), Err(ThreadError::NotJoinable { tid: 0 }));
+        assert_eq!(ThreadError::JoinTimeout { tid: stuck }.errno(), ETIMEDOUT);
     }
-}
 
-/// Libera el mutex y despierta a un waiter si existe.
-pub fn my_mutex_unlock(m: &mut MyMutex) -> c_int {
-    unsafe {
-        let sched = scheduler();
-        let curr = sched.current_thread_id().expect("unlock sin hilo actual");
+    #[test]
+    fn test_join_boxed_round_trips_the_box() {
+        let tid = my_thread_spawn(
+            || {
+                let squares: Vec<u64> = (1..=4).map(|i| i * i).collect();
+                my_thread_yield();
+                my_thread_end_boxed(Box::new(squares));
+            },
+            SchedPolicy::RoundRobin,
+        );
+        assert_eq!(my_thread_join_boxed::<Vec<u64>>(tid).as_deref(), Some(&vec![1, 4, 9, 16]));
 
-        if m.owner != Some(curr) {
-            // No es el dueño del mutex
-            return EINVAL;
-        }
+        // Un hilo que termina sin resultado da `None`.
+        let tid = my_thread_spawn(|| {}, SchedPolicy::RoundRobin);
+        assert!(my_thread_join_boxed::<Vec<u64>>(tid).is_none());
+    }
 
-        if let Some(next_tid) = m.waiters.pop_front() {
-            // Le pasamos el lock directamente al siguiente hilo
-            m.locked = true;
-            m.owner = Some(next_tid);
-            scheduler().unblock(next_tid);
-        } else {
-            // No hay nadie esperando
-            m.locked = false;
-            m.owner = None;
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "no devolvió un Box<alloc::string::String>")]
+    fn test_join_boxed_detects_wrong_type() {
+        let tid = my_thread_spawn(|| my_thread_end_boxed(Box::new(7u64)), SchedPolicy::RoundRobin);
+        my_thread_join_boxed::<String>(tid);
+    }
+
+    #[test]
+    fn test_my_once_call_with_closures() {
+        let once = Box::into_raw(Box::new(MyOnce::new()));
+        let runs = Rc::new(Cell::new(0));
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let tids: Vec<_> = (0..5)
+            .map(|_| {
+                let (runs, results) = (Rc::clone(&runs), Rc::clone(&results));
+                my_thread_spawn(
+                    move || {
+                        let rc = my_once_call(unsafe { &mut *once }, || {
+                            my_thread_yield();
+                            runs.set(runs.get() + 1);
+                        });
+                        results.borrow_mut().push(rc);
+                    },
+                    SchedPolicy::RoundRobin,
+                )
+            })
+            .collect();
+        for tid in tids {
+            my_thread_join(tid);
         }
+        assert_eq!(runs.get(), 1);
+        assert_eq!(*results.borrow(), [0; 5]);
+        drop(unsafe { Box::from_raw(once) });
 
-        0
+        // Un pánico en el inicializador lo envenena.
+        let mut once = MyOnce::new();
+        let poison = || my_once_call(&mut once, || panic!("falló"));
+        assert!(panic::catch_unwind(AssertUnwindSafe(poison)).is_err());
+        assert_eq!(my_once_call(&mut once, || {}), ENOTRECOVERABLE);
+    }
+
+    #[test]
+    fn test_my_once_runs_initializer_once() {
+        let results = race_once(slow_init);
+        assert_eq!(ONCE_RUNS.with(|c| c.get()), 1);
+        assert_eq!(results, vec![0; 10]);
+
+        // El inicializador murió: los 9 que esperaban reciben el error.
+        let results = race_once(dying_init);
+        assert_eq!(results, vec![ENOTRECOVERABLE; 9]);
     }
 }
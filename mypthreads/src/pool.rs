@@ -0,0 +1,133 @@
+// src/pool.rs
+
+//! Pool de hilos: N workers creados de antemano que sacan trabajos de una
+//! cola compartida, protegida por un `MyMutex` y dos `MyCondVar`.
+
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::{
+    my_cond_broadcast, my_cond_signal, my_cond_wait, my_mutex_lock, my_mutex_unlock,
+    my_thread_join, my_thread_spawn, MyCondVar, MyMutex, MyThreadId, SchedPolicy,
+};
+
+type Job = Box<dyn FnOnce()>;
+
+/// Estado que comparten el pool y sus workers. Solo se toca con `mutex`
+/// tomado.
+struct PoolShared {
+    mutex: MyMutex,
+    queue: VecDeque<Job>,
+    /// Trabajos encolados más los que están corriendo.
+    pending: usize,
+    shutdown: bool,
+    /// Señalada al encolar trabajo o al cerrar el pool.
+    work: MyCondVar,
+    /// Señalada cuando `pending` llega a cero.
+    idle: MyCondVar,
+}
+
+/// Pool de hilos de usuario que ejecutan trabajos en orden de llegada.
+///
+/// Al destruirse espera a que se vacíe la cola y hace join de sus workers.
+pub struct ThreadPool {
+    shared: *mut PoolShared,
+    workers: Vec<MyThreadId>,
+}
+
+impl ThreadPool {
+    /// Crea `n` workers con la política `policy`.
+    ///
+    /// # Panics
+    /// Si `n` es 0, o en los mismos casos que `my_thread_spawn`.
+    pub fn new(n: usize, policy: SchedPolicy) -> Self {
+        assert!(n > 0, "ThreadPool::new: el pool necesita al menos un worker");
+        let shared = Box::into_raw(Box::new(PoolShared {
+            mutex: MyMutex::new(),
+            queue: VecDeque::new(),
+            pending: 0,
+            shutdown: false,
+            work: MyCondVar::new(),
+            idle: MyCondVar::new(),
+        }));
+        let workers = (0..n).map(|_| my_thread_spawn(move || worker_loop(shared), policy)).collect();
+        ThreadPool { shared, workers }
+    }
+
+    /// Cantidad de workers.
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Encola `job`; lo ejecutará el primer worker libre.
+    pub fn submit<F>(&self, job: F)
+    where
+        F: FnOnce() + 'static,
+    {
+        let shared = self.shared;
+        unsafe {
+            my_mutex_lock(&(*shared).mutex);
+            (*shared).queue.push_back(Box::new(job));
+            (*shared).pending += 1;
+            my_cond_signal(&mut (*shared).work);
+            my_mutex_unlock(&(*shared).mutex);
+        }
+    }
+
+    /// Espera a que la cola se vacíe y terminen los trabajos en curso.
+    /// El pool sigue disponible para más trabajos.
+    pub fn join_all(&self) {
+        let shared = self.shared;
+        unsafe {
+            my_mutex_lock(&(*shared).mutex);
+            while (*shared).pending > 0 {
+                my_cond_wait(&mut (*shared).idle, &(*shared).mutex);
+            }
+            my_mutex_unlock(&(*shared).mutex);
+        }
+    }
+}
+
+impl Drop for ThreadPool {
+    fn drop(&mut self) {
+        self.join_all();
+        let shared = self.shared;
+        unsafe {
+            my_mutex_lock(&(*shared).mutex);
+            (*shared).shutdown = true;
+            my_cond_broadcast(&mut (*shared).work);
+            my_mutex_unlock(&(*shared).mutex);
+        }
+        for &tid in &self.workers {
+            my_thread_join(tid);
+        }
+        drop(unsafe { Box::from_raw(shared) });
+    }
+}
+
+/// Cuerpo de cada worker: saca trabajos hasta que el pool se cierra.
+fn worker_loop(shared: *mut PoolShared) {
+    unsafe {
+        my_mutex_lock(&(*shared).mutex);
+        loop {
+            while (*shared).queue.is_empty() && !(*shared).shutdown {
+                my_cond_wait(&mut (*shared).work, &(*shared).mutex);
+            }
+            let Some(job) = (*shared).queue.pop_front() else {
+                break;
+            };
+            my_mutex_unlock(&(*shared).mutex);
+
+            // Un trabajo que entra en pánico no se lleva al worker ni deja
+            // `pending` sin descontar.
+            let _ = panic::catch_unwind(AssertUnwindSafe(job));
+
+            my_mutex_lock(&(*shared).mutex);
+            (*shared).pending -= 1;
+            if (*shared).pending == 0 {
+                my_cond_broadcast(&mut (*shared).idle);
+            }
+        }
+        my_mutex_unlock(&(*shared).mutex);
+    }
+}
@@ -0,0 +1,52 @@
+// main termina con my_thread_end mientras quedan workers vivos. El proceso
+// tiene que esperarlos y salir con el retval de main, así que el escenario
+// corre en un proceso hijo: este mismo ejecutable, filtrado al fixture.
+
+use mypthreads::*;
+use std::env;
+use std::os::raw::c_void;
+use std::process::Command;
+
+const FIXTURE_ENV: &str = "MYPTHREADS_MAIN_END_FIXTURE";
+
+#[test]
+fn main_end_fixture() {
+    if env::var_os(FIXTURE_ENV).is_none() {
+        return;
+    }
+
+    for id in 0..3 {
+        let joinable = my_thread_spawn(
+            move || {
+                for step in 0..5 {
+                    println!("worker {id} paso {step}");
+                    my_thread_yield();
+                }
+                println!("worker {id} terminó");
+            },
+            SchedPolicy::RoundRobin,
+        );
+        if id == 2 {
+            my_thread_detach(joinable);
+        }
+    }
+    println!("main termina");
+    my_thread_end(7 as *mut c_void);
+}
+
+#[test]
+fn main_end_waits_for_workers_and_exits_with_retval() {
+    let output = Command::new(env::current_exe().unwrap())
+        .args(["--exact", "main_end_fixture", "--nocapture", "--test-threads=1"])
+        .env(FIXTURE_ENV, "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(output.status.code(), Some(7), "salida del fixture:\n{stdout}");
+    let main_end = stdout.find("main termina").unwrap();
+    for id in 0..3 {
+        let done = stdout.find(&format!("worker {id} terminó")).unwrap();
+        assert!(done > main_end, "salida del fixture:\n{stdout}");
+    }
+}
@@ -0,0 +1,42 @@
+// ThreadPool: los workers se reparten la cola y el pool se puede reutilizar.
+
+use mypthreads::*;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+#[test]
+fn pool_runs_every_job_and_joins_its_workers() {
+    let live = my_sched_live_threads();
+    let pool = ThreadPool::new(3, SchedPolicy::RoundRobin);
+    assert_eq!(pool.size(), 3);
+    assert_eq!(my_sched_live_threads(), live + 3);
+
+    let done = Rc::new(RefCell::new(Vec::new()));
+    let (running, max_running) = (Rc::new(Cell::new(0)), Rc::new(Cell::new(0)));
+    for job in 0..20 {
+        let (done, running, max_running) = (Rc::clone(&done), Rc::clone(&running), Rc::clone(&max_running));
+        pool.submit(move || {
+            running.set(running.get() + 1);
+            max_running.set(max_running.get().max(running.get()));
+            // Ceder a mitad del trabajo deja que otro worker tome el siguiente.
+            my_thread_yield();
+            running.set(running.get() - 1);
+            done.borrow_mut().push(job);
+        });
+    }
+    pool.join_all();
+    let mut finished = done.borrow().clone();
+    finished.sort_unstable();
+    assert_eq!(finished, (0..20).collect::<Vec<_>>());
+    assert_eq!(max_running.get(), 3);
+
+    // Un trabajo que entra en pánico no frena a los demás.
+    pool.submit(|| panic!("trabajo fallido"));
+    let after = Rc::clone(&done);
+    pool.submit(move || after.borrow_mut().push(20));
+    pool.join_all();
+    assert_eq!(done.borrow().len(), 21);
+
+    drop(pool);
+    assert_eq!(my_sched_live_threads(), live);
+}
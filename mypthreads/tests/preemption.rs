@@ -0,0 +1,46 @@
+// Preempción con SIGALRM. Va en su propio binario de test: el timer es del
+// proceso y no debe interrumpir a los hilos de los demás tests.
+
+use mypthreads::*;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+#[test]
+fn sigalrm_preempts_a_thread_that_never_yields() {
+    assert_eq!(my_sched_set_preemption(1_000), 0);
+
+    let released = Rc::new(Cell::new(false));
+    let preempted = Rc::new(Cell::new(false));
+    let hog = {
+        let (released, preempted) = (Rc::clone(&released), Rc::clone(&preempted));
+        my_thread_spawn(
+            move || {
+                // Sin preempción el otro hilo nunca correría y esto daría
+                // vueltas hasta el límite.
+                let start = Instant::now();
+                preempted.set(my_thread_preemptible(|| {
+                    while !released.get() && start.elapsed() < Duration::from_secs(5) {
+                        std::hint::spin_loop();
+                    }
+                    released.get()
+                }));
+            },
+            SchedPolicy::RoundRobin,
+        )
+    };
+    let releaser = {
+        let released = Rc::clone(&released);
+        my_thread_spawn(move || released.set(true), SchedPolicy::RoundRobin)
+    };
+
+    my_thread_join(hog);
+    my_thread_join(releaser);
+    assert_eq!(my_sched_set_preemption(0), 0);
+    assert!(preempted.get(), "el hilo nunca fue preemptado");
+
+    // Apagada la preempción, el handler ya no queda instalado.
+    let mut current: libc::sigaction = unsafe { std::mem::zeroed() };
+    assert_eq!(unsafe { libc::sigaction(libc::SIGALRM, std::ptr::null(), &mut current) }, 0);
+    assert_eq!(current.sa_sigaction, libc::SIG_IGN);
+}
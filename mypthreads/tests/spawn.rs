@@ -0,0 +1,114 @@
+// Versión con closures del worker Lottery de threadcity/src/test_mypthreads.rs
+
+use mypthreads::*;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// Estado compartido entre los hilos.
+struct Shared {
+    mutex: MyMutex,
+    lottery_counter: [i64; 3],
+}
+
+#[test]
+fn lottery_workers_with_closures() {
+    let shared = Box::into_raw(Box::new(Shared {
+        mutex: MyMutex::new(),
+        lottery_counter: [0; 3],
+    }));
+    unsafe { my_mutex_init(&mut (*shared).mutex) };
+
+    let finished = Rc::new(Cell::new(0));
+    let mut tids = Vec::new();
+    for (idx, tickets) in [1, 3, 7].into_iter().enumerate() {
+        let finished = Rc::clone(&finished);
+        let tid = my_thread_spawn(
+            move || {
+                for _ in 0..400 {
+                    unsafe {
+                        my_mutex_lock(&(*shared).mutex);
+                        (*shared).lottery_counter[idx] += 1;
+                        my_mutex_unlock(&(*shared).mutex);
+                    }
+                    my_thread_yield();
+                }
+                finished.set(finished.get() + 1);
+            },
+            SchedPolicy::Lottery { tickets },
+        );
+        tids.push(tid);
+    }
+
+    for tid in tids {
+        assert!(my_thread_join(tid).is_null());
+    }
+
+    let shared = unsafe { Box::from_raw(shared) };
+    assert_eq!(shared.lottery_counter, [400; 3]);
+    assert_eq!(finished.get(), 3);
+    // Cada closure se liberó al terminar su hilo
+    assert_eq!(Rc::strong_count(&finished), 1);
+}
+
+#[test]
+fn closure_is_released_after_running() {
+    let token = Rc::new(());
+    let captured = Rc::clone(&token);
+    let tid = my_thread_spawn(move || drop(captured), SchedPolicy::RoundRobin);
+    assert_eq!(Rc::strong_count(&token), 2);
+    my_thread_join(tid);
+    assert_eq!(Rc::strong_count(&token), 1);
+}
+
+#[test]
+fn join_handles_return_typed_values() {
+    let name = my_thread_spawn_with_result(
+        || {
+            my_thread_yield();
+            format!("auto {}", 7)
+        },
+        SchedPolicy::RoundRobin,
+    );
+    let route = my_thread_spawn_with_result(
+        || (0..4).map(|i| (i, i * 2)).collect::<Vec<(usize, usize)>>(),
+        SchedPolicy::Lottery { tickets: 5 },
+    );
+
+    assert_eq!(route.join(), vec![(0, 0), (1, 2), (2, 4), (3, 6)]);
+    assert_eq!(name.join(), "auto 7");
+}
+
+#[test]
+fn dropped_join_handle_detaches() {
+    let live = my_sched_stats().live_threads;
+    let handle = my_thread_spawn_with_result(|| String::from("nadie lo espera"), SchedPolicy::RoundRobin);
+    assert_eq!(my_sched_stats().live_threads, live + 1);
+    drop(handle);
+    for _ in 0..5 {
+        my_thread_yield();
+    }
+    assert_eq!(my_sched_stats().live_threads, live);
+}
+
+#[test]
+fn join_resumes_the_thread_panic() {
+    let live = my_sched_stats().live_threads;
+    let cell = Rc::new(Cell::new(3));
+    let handle = my_thread_spawn_with_result(
+        move || -> u32 {
+            my_thread_yield();
+            panic!("choque en la intersección {}", cell.get());
+        },
+        SchedPolicy::RoundRobin,
+    );
+    let payload = std::panic::catch_unwind(move || handle.join()).unwrap_err();
+    assert_eq!(payload.downcast_ref::<String>().unwrap(), "choque en la intersección 3");
+
+    // Un hilo que hace join a otro que entró en pánico también entra en
+    // pánico, y el pánico llega hasta main.
+    let inner = my_thread_spawn(|| panic!("sin frenos"), SchedPolicy::RoundRobin);
+    let outer = my_thread_spawn(move || { my_thread_join(inner); }, SchedPolicy::RoundRobin);
+    let payload = std::panic::catch_unwind(|| my_thread_join(outer)).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"sin frenos"));
+    assert_eq!(my_sched_stats().live_threads, live);
+}
@@ -0,0 +1,65 @@
+// Uso de MyWaitGroup como en threadcity: un add por vehículo, done al final
+// de cada hilo y un único wait en main, aunque los hilos estén detached.
+
+use mypthreads::*;
+use std::os::raw::c_void;
+use std::ptr;
+
+struct VehicleArgs {
+    group: *const MyWaitGroup,
+    steps: usize,
+    finished: *mut Vec<usize>,
+    id: usize,
+}
+
+extern "C" fn vehicle_thread(arg: *mut c_void) -> *mut c_void {
+    let args = unsafe { Box::from_raw(arg as *mut VehicleArgs) };
+    for _ in 0..args.steps {
+        my_thread_yield();
+    }
+    unsafe {
+        (*args.finished).push(args.id);
+        assert_eq!((*args.group).done(), 0);
+    }
+    ptr::null_mut()
+}
+
+#[test]
+fn wait_group_waits_for_detached_vehicles() {
+    let group = MyWaitGroup::new();
+    let mut finished = Vec::new();
+
+    for (id, steps) in [5, 1, 3, 0, 8].into_iter().enumerate() {
+        group.add(1);
+        let args = Box::new(VehicleArgs { group: &group, steps, finished: &mut finished, id });
+        let policy = if id % 2 == 0 { SchedPolicy::RoundRobin } else { SchedPolicy::Lottery { tickets: 3 } };
+        let tid = my_thread_create(vehicle_thread, Box::into_raw(args) as *mut c_void, policy);
+        if id != 0 {
+            assert_eq!(my_thread_detach(tid), 0);
+        }
+    }
+
+    group.wait();
+    assert_eq!(group.pending(), 0);
+    finished.sort_unstable();
+    assert_eq!(finished, [0, 1, 2, 3, 4]);
+    assert_eq!(group.done(), libc::EINVAL);
+}
+
+#[test]
+fn wait_group_releases_every_waiter() {
+    let group: &'static MyWaitGroup = Box::leak(Box::new(MyWaitGroup::new()));
+    group.add(2);
+
+    let waiters: Vec<_> = (0..3)
+        .map(|_| my_thread_spawn_with_result(move || group.wait(), SchedPolicy::RoundRobin))
+        .collect();
+    my_thread_yield();
+    assert_eq!(group.done(), 0);
+    assert_eq!(group.done(), 0);
+    for handle in waiters {
+        handle.join();
+    }
+    // Sin trabajos pendientes, wait no bloquea.
+    group.wait();
+}
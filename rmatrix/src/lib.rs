@@ -8,8 +8,15 @@
 //! mat.set(0, 1, 42);
 //! assert_eq!(*mat.get(0, 1), 42);
 //! ```
+//!
+//! # Features
+//! * `serde` - Implementa `Serialize`/`Deserialize` para `Matrix<T>` con la
+//!   forma `{"rows": 2, "cols": 3, "data": [...]}`
+//! * `csv` - Agrega `Matrix::to_csv` y `Matrix::from_csv`
 
-use num_traits::{Zero, One};
+use num_traits::{One, Signed, Zero};
+use std::fmt;
+use std::ops::{Add, Index, Mul, Neg};
 
 /// Representa una matriz de elementos genéricos
 #[derive(Debug, Clone, PartialEq)]
@@ -19,6 +26,75 @@ pub struct Matrix<T> {
     cols: usize,
 }
 
+/// Eje sobre el que opera una función
+///
+/// `Axis::Row` recorre cada fila (de izquierda a derecha, a través de las
+/// columnas); `Axis::Col` recorre cada columna (de arriba hacia abajo).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Axis {
+    Row,
+    Col,
+}
+
+/// Errores devueltos por las operaciones de matrices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatrixError {
+    /// El índice (row, col) está fuera de una matriz de rows x cols
+    IndexOutOfBounds {
+        row: usize,
+        col: usize,
+        rows: usize,
+        cols: usize,
+    },
+    /// Las dimensiones de los operandos no son compatibles
+    DimensionMismatch {
+        expected: (usize, usize),
+        got: (usize, usize),
+    },
+    /// La cantidad de elementos no es la esperada
+    SizeMismatch { expected: usize, got: usize },
+    /// Un valor de texto no se pudo interpretar como número
+    Parse { row: usize, col: usize, value: String },
+    /// Error de entrada/salida al leer o escribir la matriz
+    Io(String),
+    /// La matriz no es invertible
+    SingularMatrix,
+    /// La operación requiere una matriz cuadrada
+    NotSquare,
+}
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MatrixError::IndexOutOfBounds { row, col, rows, cols } => write!(
+                f,
+                "índice ({}, {}) fuera de los límites de una matriz {}x{}",
+                row, col, rows, cols
+            ),
+            MatrixError::DimensionMismatch { expected, got } => write!(
+                f,
+                "dimensiones incompatibles: se esperaba {}x{}, se obtuvo {}x{}",
+                expected.0, expected.1, got.0, got.1
+            ),
+            MatrixError::SizeMismatch { expected, got } => write!(
+                f,
+                "cantidad de elementos incorrecta: se esperaba {}, se obtuvo {}",
+                expected, got
+            ),
+            MatrixError::Parse { row, col, value } => write!(
+                f,
+                "valor inválido {:?} en la fila {}, columna {}",
+                value, row, col
+            ),
+            MatrixError::Io(msg) => write!(f, "error de entrada/salida: {}", msg),
+            MatrixError::SingularMatrix => write!(f, "la matriz es singular"),
+            MatrixError::NotSquare => write!(f, "la matriz no es cuadrada"),
+        }
+    }
+}
+
+impl std::error::Error for MatrixError {}
+
 impl<T> Matrix<T> {
     /// Crea una nueva matriz con las dimensiones especificadas
     ///
@@ -43,6 +119,19 @@ impl<T> Matrix<T> {
         }
     }
 
+    /// Posición en `data` del elemento (row, col), validando los límites
+    fn index_of(&self, row: usize, col: usize) -> Result<usize, MatrixError> {
+        if row >= self.rows || col >= self.cols {
+            return Err(MatrixError::IndexOutOfBounds {
+                row,
+                col,
+                rows: self.rows,
+                cols: self.cols,
+            });
+        }
+        Ok(row * self.cols + col)
+    }
+
     /// Obtiene una referencia al elemento en la posición (row, col)
     ///
     /// # Argumentos
@@ -50,9 +139,28 @@ impl<T> Matrix<T> {
     /// * `col` - Índice de la columna (0-based)
     ///
     /// # Panics
-    /// Panics si los índices están fuera de los límites
+    /// Panics si los índices están fuera de los límites; ver [`Matrix::try_get`]
     pub fn get(&self, row: usize, col: usize) -> &T {
-        &self.data[row * self.cols + col]
+        self.try_get(row, col).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Versión de [`Matrix::get`] que no entra en pánico
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::IndexOutOfBounds` si los índices están fuera de
+    /// los límites
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+    /// assert_eq!(mat.try_get(1, 0), Ok(&3));
+    /// assert!(mat.try_get(0, 2).is_err());
+    /// ```
+    pub fn try_get(&self, row: usize, col: usize) -> Result<&T, MatrixError> {
+        let idx = self.index_of(row, col)?;
+        Ok(&self.data[idx])
     }
 
     /// Obtiene una referencia mutable al elemento en la posición (row, col)
@@ -62,9 +170,19 @@ impl<T> Matrix<T> {
     /// * `col` - Índice de la columna (0-based)
     ///
     /// # Panics
-    /// Panics si los índices están fuera de los límites
+    /// Panics si los índices están fuera de los límites; ver [`Matrix::try_get_mut`]
     pub fn get_mut(&mut self, row: usize, col: usize) -> &mut T {
-        &mut self.data[row * self.cols + col]
+        self.try_get_mut(row, col).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Versión de [`Matrix::get_mut`] que no entra en pánico
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::IndexOutOfBounds` si los índices están fuera de
+    /// los límites
+    pub fn try_get_mut(&mut self, row: usize, col: usize) -> Result<&mut T, MatrixError> {
+        let idx = self.index_of(row, col)?;
+        Ok(&mut self.data[idx])
     }
 
     /// Establece el valor en la posición (row, col)
@@ -75,9 +193,19 @@ impl<T> Matrix<T> {
     /// * `value` - Valor a establecer
     ///
     /// # Panics
-    /// Panics si los índices están fuera de los límites
+    /// Panics si los índices están fuera de los límites; ver [`Matrix::try_set`]
     pub fn set(&mut self, row: usize, col: usize, value: T) {
-        self.data[row * self.cols + col] = value;
+        self.try_set(row, col, value).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Versión de [`Matrix::set`] que no entra en pánico
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::IndexOutOfBounds` si los índices están fuera de
+    /// los límites; en ese caso la matriz no se modifica
+    pub fn try_set(&mut self, row: usize, col: usize, value: T) -> Result<(), MatrixError> {
+        *self.try_get_mut(row, col)? = value;
+        Ok(())
     }
 
     /// Devuelve el número de filas de la matriz
@@ -103,12 +231,64 @@ impl<T> Matrix<T> {
     /// * `cols` - Número de columnas
     ///
     /// # Panics
-    /// Panics si la longitud del vector no coincide con rows * cols
+    /// Panics si la longitud del vector no coincide con rows * cols; ver
+    /// [`Matrix::try_from_vec`]
     pub fn from_vec(data: Vec<T>, rows: usize, cols: usize) -> Self {
+        Self::try_from_vec(data, rows, cols).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Versión de [`Matrix::from_vec`] que no entra en pánico
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::SizeMismatch` si la longitud del vector no
+    /// coincide con rows * cols
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::{Matrix, MatrixError};
+    ///
+    /// assert!(Matrix::try_from_vec(vec![1, 2, 3, 4], 2, 2).is_ok());
+    /// assert_eq!(
+    ///     Matrix::try_from_vec(vec![1, 2, 3], 2, 2),
+    ///     Err(MatrixError::SizeMismatch { expected: 4, got: 3 })
+    /// );
+    /// ```
+    pub fn try_from_vec(data: Vec<T>, rows: usize, cols: usize) -> Result<Self, MatrixError> {
         if data.len() != rows * cols {
-            panic!("La longitud del vector debe ser rows * cols");
+            return Err(MatrixError::SizeMismatch {
+                expected: rows * cols,
+                got: data.len(),
+            });
+        }
+        Ok(Matrix { data, rows, cols })
+    }
+
+    /// Crea una matriz a partir de un vector de filas, sin clonar los elementos
+    ///
+    /// Un vector vacío produce una matriz 0x0. Ver también [`Matrix::from_rows`].
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::SizeMismatch` si alguna fila no tiene la misma
+    /// longitud que la primera
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::{Matrix, MatrixError};
+    ///
+    /// let mat = Matrix::from_nested_vec(vec![vec![1, 2], vec![3, 4]]).unwrap();
+    /// assert_eq!(mat.to_nested_vec(), vec![vec![1, 2], vec![3, 4]]);
+    /// assert_eq!(
+    ///     Matrix::from_nested_vec(vec![vec![1, 2], vec![3]]),
+    ///     Err(MatrixError::SizeMismatch { expected: 2, got: 1 })
+    /// );
+    /// ```
+    pub fn from_nested_vec(data: Vec<Vec<T>>) -> Result<Matrix<T>, MatrixError> {
+        let rows = data.len();
+        let cols = data.first().map_or(0, |r| r.len());
+        if let Some(bad) = data.iter().find(|r| r.len() != cols) {
+            return Err(MatrixError::SizeMismatch { expected: cols, got: bad.len() });
         }
-        Matrix { data, rows, cols }
+        Ok(Matrix { data: data.into_iter().flatten().collect(), rows, cols })
     }
 
     /// Devuelve una referencia al vector de datos subyacente
@@ -120,6 +300,275 @@ impl<T> Matrix<T> {
     pub fn as_mut_slice(&mut self) -> &mut [T] {
         &mut self.data
     }
+
+    /// Devuelve la diagonal principal de la matriz
+    ///
+    /// Funciona también para matrices no cuadradas: la longitud del
+    /// resultado es `min(rows, cols)`.
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+    /// assert_eq!(mat.diagonal(), vec![1, 5]);
+    /// ```
+    pub fn diagonal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        (0..self.rows.min(self.cols))
+            .map(|i| self.get(i, i).clone())
+            .collect()
+    }
+
+    /// Devuelve la antidiagonal de la matriz (de la esquina superior derecha
+    /// a la inferior izquierda)
+    ///
+    /// La longitud del resultado es `min(rows, cols)`.
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+    /// assert_eq!(mat.anti_diagonal(), vec![3, 5]);
+    /// ```
+    pub fn anti_diagonal(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        (0..self.rows.min(self.cols))
+            .map(|i| self.get(i, self.cols - 1 - i).clone())
+            .collect()
+    }
+
+    /// Asigna `value` a cada elemento de la diagonal principal
+    ///
+    /// Igual que [`Matrix::diagonal`], recorre `min(rows, cols)` posiciones.
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mut mat = Matrix::<i32>::zeros(2, 3);
+    /// mat.fill_diagonal(7);
+    /// assert_eq!(mat.as_slice(), &[7, 0, 0, 0, 7, 0]);
+    /// ```
+    pub fn fill_diagonal(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        for i in 0..self.rows.min(self.cols) {
+            self.set(i, i, value.clone());
+        }
+    }
+
+    /// Asigna `value` a cada elemento de la antidiagonal
+    ///
+    /// Recorre las mismas posiciones que [`Matrix::anti_diagonal`].
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mut mat = Matrix::<i32>::zeros(2, 3);
+    /// mat.fill_antidiagonal(7);
+    /// assert_eq!(mat.as_slice(), &[0, 0, 7, 0, 7, 0]);
+    /// ```
+    pub fn fill_antidiagonal(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        for i in 0..self.rows.min(self.cols) {
+            let col = self.cols - 1 - i;
+            self.set(i, col, value.clone());
+        }
+    }
+
+    /// Aplica `f` a cada elemento y devuelve una nueva matriz de la misma forma
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+    /// let doubled = mat.map(|x| x * 2);
+    /// assert_eq!(doubled.as_slice(), &[2, 4, 6, 8]);
+    /// ```
+    pub fn map<U, F: Fn(&T) -> U>(&self, f: F) -> Matrix<U> {
+        Matrix {
+            data: self.data.iter().map(f).collect(),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+
+    /// Versión consumidora de [`Matrix::map`]: transforma cada elemento sin clonarlo
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2], 1, 2);
+    /// let strings = mat.into_map(|x| x.to_string());
+    /// assert_eq!(strings.as_slice(), &["1".to_string(), "2".to_string()]);
+    /// ```
+    pub fn into_map<U, F: Fn(T) -> U>(self, f: F) -> Matrix<U> {
+        Matrix {
+            data: self.data.into_iter().map(f).collect(),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+
+    /// Modifica cada elemento en el lugar aplicando `f`
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mut mat = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+    /// mat.apply(|x| *x += 10);
+    /// assert_eq!(mat.as_slice(), &[11, 12, 13, 14]);
+    /// ```
+    pub fn apply<F: FnMut(&mut T)>(&mut self, f: F) {
+        self.data.iter_mut().for_each(f);
+    }
+
+    /// Combina dos matrices elemento a elemento con la función `f`
+    ///
+    /// # Argumentos
+    /// * `other` - Matriz con las mismas dimensiones que `self`
+    /// * `f` - Función que recibe el par de elementos en la misma posición
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::DimensionMismatch` si las dimensiones difieren
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let a = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+    /// let b = Matrix::from_vec(vec![10, 20, 30, 40], 2, 2);
+    /// let sum = a.zip_with(&b, |x, y| x + y).unwrap();
+    /// assert_eq!(sum.as_slice(), &[11, 22, 33, 44]);
+    /// ```
+    pub fn zip_with<U, V, F: Fn(&T, &U) -> V>(
+        &self,
+        other: &Matrix<U>,
+        f: F,
+    ) -> Result<Matrix<V>, MatrixError> {
+        if self.dimensions() != other.dimensions() {
+            return Err(MatrixError::DimensionMismatch {
+                expected: self.dimensions(),
+                got: other.dimensions(),
+            });
+        }
+        Ok(Matrix {
+            data: self
+                .data
+                .iter()
+                .zip(other.data.iter())
+                .map(|(a, b)| f(a, b))
+                .collect(),
+            rows: self.rows,
+            cols: self.cols,
+        })
+    }
+
+    /// Producto de Hadamard: multiplicación elemento a elemento
+    ///
+    /// No confundir con la multiplicación matricial: aquí
+    /// `result[i][j] = self[i][j] * other[i][j]`.
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::DimensionMismatch` si las dimensiones difieren
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let a = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+    /// let b = Matrix::from_vec(vec![5, 6, 7, 8], 2, 2);
+    /// assert_eq!(a.hadamard(&b).unwrap().as_slice(), &[5, 12, 21, 32]);
+    /// ```
+    pub fn hadamard(&self, other: &Matrix<T>) -> Result<Matrix<T>, MatrixError>
+    where
+        T: Mul<Output = T> + Clone,
+    {
+        self.zip_with(other, |a, b| a.clone() * b.clone())
+    }
+
+    /// Crea una matriz cuadrada con `data` en la diagonal principal y cero
+    /// en el resto de posiciones
+    ///
+    /// # Argumentos
+    /// * `data` - Elementos de la diagonal; su longitud define el tamaño
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_diagonal(vec![1, 2, 3]);
+    /// assert_eq!(*mat.get(1, 1), 2);
+    /// assert_eq!(*mat.get(0, 1), 0);
+    /// ```
+    pub fn from_diagonal(data: Vec<T>) -> Self
+    where
+        T: Zero + Clone,
+    {
+        let size = data.len();
+        let mut mat = Matrix {
+            data: vec![T::zero(); size * size],
+            rows: size,
+            cols: size,
+        };
+        for (i, value) in data.into_iter().enumerate() {
+            mat.set(i, i, value);
+        }
+        mat
+    }
+
+    /// Crea una matriz diagonal por bloques: cada bloque se coloca a
+    /// continuación del anterior sobre la diagonal y el resto queda en cero
+    ///
+    /// Las dimensiones del resultado son la suma de las filas y la suma de las
+    /// columnas de todos los bloques, que no necesitan ser cuadrados.
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let a = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+    /// let b = Matrix::from_vec(vec![5], 1, 1);
+    /// let mat = Matrix::block_diagonal(&[&a, &b]);
+    /// assert_eq!(mat.dimensions(), (3, 3));
+    /// assert_eq!(mat.as_slice(), &[1, 2, 0, 3, 4, 0, 0, 0, 5]);
+    /// ```
+    pub fn block_diagonal(blocks: &[&Matrix<T>]) -> Self
+    where
+        T: Zero + Clone,
+    {
+        let rows = blocks.iter().map(|b| b.rows).sum();
+        let cols = blocks.iter().map(|b| b.cols).sum();
+        let mut mat = Matrix {
+            data: vec![T::zero(); rows * cols],
+            rows,
+            cols,
+        };
+        let (mut r0, mut c0) = (0, 0);
+        for block in blocks {
+            for r in 0..block.rows {
+                let src = &block.data[r * block.cols..(r + 1) * block.cols];
+                let start = (r0 + r) * cols + c0;
+                mat.data[start..start + block.cols].clone_from_slice(src);
+            }
+            r0 += block.rows;
+            c0 += block.cols;
+        }
+        mat
+    }
 }
 
 // Implementación para tipos que pueden ser inicializados a cero
@@ -148,55 +597,1372 @@ where
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_matrix_creation() {
-        let mat = Matrix::<i32>::new(2, 3);
-        assert_eq!(mat.rows(), 2);
-        assert_eq!(mat.cols(), 3);
-        assert_eq!(mat.dimensions(), (2, 3));
+// Extracción de partes triangulares
+impl<T> Matrix<T>
+where
+    T: Zero + Clone,
+{
+    /// Copia la matriz dejando en cero los elementos que no cumplen `keep(row, col)`
+    fn masked<F: Fn(usize, usize) -> bool>(&self, keep: F) -> Matrix<T> {
+        let data = self
+            .data
+            .iter()
+            .enumerate()
+            .map(|(idx, x)| {
+                if keep(idx / self.cols, idx % self.cols) {
+                    x.clone()
+                } else {
+                    T::zero()
+                }
+            })
+            .collect();
+        Matrix { data, rows: self.rows, cols: self.cols }
     }
 
-    #[test]
-    fn test_get_set() {
-        let mut mat = Matrix::<i32>::new(2, 2);
-        mat.set(0, 0, 1);
-        mat.set(0, 1, 2);
-        mat.set(1, 0, 3);
-        mat.set(1, 1, 4);
+    /// Devuelve la parte triangular superior (incluye la diagonal); los
+    /// elementos debajo de la diagonal quedan en cero
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3);
+    /// assert_eq!(mat.upper_triangular().as_slice(), &[1, 2, 3, 0, 5, 6, 0, 0, 9]);
+    /// ```
+    pub fn upper_triangular(&self) -> Matrix<T> {
+        self.masked(|row, col| col >= row)
+    }
 
-        assert_eq!(*mat.get(0, 0), 1);
-        assert_eq!(*mat.get(0, 1), 2);
-        assert_eq!(*mat.get(1, 0), 3);
-        assert_eq!(*mat.get(1, 1), 4);
+    /// Devuelve la parte triangular inferior (incluye la diagonal); los
+    /// elementos encima de la diagonal quedan en cero
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3);
+    /// assert_eq!(mat.lower_triangular().as_slice(), &[1, 0, 0, 4, 5, 0, 7, 8, 9]);
+    /// ```
+    pub fn lower_triangular(&self) -> Matrix<T> {
+        self.masked(|row, col| col <= row)
     }
 
-    #[test]
-    fn test_get_mut() {
-        let mut mat = Matrix::<i32>::new(2, 2);
-        *mat.get_mut(0, 0) = 10;
-        *mat.get_mut(1, 1) = 20;
+    /// Igual que [`Matrix::upper_triangular`] pero también deja en cero la diagonal
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3);
+    /// assert_eq!(mat.strictly_upper_triangular().as_slice(), &[0, 2, 3, 0, 0, 6, 0, 0, 0]);
+    /// ```
+    pub fn strictly_upper_triangular(&self) -> Matrix<T> {
+        self.masked(|row, col| col > row)
+    }
+}
 
-        assert_eq!(*mat.get(0, 0), 10);
-        assert_eq!(*mat.get(1, 1), 20);
+// Predicados sobre la estructura de la matriz
+impl<T> Matrix<T> {
+    /// Indica si todos los elementos son cero
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// assert!(Matrix::<i32>::zeros(2, 3).is_zero());
+    /// assert!(!Matrix::from_vec(vec![0, 1], 1, 2).is_zero());
+    /// ```
+    pub fn is_zero(&self) -> bool
+    where
+        T: Zero + PartialEq,
+    {
+        let zero = T::zero();
+        self.data.iter().all(|x| *x == zero)
     }
 
-    #[test]
-    fn test_from_vec() {
-        let data = vec![1, 2, 3, 4, 5, 6];
-        let mat = Matrix::from_vec(data, 2, 3);
+    /// Indica si la matriz es la identidad: cuadrada, con uno en la diagonal
+    /// y cero en el resto
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// assert!(Matrix::<i32>::identity(3).is_identity());
+    /// assert!(!Matrix::from_vec(vec![1, 0], 1, 2).is_identity());
+    /// ```
+    pub fn is_identity(&self) -> bool
+    where
+        T: Zero + One + PartialEq,
+    {
+        if self.rows != self.cols {
+            return false;
+        }
+        let (zero, one) = (T::zero(), T::one());
+        self.data.iter().enumerate().all(|(idx, x)| {
+            let expected = if idx / self.cols == idx % self.cols { &one } else { &zero };
+            x == expected
+        })
+    }
+}
 
-        assert_eq!(mat.rows(), 2);
-        assert_eq!(mat.cols(), 3);
-        assert_eq!(*mat.get(0, 0), 1);
-        assert_eq!(*mat.get(1, 2), 6);
+impl<T: fmt::Display> Matrix<T> {
+    /// Convierte cada elemento a texto y calcula el ancho de cada columna
+    fn formatted_cells(&self) -> (Vec<String>, Vec<usize>) {
+        let cells: Vec<String> = self.data.iter().map(|x| x.to_string()).collect();
+        let mut widths = vec![0; self.cols];
+        for (i, cell) in cells.iter().enumerate() {
+            let col = i % self.cols;
+            widths[col] = widths[col].max(cell.chars().count());
+        }
+        (cells, widths)
     }
 
-    #[test]
-    fn test_as_slice() {
+    /// Imprime la matriz con índices de fila y columna
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 20, 300, 4], 2, 2);
+    /// mat.pretty_print();
+    /// // Salida:
+    /// //      0  1
+    /// // 0:   1 20
+    /// // 1: 300  4
+    /// ```
+    pub fn pretty_print(&self) {
+        let (cells, mut widths) = self.formatted_cells();
+        for (col, width) in widths.iter_mut().enumerate() {
+            *width = (*width).max(col.to_string().len());
+        }
+        let label_width = self.rows.saturating_sub(1).to_string().len() + 1;
+
+        print!("{:label_width$}", "");
+        for (col, width) in widths.iter().enumerate() {
+            print!(" {:>width$}", col);
+        }
+        println!();
+
+        for row in 0..self.rows {
+            print!("{:>label_width$}", format!("{}:", row));
+            for (col, width) in widths.iter().enumerate() {
+                print!(" {:>width$}", cells[row * self.cols + col]);
+            }
+            println!();
+        }
+    }
+}
+
+/// Muestra cada fila en su propia línea, con las columnas alineadas a la
+/// derecha según el elemento más ancho de cada una
+impl<T: fmt::Display> fmt::Display for Matrix<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (cells, widths) = self.formatted_cells();
+        for row in 0..self.rows {
+            if row > 0 {
+                writeln!(f)?;
+            }
+            for (col, width) in widths.iter().enumerate() {
+                if col > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{:>width$}", cells[row * self.cols + col])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "csv")]
+mod csv_impl {
+    use super::{Matrix, MatrixError};
+    use std::fmt::Display;
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    impl<T: Display> Matrix<T> {
+        /// Escribe la matriz en formato CSV: una fila por línea, separada por comas
+        ///
+        /// # Ejemplos
+        /// ```
+        /// use rmatrix::Matrix;
+        ///
+        /// let mat = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+        /// let mut out = Vec::new();
+        /// mat.to_csv(&mut out).unwrap();
+        /// assert_eq!(String::from_utf8(out).unwrap(), "1,2\n3,4\n");
+        /// ```
+        pub fn to_csv(&self, mut writer: impl Write) -> Result<(), std::io::Error> {
+            for row in 0..self.rows {
+                let line: Vec<String> = (0..self.cols)
+                    .map(|col| self.get(row, col).to_string())
+                    .collect();
+                writeln!(writer, "{}", line.join(","))?;
+            }
+            Ok(())
+        }
+    }
+
+    impl Matrix<f64> {
+        /// Lee una matriz de `f64` en formato CSV
+        ///
+        /// El número de columnas se toma de la primera fila; las líneas vacías
+        /// se ignoran.
+        ///
+        /// # Errores
+        /// * `MatrixError::SizeMismatch` si una fila tiene otra cantidad de columnas
+        /// * `MatrixError::Parse` si un valor no es un número
+        /// * `MatrixError::Io` si falla la lectura
+        ///
+        /// # Ejemplos
+        /// ```
+        /// use rmatrix::Matrix;
+        ///
+        /// let mat = Matrix::from_csv("1,2\n3.5,4\n".as_bytes()).unwrap();
+        /// assert_eq!(mat.dimensions(), (2, 2));
+        /// assert_eq!(*mat.get(1, 0), 3.5);
+        /// ```
+        pub fn from_csv(reader: impl Read) -> Result<Matrix<f64>, MatrixError> {
+            let mut data = Vec::new();
+            let mut rows = 0;
+            let mut cols = 0;
+
+            for line in BufReader::new(reader).lines() {
+                let line = line.map_err(|e| MatrixError::Io(e.to_string()))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let fields: Vec<&str> = line.split(',').collect();
+                if rows == 0 {
+                    cols = fields.len();
+                } else if fields.len() != cols {
+                    return Err(MatrixError::SizeMismatch {
+                        expected: cols,
+                        got: fields.len(),
+                    });
+                }
+
+                for (col, field) in fields.iter().enumerate() {
+                    let value = field.trim().parse::<f64>().map_err(|_| MatrixError::Parse {
+                        row: rows,
+                        col,
+                        value: field.to_string(),
+                    })?;
+                    data.push(value);
+                }
+                rows += 1;
+            }
+
+            Ok(Matrix { data, rows, cols })
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::Matrix;
+    use serde::de::{self, Deserializer};
+    use serde::ser::{SerializeStruct, Serializer};
+    use serde::{Deserialize, Serialize};
+
+    impl<T: Serialize> Serialize for Matrix<T> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut state = serializer.serialize_struct("Matrix", 3)?;
+            state.serialize_field("rows", &self.rows)?;
+            state.serialize_field("cols", &self.cols)?;
+            state.serialize_field("data", &self.data)?;
+            state.end()
+        }
+    }
+
+    /// Forma serializada de la matriz, antes de validar sus dimensiones
+    #[derive(Deserialize)]
+    #[serde(rename = "Matrix")]
+    struct RawMatrix<T> {
+        rows: usize,
+        cols: usize,
+        data: Vec<T>,
+    }
+
+    impl<'de, T: Deserialize<'de>> Deserialize<'de> for Matrix<T> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let raw = RawMatrix::<T>::deserialize(deserializer)?;
+            let Some(len) = raw.rows.checked_mul(raw.cols) else {
+                return Err(de::Error::custom(format!(
+                    "una matriz {}x{} no entra en memoria",
+                    raw.rows, raw.cols
+                )));
+            };
+            if raw.data.len() != len {
+                return Err(de::Error::custom(format!(
+                    "se esperaban {} elementos para una matriz {}x{}, se obtuvieron {}",
+                    len,
+                    raw.rows,
+                    raw.cols,
+                    raw.data.len()
+                )));
+            }
+            Ok(Matrix {
+                data: raw.data,
+                rows: raw.rows,
+                cols: raw.cols,
+            })
+        }
+    }
+}
+
+// Conversión desde y hacia filas o columnas
+impl<T: Clone> Matrix<T> {
+    /// Crea una matriz a partir de sus filas
+    ///
+    /// Una lista vacía produce una matriz 0x0.
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::SizeMismatch` si alguna fila no tiene la misma
+    /// longitud que la primera
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_rows(&[&[1, 2, 3], &[4, 5, 6]]).unwrap();
+    /// assert_eq!(mat.dimensions(), (2, 3));
+    /// assert_eq!(mat.to_rows(), vec![vec![1, 2, 3], vec![4, 5, 6]]);
+    /// ```
+    pub fn from_rows(rows: &[&[T]]) -> Result<Matrix<T>, MatrixError> {
+        let cols = rows.first().map_or(0, |r| r.len());
+        let mut data = Vec::with_capacity(rows.len() * cols);
+        for row in rows {
+            if row.len() != cols {
+                return Err(MatrixError::SizeMismatch { expected: cols, got: row.len() });
+            }
+            data.extend_from_slice(row);
+        }
+        Ok(Matrix { data, rows: rows.len(), cols })
+    }
+
+    /// Crea una matriz a partir de sus columnas
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::SizeMismatch` si alguna columna no tiene la
+    /// misma longitud que la primera
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_cols(&[&[1, 4], &[2, 5], &[3, 6]]).unwrap();
+    /// assert_eq!(mat.as_slice(), &[1, 2, 3, 4, 5, 6]);
+    /// ```
+    pub fn from_cols(cols: &[&[T]]) -> Result<Matrix<T>, MatrixError> {
+        let rows = cols.first().map_or(0, |c| c.len());
+        if let Some(bad) = cols.iter().find(|c| c.len() != rows) {
+            return Err(MatrixError::SizeMismatch { expected: rows, got: bad.len() });
+        }
+        let mut data = Vec::with_capacity(rows * cols.len());
+        for row in 0..rows {
+            data.extend(cols.iter().map(|c| c[row].clone()));
+        }
+        Ok(Matrix { data, rows, cols: cols.len() })
+    }
+
+    /// Devuelve una copia de cada fila; inversa de [`Matrix::from_nested_vec`]
+    pub fn to_nested_vec(&self) -> Vec<Vec<T>> {
+        self.to_rows()
+    }
+
+    /// Devuelve una copia de cada fila
+    pub fn to_rows(&self) -> Vec<Vec<T>> {
+        if self.cols == 0 {
+            return vec![Vec::new(); self.rows];
+        }
+        self.data.chunks(self.cols).map(|r| r.to_vec()).collect()
+    }
+
+    /// Devuelve una copia de cada columna
+    pub fn to_cols(&self) -> Vec<Vec<T>> {
+        (0..self.cols)
+            .map(|col| (0..self.rows).map(|row| self.get(row, col).clone()).collect())
+            .collect()
+    }
+
+    /// Devuelve una copia de la fila `r`
+    ///
+    /// # Panics
+    /// Si `r` está fuera de rango
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+    /// assert_eq!(mat.clone_row(1), vec![4, 5, 6]);
+    /// ```
+    pub fn clone_row(&self, r: usize) -> Vec<T> {
+        self.check_row(r);
+        self.data[r * self.cols..(r + 1) * self.cols].to_vec()
+    }
+
+    /// Devuelve una copia de la columna `c`
+    ///
+    /// # Panics
+    /// Si `c` está fuera de rango
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+    /// assert_eq!(mat.clone_col(2), vec![3, 6]);
+    /// ```
+    pub fn clone_col(&self, c: usize) -> Vec<T> {
+        self.check_col(c);
+        self.data[c..].iter().step_by(self.cols).cloned().collect()
+    }
+
+    /// Reemplaza la fila `r` por `data`
+    ///
+    /// # Panics
+    /// Si `r` está fuera de rango o `data.len()` no coincide con la cantidad
+    /// de columnas
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mut mat = Matrix::<i32>::zeros(2, 2);
+    /// mat.set_row(0, &[7, 8]);
+    /// assert_eq!(mat.as_slice(), &[7, 8, 0, 0]);
+    /// ```
+    pub fn set_row(&mut self, r: usize, data: &[T]) {
+        self.check_row(r);
+        if data.len() != self.cols {
+            panic!("{}", MatrixError::SizeMismatch { expected: self.cols, got: data.len() });
+        }
+        self.data[r * self.cols..(r + 1) * self.cols].clone_from_slice(data);
+    }
+
+    /// Reemplaza la columna `c` por `data`
+    ///
+    /// # Panics
+    /// Si `c` está fuera de rango o `data.len()` no coincide con la cantidad
+    /// de filas
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mut mat = Matrix::<i32>::zeros(2, 2);
+    /// mat.set_col(1, &[7, 8]);
+    /// assert_eq!(mat.as_slice(), &[0, 7, 0, 8]);
+    /// ```
+    pub fn set_col(&mut self, c: usize, data: &[T]) {
+        self.check_col(c);
+        if data.len() != self.rows {
+            panic!("{}", MatrixError::SizeMismatch { expected: self.rows, got: data.len() });
+        }
+        let cols = self.cols;
+        for (slot, value) in self.data[c..].iter_mut().step_by(cols).zip(data) {
+            *slot = value.clone();
+        }
+    }
+
+    fn check_row(&self, r: usize) {
+        assert!(r < self.rows, "fila {r} fuera de rango (la matriz tiene {} filas)", self.rows);
+    }
+
+    fn check_col(&self, c: usize) {
+        assert!(c < self.cols, "columna {c} fuera de rango (la matriz tiene {} columnas)", self.cols);
+    }
+}
+
+// Reflexiones y rotaciones
+impl<T> Matrix<T> {
+    /// Devuelve la matriz reflejada horizontalmente (invierte el orden de las
+    /// columnas de cada fila)
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+    /// assert_eq!(mat.flip_horizontal().as_slice(), &[3, 2, 1, 6, 5, 4]);
+    /// ```
+    pub fn flip_horizontal(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        let mut flipped = self.clone();
+        flipped.flip_horizontal_inplace();
+        flipped
+    }
+
+    /// Devuelve la matriz reflejada verticalmente (invierte el orden de las filas)
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+    /// assert_eq!(mat.flip_vertical().as_slice(), &[4, 5, 6, 1, 2, 3]);
+    /// ```
+    pub fn flip_vertical(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        let mut flipped = self.clone();
+        flipped.flip_vertical_inplace();
+        flipped
+    }
+
+    /// Rota la matriz `times` veces 90 grados en sentido horario
+    ///
+    /// `times` se reduce módulo 4, así que `-1` equivale a una rotación
+    /// antihoraria. Con una cantidad impar de rotaciones el resultado tiene
+    /// dimensiones `(cols, rows)`.
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// // 1 2 3      4 1
+    /// // 4 5 6  ->  5 2
+    /// //            6 3
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+    /// let rotated = mat.rotate_90(1);
+    /// assert_eq!(rotated.dimensions(), (3, 2));
+    /// assert_eq!(rotated.as_slice(), &[4, 1, 5, 2, 6, 3]);
+    /// ```
+    pub fn rotate_90(&self, times: i32) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        let (rows, cols) = (self.rows, self.cols);
+        match times.rem_euclid(4) {
+            0 => self.clone(),
+            2 => {
+                let mut data = self.data.clone();
+                data.reverse();
+                Matrix { data, rows, cols }
+            }
+            turns => {
+                // result[j][rows-1-i] = self[i][j] (horario); antihorario al revés
+                let data = (0..cols)
+                    .flat_map(|j| {
+                        (0..rows).map(move |k| {
+                            if turns == 1 {
+                                (rows - 1 - k, j)
+                            } else {
+                                (k, cols - 1 - j)
+                            }
+                        })
+                    })
+                    .map(|(i, col)| self.get(i, col).clone())
+                    .collect();
+                Matrix { data, rows: cols, cols: rows }
+            }
+        }
+    }
+
+    /// Versión de [`Matrix::flip_horizontal`] que modifica la matriz sin reservar memoria
+    pub fn flip_horizontal_inplace(&mut self) {
+        if self.cols == 0 {
+            return;
+        }
+        for row in self.data.chunks_mut(self.cols) {
+            row.reverse();
+        }
+    }
+
+    /// Versión de [`Matrix::flip_vertical`] que modifica la matriz sin reservar memoria
+    pub fn flip_vertical_inplace(&mut self) {
+        let cols = self.cols;
+        for top in 0..self.rows / 2 {
+            let bottom = self.rows - 1 - top;
+            let (head, tail) = self.data.split_at_mut(bottom * cols);
+            head[top * cols..(top + 1) * cols].swap_with_slice(&mut tail[..cols]);
+        }
+    }
+}
+
+// Concatenación de matrices
+impl<T: Clone> Matrix<T> {
+    /// Concatena `other` a la derecha de `self`
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::DimensionMismatch` si la cantidad de filas difiere
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let a = Matrix::from_vec(vec![1, 2], 2, 1);
+    /// let b = Matrix::from_vec(vec![3, 4, 5, 6], 2, 2);
+    /// assert_eq!(a.hstack(&b).unwrap().as_slice(), &[1, 3, 4, 2, 5, 6]);
+    /// ```
+    pub fn hstack(&self, other: &Matrix<T>) -> Result<Matrix<T>, MatrixError> {
+        Self::concat_all(&[self, other], Axis::Row)
+    }
+
+    /// Concatena `other` debajo de `self`
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::DimensionMismatch` si la cantidad de columnas difiere
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let a = Matrix::from_vec(vec![1, 2], 1, 2);
+    /// let b = Matrix::from_vec(vec![3, 4], 1, 2);
+    /// assert_eq!(a.vstack(&b).unwrap().as_slice(), &[1, 2, 3, 4]);
+    /// ```
+    pub fn vstack(&self, other: &Matrix<T>) -> Result<Matrix<T>, MatrixError> {
+        Self::concat_all(&[self, other], Axis::Col)
+    }
+
+    /// Concatena varias matrices a lo largo de `axis`
+    ///
+    /// Con `Axis::Row` las matrices se ubican una al lado de la otra (como
+    /// `hstack`) y deben tener la misma cantidad de filas; con `Axis::Col` se
+    /// apilan (como `vstack`) y deben tener la misma cantidad de columnas.
+    /// Una lista vacía produce una matriz 0x0.
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::DimensionMismatch` con las dimensiones de la
+    /// primera matriz incompatible
+    pub fn concat_all(matrices: &[&Matrix<T>], axis: Axis) -> Result<Matrix<T>, MatrixError> {
+        let Some(first) = matrices.first() else {
+            return Ok(Matrix { data: Vec::new(), rows: 0, cols: 0 });
+        };
+
+        for m in matrices {
+            let compatible = match axis {
+                Axis::Row => m.rows == first.rows,
+                Axis::Col => m.cols == first.cols,
+            };
+            if !compatible {
+                return Err(MatrixError::DimensionMismatch {
+                    expected: first.dimensions(),
+                    got: m.dimensions(),
+                });
+            }
+        }
+
+        match axis {
+            Axis::Row => {
+                let cols = matrices.iter().map(|m| m.cols).sum();
+                let mut data = Vec::with_capacity(first.rows * cols);
+                for row in 0..first.rows {
+                    for m in matrices {
+                        data.extend_from_slice(&m.data[row * m.cols..(row + 1) * m.cols]);
+                    }
+                }
+                Ok(Matrix { data, rows: first.rows, cols })
+            }
+            Axis::Col => {
+                let rows = matrices.iter().map(|m| m.rows).sum();
+                let mut data = Vec::with_capacity(rows * first.cols);
+                for m in matrices {
+                    data.extend_from_slice(&m.data);
+                }
+                Ok(Matrix { data, rows, cols: first.cols })
+            }
+        }
+    }
+}
+
+// Negación elemento a elemento
+impl<T: Neg<Output = T>> Neg for Matrix<T> {
+    type Output = Matrix<T>;
+
+    /// Niega cada elemento, reutilizando el buffer de la matriz
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, -2, 3, 0], 2, 2);
+    /// assert_eq!((-mat).as_slice(), &[-1, 2, -3, 0]);
+    /// ```
+    fn neg(self) -> Matrix<T> {
+        self.into_map(|x| -x)
+    }
+}
+
+impl<T: Neg<Output = T> + Clone> Neg for &Matrix<T> {
+    type Output = Matrix<T>;
+
+    /// Niega cada elemento sin consumir la matriz original (`-&mat`)
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1.5, -2.0], 1, 2);
+    /// let neg = -&mat;
+    /// assert_eq!(neg.as_slice(), &[-1.5, 2.0]);
+    /// assert_eq!(mat.as_slice(), &[1.5, -2.0]);
+    /// ```
+    fn neg(self) -> Matrix<T> {
+        self.map(|x| -x.clone())
+    }
+}
+
+// Comparación aproximada (para resultados con error de redondeo)
+impl<T> Matrix<T>
+where
+    T: Into<f64> + Copy,
+{
+    /// Mayor diferencia absoluta entre elementos en la misma posición
+    ///
+    /// Devuelve `f64::INFINITY` si las dimensiones difieren y `0.0` para dos
+    /// matrices vacías.
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let a = Matrix::from_vec(vec![1.0, 2.0], 1, 2);
+    /// let b = Matrix::from_vec(vec![1.5, 1.75], 1, 2);
+    /// assert_eq!(a.max_abs_diff(&b), 0.5);
+    /// ```
+    pub fn max_abs_diff(&self, other: &Matrix<T>) -> f64 {
+        if self.dimensions() != other.dimensions() {
+            return f64::INFINITY;
+        }
+        self.data
+            .iter()
+            .zip(&other.data)
+            .map(|(&a, &b)| (a.into() - b.into()).abs())
+            .fold(0.0, f64::max)
+    }
+
+    /// Indica si ambas matrices tienen las mismas dimensiones y todos sus
+    /// elementos difieren a lo sumo en `eps`
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let a = Matrix::from_vec(vec![0.1 + 0.2, 1.0], 1, 2);
+    /// let b = Matrix::from_vec(vec![0.3, 1.0], 1, 2);
+    /// assert!(a != b);
+    /// assert!(a.approx_eq(&b, 1e-12));
+    /// ```
+    pub fn approx_eq(&self, other: &Matrix<T>, eps: f64) -> bool {
+        self.max_abs_diff(other) <= eps
+    }
+}
+
+// Producto matriz-vector
+impl<T> Matrix<T> {
+    /// Multiplica la matriz por el vector columna `v`
+    ///
+    /// Cuesta O(rows * cols), frente a O(n³) de un producto matriz-matriz.
+    ///
+    /// # Argumentos
+    /// * `v` - Vector de longitud `cols`
+    ///
+    /// # Panics
+    /// Panics si `v.len()` no coincide con la cantidad de columnas
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+    /// assert_eq!(mat.dot(&[1, 0, -1]), vec![-2, -2]);
+    /// ```
+    pub fn dot(&self, v: &[T]) -> Vec<T>
+    where
+        T: Mul<Output = T> + Add<Output = T> + Zero + Clone,
+    {
+        if v.len() != self.cols {
+            panic!(
+                "{}",
+                MatrixError::SizeMismatch { expected: self.cols, got: v.len() }
+            );
+        }
+        (0..self.rows)
+            .map(|row| {
+                self.data[row * self.cols..(row + 1) * self.cols]
+                    .iter()
+                    .zip(v)
+                    .fold(T::zero(), |acc, (a, b)| acc + a.clone() * b.clone())
+            })
+            .collect()
+    }
+}
+
+// Potencias de matrices cuadradas
+impl<T> Matrix<T>
+where
+    T: Mul<Output = T> + Add<Output = T> + Zero + One + Clone,
+{
+    /// Eleva la matriz a la potencia `n` por exponenciación binaria
+    ///
+    /// Hace O(log n) productos de matrices en vez de `n - 1`. `power(0)` es
+    /// la identidad.
+    ///
+    /// # Errores
+    /// Devuelve `MatrixError::NotSquare` si la matriz no es cuadrada
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// // Fibonacci: [[1, 1], [1, 0]]^n = [[F(n+1), F(n)], [F(n), F(n-1)]]
+    /// let fib = Matrix::from_vec(vec![1u64, 1, 1, 0], 2, 2);
+    /// assert_eq!(fib.power(10).unwrap().as_slice(), &[89, 55, 55, 34]);
+    /// ```
+    pub fn power(&self, n: u32) -> Result<Matrix<T>, MatrixError> {
+        if self.rows != self.cols {
+            return Err(MatrixError::NotSquare);
+        }
+
+        // Identidad armada a mano: `Matrix::identity` además pide `Default`
+        let n_rows = self.rows;
+        let mut result = Matrix {
+            data: vec![T::zero(); n_rows * n_rows],
+            rows: n_rows,
+            cols: n_rows,
+        };
+        result.fill_diagonal(T::one());
+        let mut base = self.clone();
+        let mut n = n;
+        while n > 0 {
+            if n & 1 == 1 {
+                result = result.square_product(&base);
+            }
+            n >>= 1;
+            if n > 0 {
+                base = base.square_product(&base);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Producto de dos matrices cuadradas del mismo tamaño
+    fn square_product(&self, other: &Matrix<T>) -> Matrix<T> {
+        let n = self.rows;
+        let mut data = Vec::with_capacity(n * n);
+        for row in 0..n {
+            for col in 0..n {
+                data.push((0..n).fold(T::zero(), |acc, k| {
+                    acc + self.data[row * n + k].clone() * other.data[k * n + col].clone()
+                }));
+            }
+        }
+        Matrix { data, rows: n, cols: n }
+    }
+}
+
+// Reducciones (sumas y productos) sobre toda la matriz, filas o columnas
+impl<T> Matrix<T>
+where
+    T: Clone,
+{
+    /// Suma todos los elementos de la matriz
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+    /// assert_eq!(mat.sum(), 10);
+    /// ```
+    pub fn sum(&self) -> T
+    where
+        T: Zero + Add<Output = T>,
+    {
+        self.data.iter().cloned().fold(T::zero(), |acc, x| acc + x)
+    }
+
+    /// Devuelve la suma de cada fila (longitud `rows`)
+    pub fn row_sums(&self) -> Vec<T>
+    where
+        T: Zero + Add<Output = T>,
+    {
+        self.fold_rows(T::zero(), |acc, x| acc + x)
+    }
+
+    /// Devuelve la suma de cada columna (longitud `cols`)
+    pub fn col_sums(&self) -> Vec<T>
+    where
+        T: Zero + Add<Output = T>,
+    {
+        self.fold_cols(T::zero(), |acc, x| acc + x)
+    }
+
+    /// Multiplica todos los elementos de la matriz
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+    /// assert_eq!(mat.product(), 24);
+    /// ```
+    pub fn product(&self) -> T
+    where
+        T: One + Mul<Output = T>,
+    {
+        self.data.iter().cloned().fold(T::one(), |acc, x| acc * x)
+    }
+
+    /// Devuelve el producto de cada fila (longitud `rows`)
+    pub fn row_products(&self) -> Vec<T>
+    where
+        T: One + Mul<Output = T>,
+    {
+        self.fold_rows(T::one(), |acc, x| acc * x)
+    }
+
+    /// Devuelve el producto de cada columna (longitud `cols`)
+    pub fn col_products(&self) -> Vec<T>
+    where
+        T: One + Mul<Output = T>,
+    {
+        self.fold_cols(T::one(), |acc, x| acc * x)
+    }
+
+    /// Sumas acumuladas (prefijos) a lo largo de `axis`
+    ///
+    /// Con `Axis::Row` cada elemento pasa a ser la suma de los anteriores de
+    /// su fila (incluido él); con `Axis::Col`, la de los anteriores de su
+    /// columna. Aplicar ambas da una tabla de áreas sumadas.
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::{Axis, Matrix};
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+    /// assert_eq!(mat.cumulative_sum(Axis::Row).as_slice(), &[1, 3, 6, 4, 9, 15]);
+    /// assert_eq!(mat.cumulative_sum(Axis::Col).as_slice(), &[1, 2, 3, 5, 7, 9]);
+    /// ```
+    pub fn cumulative_sum(&self, axis: Axis) -> Matrix<T>
+    where
+        T: Add<Output = T>,
+    {
+        let mut out = self.clone();
+        match axis {
+            Axis::Row => {
+                for row in 0..self.rows {
+                    for col in 1..self.cols {
+                        let prev = out.get(row, col - 1).clone();
+                        let x = out.get_mut(row, col);
+                        *x = prev + x.clone();
+                    }
+                }
+            }
+            Axis::Col => {
+                for row in 1..self.rows {
+                    for col in 0..self.cols {
+                        let prev = out.get(row - 1, col).clone();
+                        let x = out.get_mut(row, col);
+                        *x = prev + x.clone();
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    fn fold_rows<F: Fn(T, T) -> T>(&self, init: T, f: F) -> Vec<T> {
+        (0..self.rows)
+            .map(|row| {
+                self.data[row * self.cols..(row + 1) * self.cols]
+                    .iter()
+                    .cloned()
+                    .fold(init.clone(), &f)
+            })
+            .collect()
+    }
+
+    fn fold_cols<F: Fn(T, T) -> T>(&self, init: T, f: F) -> Vec<T> {
+        (0..self.cols)
+            .map(|col| {
+                (0..self.rows)
+                    .map(|row| self.get(row, col).clone())
+                    .fold(init.clone(), &f)
+            })
+            .collect()
+    }
+}
+
+// Posición de los elementos extremos
+impl<T: PartialOrd> Matrix<T> {
+    /// Índice del primer elemento de `items` que gana contra todos los
+    /// anteriores según `better`
+    fn arg_best<'a>(items: impl Iterator<Item = &'a T>, better: fn(&T, &T) -> bool) -> usize
+    where
+        T: 'a,
+    {
+        let mut items = items.enumerate();
+        let (mut best_idx, mut best) = items.next().expect("no hay elementos que comparar");
+        for (i, x) in items {
+            if better(x, best) {
+                best_idx = i;
+                best = x;
+            }
+        }
+        best_idx
+    }
+
+    /// Coordenadas `(fila, columna)` del elemento más grande
+    ///
+    /// Recorre la matriz por filas; ante empates devuelve la primera aparición.
+    ///
+    /// # Panics
+    /// Si la matriz está vacía
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, 9, 3, 9, 0, 2], 2, 3);
+    /// assert_eq!(mat.argmax(), (0, 1));
+    /// ```
+    pub fn argmax(&self) -> (usize, usize) {
+        let idx = Self::arg_best(self.data.iter(), |x, best| x > best);
+        (idx / self.cols, idx % self.cols)
+    }
+
+    /// Coordenadas `(fila, columna)` del elemento más chico
+    ///
+    /// Recorre la matriz por filas; ante empates devuelve la primera aparición.
+    ///
+    /// # Panics
+    /// Si la matriz está vacía
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![4, 1, 3, 0, 5, 0], 2, 3);
+    /// assert_eq!(mat.argmin(), (1, 0));
+    /// ```
+    pub fn argmin(&self) -> (usize, usize) {
+        let idx = Self::arg_best(self.data.iter(), |x, best| x < best);
+        (idx / self.cols, idx % self.cols)
+    }
+
+    /// Columna del elemento más grande de cada fila (la primera ante empates)
+    ///
+    /// Útil para convertir la salida de una capa de clasificación en la
+    /// clase predicha de cada muestra.
+    ///
+    /// # Panics
+    /// Si la matriz tiene filas pero ninguna columna
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let scores = Matrix::from_vec(vec![0.1, 0.7, 0.2, 0.5, 0.5, 0.0], 2, 3);
+    /// assert_eq!(scores.row_argmax(), vec![1, 0]);
+    /// ```
+    pub fn row_argmax(&self) -> Vec<usize> {
+        (0..self.rows)
+            .map(|row| {
+                let start = row * self.cols;
+                Self::arg_best(self.data[start..start + self.cols].iter(), |x, best| x > best)
+            })
+            .collect()
+    }
+}
+
+// Normas de matrices
+impl Matrix<f64> {
+    /// Norma de Frobenius: raíz cuadrada de la suma de los cuadrados
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![3.0, 0.0, 0.0, 4.0], 2, 2);
+    /// assert_eq!(mat.frobenius_norm(), 5.0);
+    /// ```
+    pub fn frobenius_norm(&self) -> f64 {
+        self.data.iter().map(|x| x * x).sum::<f64>().sqrt()
+    }
+
+    /// Norma máxima: valor absoluto del elemento de mayor magnitud
+    /// (0.0 para una matriz vacía)
+    pub fn max_norm(&self) -> f64 {
+        self.data.iter().fold(0.0, |acc: f64, x| acc.max(x.abs()))
+    }
+}
+
+// Normalización de filas y columnas
+impl<T> Matrix<T>
+where
+    T: Into<f64> + Copy,
+{
+    /// Divide cada columna por su norma L2
+    ///
+    /// Las columnas con norma casi nula (menor o igual a `f64::EPSILON`)
+    /// quedan en cero en vez de llenarse de NaN.
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![3, 0, 4, 0], 2, 2);
+    /// assert_eq!(mat.column_normalize().as_slice(), &[0.6, 0.0, 0.8, 0.0]);
+    /// ```
+    pub fn column_normalize(&self) -> Matrix<f64> {
+        let mut out = self.map(|&x| x.into());
+        for col in 0..self.cols {
+            let norm = (0..self.rows).map(|row| out.get(row, col).powi(2)).sum::<f64>().sqrt();
+            for row in 0..self.rows {
+                let x = out.get_mut(row, col);
+                *x = if norm <= f64::EPSILON { 0.0 } else { *x / norm };
+            }
+        }
+        out
+    }
+
+    /// Divide cada fila por su norma L2
+    ///
+    /// Igual que [`Matrix::column_normalize`], las filas con norma casi nula
+    /// quedan en cero.
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![3, 4, 0, 0], 2, 2);
+    /// assert_eq!(mat.row_normalize().as_slice(), &[0.6, 0.8, 0.0, 0.0]);
+    /// ```
+    pub fn row_normalize(&self) -> Matrix<f64> {
+        let mut out = self.map(|&x| x.into());
+        for row in out.data.chunks_mut(self.cols.max(1)) {
+            let norm = row.iter().map(|x| x * x).sum::<f64>().sqrt();
+            for x in row {
+                *x = if norm <= f64::EPSILON { 0.0 } else { *x / norm };
+            }
+        }
+        out
+    }
+}
+
+impl<T> Matrix<T>
+where
+    T: Signed + PartialOrd + Clone,
+{
+    /// Norma L∞: la mayor suma de valores absolutos de una fila
+    /// (cero para una matriz vacía)
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec(vec![1, -2, 3, -4], 2, 2);
+    /// assert_eq!(mat.linf_norm(), 7);
+    /// ```
+    pub fn linf_norm(&self) -> T {
+        (0..self.rows)
+            .map(|row| {
+                (0..self.cols).fold(T::zero(), |acc, col| acc + self.get(row, col).abs())
+            })
+            .fold(T::zero(), |best, sum| if sum > best { sum } else { best })
+    }
+}
+
+// Vistas de solo lectura (sin copiar)
+
+/// Región rectangular de una matriz, prestada sin copiar sus elementos
+///
+/// Se obtiene con [`Matrix::view`]. Las filas de la vista son contiguas en
+/// la matriz original; `stride` es la distancia entre el inicio de una fila
+/// y el de la siguiente.
+#[derive(Debug)]
+pub struct MatrixView<'a, T> {
+    data: &'a [T],
+    offset: usize,
+    rows: usize,
+    cols: usize,
+    stride: usize,
+}
+
+// Copiar la vista solo copia el préstamo, así que no hace falta `T: Copy`
+impl<T> Clone for MatrixView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for MatrixView<'_, T> {}
+
+impl<T> Matrix<T> {
+    /// Devuelve una vista de `rows` x `cols` elementos que empieza en
+    /// (`row_start`, `col_start`)
+    ///
+    /// # Panics
+    /// Si la región no cabe dentro de la matriz
+    ///
+    /// # Ejemplos
+    /// ```
+    /// use rmatrix::Matrix;
+    ///
+    /// let mat = Matrix::from_vec((1..=9).collect(), 3, 3);
+    /// let view = mat.view(1, 1, 2, 2);
+    /// assert_eq!(view[(0, 0)], 5);
+    /// assert_eq!(view.iter_rows().collect::<Vec<_>>(), vec![&[5, 6], &[8, 9]]);
+    /// ```
+    pub fn view(&self, row_start: usize, col_start: usize, rows: usize, cols: usize) -> MatrixView<'_, T> {
+        MatrixView {
+            data: &self.data,
+            offset: 0,
+            rows: self.rows,
+            cols: self.cols,
+            stride: self.cols,
+        }
+        .view(row_start, col_start, rows, cols)
+    }
+}
+
+impl<'a, T> MatrixView<'a, T> {
+    /// Sub-vista relativa a esta vista; ver [`Matrix::view`]
+    ///
+    /// # Panics
+    /// Si la región no cabe dentro de la vista
+    pub fn view(&self, row_start: usize, col_start: usize, rows: usize, cols: usize) -> MatrixView<'a, T> {
+        let fits = row_start.checked_add(rows).is_some_and(|end| end <= self.rows)
+            && col_start.checked_add(cols).is_some_and(|end| end <= self.cols);
+        assert!(
+            fits,
+            "la región de {rows}x{cols} en ({row_start}, {col_start}) no cabe en una matriz de {}x{}",
+            self.rows,
+            self.cols
+        );
+        MatrixView {
+            data: self.data,
+            offset: self.offset + row_start * self.stride + col_start,
+            rows,
+            cols,
+            stride: self.stride,
+        }
+    }
+
+    /// Devuelve el número de filas de la vista
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Devuelve el número de columnas de la vista
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Devuelve las dimensiones de la vista como (filas, columnas)
+    pub fn dimensions(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    /// Elemento en (row, col) de la vista, o `None` si está fuera de rango
+    pub fn get(&self, row: usize, col: usize) -> Option<&'a T> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        Some(&self.data[self.offset + row * self.stride + col])
+    }
+
+    /// Fila `r` de la vista como slice
+    ///
+    /// # Panics
+    /// Si `r` está fuera de rango
+    pub fn row(&self, r: usize) -> &'a [T] {
+        assert!(r < self.rows, "fila {r} fuera de rango (la vista tiene {} filas)", self.rows);
+        let start = self.offset + r * self.stride;
+        &self.data[start..start + self.cols]
+    }
+
+    /// Itera las filas de la vista, de arriba hacia abajo
+    pub fn iter_rows(&self) -> impl Iterator<Item = &'a [T]> + use<'a, T> {
+        let view = *self;
+        (0..self.rows).map(move |r| view.row(r))
+    }
+
+    /// Copia la región a una matriz nueva
+    pub fn to_matrix(&self) -> Matrix<T>
+    where
+        T: Clone,
+    {
+        Matrix {
+            data: self.iter_rows().flatten().cloned().collect(),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for MatrixView<'_, T> {
+    type Output = T;
+
+    /// # Panics
+    /// Si (row, col) está fuera de la vista
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        self.get(row, col).unwrap_or_else(|| {
+            panic!(
+                "{}",
+                MatrixError::IndexOutOfBounds { row, col, rows: self.rows, cols: self.cols }
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_creation() {
+        let mat = Matrix::<i32>::new(2, 3);
+        assert_eq!(mat.rows(), 2);
+        assert_eq!(mat.cols(), 3);
+        assert_eq!(mat.dimensions(), (2, 3));
+    }
+
+    #[test]
+    fn test_get_set() {
+        let mut mat = Matrix::<i32>::new(2, 2);
+        mat.set(0, 0, 1);
+        mat.set(0, 1, 2);
+        mat.set(1, 0, 3);
+        mat.set(1, 1, 4);
+
+        assert_eq!(*mat.get(0, 0), 1);
+        assert_eq!(*mat.get(0, 1), 2);
+        assert_eq!(*mat.get(1, 0), 3);
+        assert_eq!(*mat.get(1, 1), 4);
+    }
+
+    #[test]
+    fn test_get_mut() {
+        let mut mat = Matrix::<i32>::new(2, 2);
+        *mat.get_mut(0, 0) = 10;
+        *mat.get_mut(1, 1) = 20;
+
+        assert_eq!(*mat.get(0, 0), 10);
+        assert_eq!(*mat.get(1, 1), 20);
+    }
+
+    #[test]
+    fn test_from_vec() {
+        let data = vec![1, 2, 3, 4, 5, 6];
+        let mat = Matrix::from_vec(data, 2, 3);
+
+        assert_eq!(mat.rows(), 2);
+        assert_eq!(mat.cols(), 3);
+        assert_eq!(*mat.get(0, 0), 1);
+        assert_eq!(*mat.get(1, 2), 6);
+    }
+
+    #[test]
+    fn test_as_slice() {
         let mut mat = Matrix::<i32>::new(2, 2);
         mat.set(0, 0, 1);
         mat.set(0, 1, 2);
@@ -207,6 +1973,546 @@ mod tests {
         assert_eq!(slice, &[1, 2, 3, 4]);
     }
 
+    #[test]
+    fn test_diagonal() {
+        let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6, 7, 8, 9], 3, 3);
+        assert_eq!(mat.diagonal(), vec![1, 5, 9]);
+        assert_eq!(mat.anti_diagonal(), vec![3, 5, 7]);
+
+        let tall = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 3, 2);
+        assert_eq!(tall.diagonal(), vec![1, 4]);
+        assert_eq!(tall.anti_diagonal(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_from_diagonal() {
+        let mat = Matrix::from_diagonal(vec![1, 1, 1]);
+        assert_eq!(mat, Matrix::<i32>::identity(3));
+
+        let mat = Matrix::from_diagonal(vec![2, 3]);
+        assert_eq!(mat.as_slice(), &[2, 0, 0, 3]);
+        assert_eq!(mat.diagonal(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_map() {
+        let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let squared = mat.map(|x| x * x);
+        assert_eq!(squared.dimensions(), (2, 3));
+        assert_eq!(squared.as_slice(), &[1, 4, 9, 16, 25, 36]);
+
+        let halves = mat.into_map(|x| x as f64 / 2.0);
+        assert_eq!(*halves.get(1, 2), 3.0);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut mat = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+        let mut visited = 0;
+        mat.apply(|x| {
+            *x *= -1;
+            visited += 1;
+        });
+        assert_eq!(visited, 4);
+        assert_eq!(mat.as_slice(), &[-1, -2, -3, -4]);
+    }
+
+    #[test]
+    fn test_zip_with() {
+        let a = Matrix::from_vec(vec![1, 5, 3, 8], 2, 2);
+        let b = Matrix::from_vec(vec![4, 2, 6, 7], 2, 2);
+
+        let max = a.zip_with(&b, |x, y| *x.max(y)).unwrap();
+        assert_eq!(max.as_slice(), &[4, 5, 6, 8]);
+
+        let dot: i32 = a.zip_with(&b, |x, y| x * y).unwrap().as_slice().iter().sum();
+        assert_eq!(dot, 4 + 10 + 18 + 56);
+    }
+
+    #[test]
+    fn test_zip_with_dimension_mismatch() {
+        let a = Matrix::<i32>::new(2, 3);
+        let b = Matrix::<i32>::new(3, 2);
+        assert_eq!(
+            a.zip_with(&b, |x, y| x + y),
+            Err(MatrixError::DimensionMismatch {
+                expected: (2, 3),
+                got: (3, 2),
+            })
+        );
+    }
+
+    #[test]
+    fn test_sums() {
+        let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert_eq!(mat.sum(), 21);
+        assert_eq!(mat.row_sums(), vec![6, 15]);
+        assert_eq!(mat.col_sums(), vec![5, 7, 9]);
+        assert_eq!(Matrix::<i32>::new(0, 0).sum(), 0);
+    }
+
+    #[test]
+    fn test_products() {
+        let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert_eq!(mat.product(), 720);
+        assert_eq!(mat.row_products(), vec![6, 120]);
+        assert_eq!(mat.col_products(), vec![4, 10, 18]);
+    }
+
+    #[test]
+    fn test_display() {
+        let mat = Matrix::from_vec(vec![1, 200, -3, 4, 5, 60], 2, 3);
+        assert_eq!(mat.to_string(), "1 200 -3\n4   5 60");
+
+        let floats = Matrix::from_vec(vec![1.5, 2.0], 2, 1);
+        assert_eq!(floats.to_string(), "1.5\n  2");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_roundtrip() {
+        let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let json = serde_json::to_string(&mat).unwrap();
+        assert_eq!(json, r#"{"rows":2,"cols":3,"data":[1,2,3,4,5,6]}"#);
+
+        let back: Matrix<i32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, mat);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_rejects_wrong_length() {
+        let json = r#"{"rows":2,"cols":2,"data":[1,2,3]}"#;
+        assert!(serde_json::from_str::<Matrix<i32>>(json).is_err());
+        let json = r#"{"rows":4294967296,"cols":4294967296,"data":[]}"#;
+        assert!(serde_json::from_str::<Matrix<i32>>(json).is_err());
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_csv_roundtrip() {
+        let mat = Matrix::from_vec(vec![1.5, -2.0, 3.0, 0.25, 5.0, 6.0], 2, 3);
+        let mut out = Vec::new();
+        mat.to_csv(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out.clone()).unwrap(), "1.5,-2,3\n0.25,5,6\n");
+
+        let back = Matrix::from_csv(out.as_slice()).unwrap();
+        assert_eq!(back, mat);
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_csv_errors() {
+        assert_eq!(
+            Matrix::from_csv("1,2\n3\n".as_bytes()),
+            Err(MatrixError::SizeMismatch { expected: 2, got: 1 })
+        );
+        assert_eq!(
+            Matrix::from_csv("1,x\n".as_bytes()),
+            Err(MatrixError::Parse { row: 0, col: 1, value: "x".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_norms() {
+        for n in 1..6 {
+            let id = Matrix::<f64>::identity(n);
+            assert_eq!(id.frobenius_norm(), (n as f64).sqrt());
+        }
+
+        let mat = Matrix::from_vec(vec![1.0, -7.5, 2.0, 3.0], 2, 2);
+        assert_eq!(mat.max_norm(), 7.5);
+        assert_eq!(mat.linf_norm(), 8.5);
+        assert_eq!(Matrix::<f64>::new(0, 0).max_norm(), 0.0);
+    }
+
+    #[test]
+    fn test_hadamard() {
+        let a = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        let b = Matrix::from_vec(vec![2, 0, -1, 1, 2, 3], 2, 3);
+        assert_eq!(a.hadamard(&b).unwrap().as_slice(), &[2, 0, -3, 4, 10, 18]);
+
+        let id = Matrix::<i32>::identity(2);
+        let c = Matrix::from_vec(vec![7, 8, 9, 10], 2, 2);
+        assert_eq!(c.hadamard(&id).unwrap().as_slice(), &[7, 0, 0, 10]);
+
+        assert!(a.hadamard(&c).is_err());
+    }
+
+    #[test]
+    fn test_stack() {
+        let a = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+        let b = Matrix::from_vec(vec![5, 6], 2, 1);
+        let h = a.hstack(&b).unwrap();
+        assert_eq!(h.dimensions(), (2, 3));
+        assert_eq!(h.as_slice(), &[1, 2, 5, 3, 4, 6]);
+
+        let c = Matrix::from_vec(vec![7, 8], 1, 2);
+        let v = a.vstack(&c).unwrap();
+        assert_eq!(v.dimensions(), (3, 2));
+        assert_eq!(v.as_slice(), &[1, 2, 3, 4, 7, 8]);
+
+        assert!(a.hstack(&c).is_err());
+        assert!(a.vstack(&b).is_err());
+    }
+
+    #[test]
+    fn test_concat_all() {
+        let a = Matrix::from_vec(vec![1, 2], 2, 1);
+        let b = Matrix::from_vec(vec![3, 4], 2, 1);
+        let c = Matrix::from_vec(vec![5, 6], 2, 1);
+
+        let row = Matrix::concat_all(&[&a, &b, &c], Axis::Row).unwrap();
+        assert_eq!(row.dimensions(), (2, 3));
+        assert_eq!(row.as_slice(), &[1, 3, 5, 2, 4, 6]);
+
+        let col = Matrix::concat_all(&[&a, &b, &c], Axis::Col).unwrap();
+        assert_eq!(col.dimensions(), (6, 1));
+        assert_eq!(col.as_slice(), &[1, 2, 3, 4, 5, 6]);
+
+        let empty = Matrix::<i32>::concat_all(&[], Axis::Row).unwrap();
+        assert_eq!(empty.dimensions(), (0, 0));
+    }
+
+    #[test]
+    fn test_rows_and_cols() {
+        let mat = Matrix::from_rows(&[&[1, 2], &[3, 4], &[5, 6]]).unwrap();
+        assert_eq!(mat.dimensions(), (3, 2));
+        assert_eq!(mat.to_cols(), vec![vec![1, 3, 5], vec![2, 4, 6]]);
+
+        let cols = mat.to_cols();
+        let col_refs: Vec<&[i32]> = cols.iter().map(|c| c.as_slice()).collect();
+        assert_eq!(Matrix::from_cols(&col_refs).unwrap(), mat);
+
+        assert_eq!(
+            Matrix::from_rows(&[&[1, 2], &[3]]),
+            Err(MatrixError::SizeMismatch { expected: 2, got: 1 })
+        );
+        assert!(Matrix::from_cols(&[&[1], &[2, 3]]).is_err());
+        assert_eq!(Matrix::<i32>::from_rows(&[]).unwrap().dimensions(), (0, 0));
+    }
+
+    #[test]
+    fn test_checked_access() {
+        let mut mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert_eq!(mat.try_get(1, 2), Ok(&6));
+        // Antes (0, 3) caía silenciosamente en (1, 0).
+        assert_eq!(
+            mat.try_get(0, 3),
+            Err(MatrixError::IndexOutOfBounds { row: 0, col: 3, rows: 2, cols: 3 })
+        );
+        assert!(mat.try_set(2, 0, 9).is_err());
+        assert_eq!(mat.try_set(0, 0, 9), Ok(()));
+        *mat.try_get_mut(1, 1).unwrap() = 7;
+        assert_eq!(mat.as_slice(), &[9, 2, 3, 4, 7, 6]);
+
+        assert!(Matrix::try_from_vec(vec![1, 2, 3], 2, 2).is_err());
+        assert_eq!(MatrixError::NotSquare.to_string(), "la matriz no es cuadrada");
+    }
+
+    #[test]
+    #[should_panic(expected = "fuera de los límites")]
+    fn test_get_out_of_bounds_panics() {
+        let mat = Matrix::from_vec(vec![1, 2, 3, 4], 2, 2);
+        mat.get(0, 2);
+    }
+
+    #[test]
+    fn test_flips() {
+        let mat = Matrix::from_vec((1..=12).collect(), 3, 4);
+        assert_eq!(mat.flip_horizontal().to_rows()[0], vec![4, 3, 2, 1]);
+        assert_eq!(mat.flip_vertical().to_rows()[0], vec![9, 10, 11, 12]);
+        assert_eq!(mat.flip_vertical().to_rows()[1], vec![5, 6, 7, 8]);
+
+        // Reflejar dos veces devuelve la original
+        assert_eq!(mat.flip_horizontal().flip_horizontal(), mat);
+        assert_eq!(mat.flip_vertical().flip_vertical(), mat);
+
+        let mut inplace = mat.clone();
+        inplace.flip_horizontal_inplace();
+        assert_eq!(inplace, mat.flip_horizontal());
+        inplace.flip_horizontal_inplace();
+        inplace.flip_vertical_inplace();
+        assert_eq!(inplace, mat.flip_vertical());
+    }
+
+    #[test]
+    fn test_rotate_90() {
+        let mat = Matrix::from_vec((1..=6).collect(), 2, 3);
+
+        let cw = mat.rotate_90(1);
+        for i in 0..2 {
+            for j in 0..3 {
+                assert_eq!(cw.get(j, 1 - i), mat.get(i, j));
+            }
+        }
+
+        assert_eq!(mat.rotate_90(2), mat.flip_horizontal().flip_vertical());
+        assert_eq!(mat.rotate_90(3).as_slice(), &[3, 6, 2, 5, 1, 4]);
+        assert_eq!(mat.rotate_90(-1), mat.rotate_90(3));
+        assert_eq!(mat.rotate_90(4), mat);
+        assert_eq!(cw.rotate_90(3), mat);
+    }
+
+    #[test]
+    fn test_predicates() {
+        assert!(Matrix::<f64>::zeros(3, 2).is_zero());
+        assert!(!Matrix::<f64>::identity(2).is_zero());
+
+        assert!(Matrix::<f64>::identity(4).is_identity());
+        assert!(Matrix::from_diagonal(vec![1, 1, 1]).is_identity());
+        assert!(!Matrix::from_diagonal(vec![1, 2, 1]).is_identity());
+        assert!(!Matrix::from_vec(vec![1, 1, 0, 1], 2, 2).is_identity());
+        assert!(!Matrix::<i32>::zeros(2, 3).is_identity());
+    }
+
+    #[test]
+    fn test_dot() {
+        let mat = Matrix::from_vec(vec![2.0, 0.0, 1.0, 3.0], 2, 2);
+        assert_eq!(mat.dot(&[1.0, 2.0]), vec![2.0, 7.0]);
+        assert_eq!(Matrix::<f64>::identity(3).dot(&[4.0, 5.0, 6.0]), vec![4.0, 5.0, 6.0]);
+        assert_eq!(Matrix::<i32>::zeros(0, 2).dot(&[1, 1]), Vec::<i32>::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "cantidad de elementos incorrecta")]
+    fn test_dot_wrong_length_panics() {
+        Matrix::<i32>::identity(2).dot(&[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_fill_diagonals() {
+        let mut mat = Matrix::<i32>::zeros(3, 3);
+        mat.fill_diagonal(2);
+        mat.fill_antidiagonal(5);
+        assert_eq!(mat.diagonal(), vec![2, 5, 2]);
+        assert_eq!(mat.anti_diagonal(), vec![5, 5, 5]);
+        assert_eq!(mat.sum(), 19);
+
+        let mut tall = Matrix::<i32>::zeros(4, 2);
+        tall.fill_diagonal(1);
+        assert_eq!(tall.sum(), 2);
+        assert_eq!(*tall.get(1, 1), 1);
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let a = Matrix::from_vec(vec![1.0, 2.0, 3.0, 4.0], 2, 2);
+        let b = a.map(|x| x + 1e-9);
+        assert!(a.approx_eq(&b, 1e-6));
+        assert!(!a.approx_eq(&b, 1e-12));
+
+        let c = Matrix::from_vec(vec![1.0, 2.0, 3.5, 4.0], 2, 2);
+        assert_eq!(a.max_abs_diff(&c), 0.5);
+        assert!(a.approx_eq(&c, 0.5));
+
+        let ints = Matrix::from_vec(vec![1, 2], 1, 2);
+        assert_eq!(ints.max_abs_diff(&Matrix::from_vec(vec![4, 2], 1, 2)), 3.0);
+        assert!(!ints.approx_eq(&Matrix::from_vec(vec![1, 2], 2, 1), 1.0));
+    }
+
+    #[test]
+    fn test_triangular() {
+        let mat = Matrix::from_vec((1..=12).collect(), 3, 4);
+        let upper = mat.upper_triangular();
+        let lower = mat.lower_triangular();
+        assert_eq!(upper.to_rows()[2], vec![0, 0, 11, 12]);
+        assert_eq!(lower.to_rows()[0], vec![1, 0, 0, 0]);
+
+        // U + L - diag = A
+        let recombined = upper.zip_with(&lower, |u, l| u + l).unwrap();
+        let diag = Matrix::from_diagonal(mat.diagonal()).hstack(&Matrix::zeros(3, 1)).unwrap();
+        assert_eq!(recombined.zip_with(&diag, |a, d| a - d).unwrap(), mat);
+
+        let strict = mat.strictly_upper_triangular();
+        assert_eq!(strict.diagonal(), vec![0, 0, 0]);
+        assert_eq!(strict.zip_with(&mat.lower_triangular(), |s, l| s + l).unwrap(), mat);
+    }
+
+    #[test]
+    fn test_neg() {
+        let mat = Matrix::from_vec(vec![1, -2, 3, -4, 5, 0], 2, 3);
+        let neg = -&mat;
+        assert_eq!(neg.as_slice(), &[-1, 2, -3, 4, -5, 0]);
+        assert_eq!(-neg, mat);
+
+        // No hay operador `+` entre matrices: la suma elemento a elemento es zip_with.
+        let sum = mat.zip_with(&-mat.clone(), |a, b| a + b).unwrap();
+        assert!(sum.is_zero());
+        assert_eq!(sum, Matrix::zeros(2, 3));
+    }
+
+    #[test]
+    fn test_normalize() {
+        // Normas de columna: 3, 5 y 0; de fila: 1, √13 y √20
+        let mat = Matrix::from_vec(vec![1.0, 0.0, 0.0, 2.0, 3.0, 0.0, 2.0, 4.0, 0.0], 3, 3);
+
+        let expected_cols = Matrix::from_vec(
+            vec![1.0 / 3.0, 0.0, 0.0, 2.0 / 3.0, 0.6, 0.0, 2.0 / 3.0, 0.8, 0.0],
+            3,
+            3,
+        );
+        assert!(mat.column_normalize().approx_eq(&expected_cols, 1e-12));
+
+        let (s13, s20) = (13f64.sqrt(), 20f64.sqrt());
+        let expected_rows = Matrix::from_vec(
+            vec![1.0, 0.0, 0.0, 2.0 / s13, 3.0 / s13, 0.0, 2.0 / s20, 4.0 / s20, 0.0],
+            3,
+            3,
+        );
+        assert!(mat.row_normalize().approx_eq(&expected_rows, 1e-12));
+
+        let zeros = Matrix::<i32>::zeros(2, 2);
+        assert!(zeros.column_normalize().is_zero());
+        assert!(zeros.row_normalize().is_zero());
+        assert_eq!(Matrix::<i32>::new(0, 3).row_normalize().dimensions(), (0, 3));
+    }
+
+    #[test]
+    fn test_argmin_argmax() {
+        let mat = Matrix::from_vec(vec![3, -1, 7, 7, 2, -1, 0, 5, 1], 3, 3);
+        assert_eq!(mat.argmax(), (0, 2));
+        assert_eq!(mat.argmin(), (0, 1));
+        assert_eq!(mat.row_argmax(), vec![2, 0, 1]);
+
+        let single = Matrix::from_vec(vec![4.5], 1, 1);
+        assert_eq!(single.argmax(), (0, 0));
+        assert_eq!(single.argmin(), (0, 0));
+        assert!(Matrix::<i32>::new(0, 3).row_argmax().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "no hay elementos que comparar")]
+    fn test_argmax_empty_panics() {
+        Matrix::<i32>::new(0, 0).argmax();
+    }
+
+    #[test]
+    fn test_power() {
+        let fib = Matrix::from_vec(vec![1u64, 1, 1, 0], 2, 2);
+        assert_eq!(fib.power(0).unwrap(), Matrix::identity(2));
+        assert_eq!(fib.power(1).unwrap(), fib);
+        assert_eq!(fib.power(2).unwrap().as_slice(), &[2, 1, 1, 1]);
+        assert_eq!(fib.power(50).unwrap().get(0, 1), &12_586_269_025);
+
+        // Alcanzabilidad en dos pasos: 0 -> 1 -> 2
+        let graph = Matrix::from_vec(vec![0, 1, 0, 0, 0, 1, 0, 0, 0], 3, 3);
+        assert_eq!(graph.power(2).unwrap().as_slice(), &[0, 0, 1, 0, 0, 0, 0, 0, 0]);
+        assert!(graph.power(3).unwrap().is_zero());
+
+        let rect = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6], 2, 3);
+        assert_eq!(rect.power(2), Err(MatrixError::NotSquare));
+        assert_eq!(rect.power(0), Err(MatrixError::NotSquare));
+    }
+
+    #[test]
+    fn test_cumulative_sum() {
+        let mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, -1, -2, -3], 4, 3);
+        assert_eq!(
+            mat.cumulative_sum(Axis::Row).as_slice(),
+            &[1, 3, 6, 4, 9, 15, 7, 15, 24, -1, -3, -6]
+        );
+        assert_eq!(
+            mat.cumulative_sum(Axis::Col).as_slice(),
+            &[1, 2, 3, 5, 7, 9, 12, 15, 18, 11, 13, 15]
+        );
+
+        // Tabla de áreas sumadas: la última celda es la suma total
+        let sat = mat.cumulative_sum(Axis::Row).cumulative_sum(Axis::Col);
+        assert_eq!(*sat.get(3, 2), mat.sum());
+        assert_eq!(*sat.get(1, 1), 1 + 2 + 4 + 5);
+
+        let empty = Matrix::<i32>::new(0, 4);
+        assert_eq!(empty.cumulative_sum(Axis::Col), empty);
+    }
+
+    #[test]
+    fn test_clone_and_set_rows_cols() {
+        let mut mat = Matrix::from_vec(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12], 3, 4);
+        assert_eq!(mat.clone_row(0), vec![1, 2, 3, 4]);
+        assert_eq!(mat.clone_row(2), vec![9, 10, 11, 12]);
+        assert_eq!(mat.clone_col(0), vec![1, 5, 9]);
+        assert_eq!(mat.clone_col(3), vec![4, 8, 12]);
+
+        mat.set_row(1, &[0, 0, 0, 0]);
+        mat.set_col(2, &[-1, -2, -3]);
+        assert_eq!(mat.as_slice(), &[1, 2, -1, 4, 0, 0, -2, 0, 9, 10, -3, 12]);
+        assert_eq!(mat.clone_col(2), vec![-1, -2, -3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "columna 4 fuera de rango")]
+    fn test_clone_col_out_of_range() {
+        Matrix::<i32>::zeros(3, 4).clone_col(4);
+    }
+
+    #[test]
+    #[should_panic(expected = "cantidad de elementos incorrecta: se esperaba 3, se obtuvo 2")]
+    fn test_set_row_wrong_length() {
+        Matrix::<i32>::zeros(2, 3).set_row(0, &[1, 2]);
+    }
+
+    #[test]
+    fn test_nested_vec_round_trip() {
+        let nested = vec![vec![1, 2, 3], vec![4, 5, 6]];
+        let mat = Matrix::from_nested_vec(nested.clone()).unwrap();
+        assert_eq!(mat.dimensions(), (2, 3));
+        assert_eq!(mat.as_slice(), &[1, 2, 3, 4, 5, 6]);
+        assert_eq!(mat.to_nested_vec(), nested);
+
+        let empty = Matrix::<i32>::from_nested_vec(Vec::new()).unwrap();
+        assert_eq!(empty.dimensions(), (0, 0));
+        assert!(empty.to_nested_vec().is_empty());
+
+        assert_eq!(
+            Matrix::from_nested_vec(vec![vec![1], vec![2, 3]]),
+            Err(MatrixError::SizeMismatch { expected: 1, got: 2 })
+        );
+    }
+
+    #[test]
+    fn test_matrix_view() {
+        let mat = Matrix::from_vec((0..20).collect(), 4, 5);
+        let view = mat.view(1, 2, 3, 2);
+        assert_eq!(view.dimensions(), (3, 2));
+        assert_eq!(view[(0, 0)], 7);
+        assert_eq!(view[(2, 1)], 18);
+        assert_eq!(view.get(3, 0), None);
+        assert_eq!(view.row(1), &[12, 13]);
+        let rows: Vec<&[i32]> = view.iter_rows().collect();
+        assert_eq!(rows, vec![&[7, 8][..], &[12, 13], &[17, 18]]);
+        assert_eq!(view.to_matrix(), Matrix::from_vec(vec![7, 8, 12, 13, 17, 18], 3, 2));
+
+        // Bloques de una vista: las coordenadas son relativas a ella
+        let block = view.view(1, 1, 2, 1);
+        assert_eq!(block.to_matrix().as_slice(), &[13, 18]);
+
+        let empty = mat.view(4, 5, 0, 0);
+        assert_eq!(empty.iter_rows().count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "no cabe")]
+    fn test_matrix_view_out_of_bounds() {
+        Matrix::<i32>::zeros(3, 3).view(2, 0, 2, 1);
+    }
+
+    #[test]
+    fn test_block_diagonal() {
+        let i2 = Matrix::<i32>::identity(2);
+        let i3 = Matrix::<i32>::identity(3);
+        assert_eq!(Matrix::block_diagonal(&[&i2, &i3]), Matrix::identity(5));
+
+        // Bloques rectangulares
+        let a = Matrix::from_vec(vec![1, 2, 3], 1, 3);
+        let b = Matrix::from_vec(vec![4, 5], 2, 1);
+        let mat = Matrix::block_diagonal(&[&a, &b]);
+        assert_eq!(mat.dimensions(), (3, 4));
+        assert_eq!(mat.as_slice(), &[1, 2, 3, 0, 0, 0, 0, 4, 0, 0, 0, 5]);
+
+        assert_eq!(Matrix::<i32>::block_diagonal(&[]).dimensions(), (0, 0));
+    }
+
     #[test]
     fn test_identity() {
         let mat = Matrix::<i32>::identity(3);
@@ -101,7 +101,7 @@ pub fn bfs_path(
 }
 
 /// Función auxiliar para imprimir la ciudad con la ruta resaltada en rojo
-fn print_path_on_city(city: &Matrix<Block>, path: &Vec<Coord>) {
+fn print_path_on_city(city: &Matrix<Block>, path: &[Coord]) {
     println!("\n Mapa con ruta marcada en ROJO:");
     println!("Leyenda: ");
     println!("'•' = Path, '■' = Building, '~' = River, '⌂' = Shop");
@@ -1,22 +1,19 @@
-use std::collections::{HashMap, VecDeque};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::collections::HashMap;
 use mypthreads::*;
 use rmatrix::*;
 mod bfs;
 mod city_design;
 use bfs::bfs_path;
-use rand;
 use rand::Rng;
 use std::ffi::c_void;
 use std::{fmt, ptr};
 use std::ptr::null_mut;
-use std::time::Duration;
 
 use crate::city_design::CITY_DESIGN;
 
-/// --------------------------------------------------------------------------- ///
-///                                 Vehiculos                                   ///
-/// --------------------------------------------------------------------------- ///
+// --------------------------------------------------------------------------- //
+//                                 Vehiculos                                   //
+// --------------------------------------------------------------------------- //
 
 /// Coordenada (x, y) en la grid: x = columna, y = fila.
 pub type Coord = (usize, usize);
@@ -30,7 +27,26 @@ pub const MAX_VEHICLES: usize = 10;
 // Número de vehículos totales a simular
 pub const TOTAL_VEHICLES: usize = 25;
 
-pub static mut COUNT: usize = 0;
+// Permisos para estar dentro de la ciudad: a lo sumo MAX_VEHICLES a la vez
+static mut VEHICLE_SLOTS: MySemaphore = MySemaphore::new(MAX_VEHICLES as u32);
+
+fn vehicle_slots() -> &'static MySemaphore {
+    unsafe { &*ptr::addr_of!(VEHICLE_SLOTS) }
+}
+
+// Vehículos creados que todavía no terminaron su recorrido
+static mut VEHICLES_RUNNING: MyWaitGroup = MyWaitGroup::new();
+
+fn vehicles_running() -> &'static MyWaitGroup {
+    unsafe { &*ptr::addr_of!(VEHICLES_RUNNING) }
+}
+
+// Ticks que main espera a un vehículo antes de darlo por atascado
+const VEHICLE_JOIN_TIMEOUT: u64 = 20_000;
+
+// Ticks que un vehículo espera la celda siguiente antes de rendirse: dos
+// vehículos que se quieren cruzar no se quedan bloqueados para siempre
+const CELL_LOCK_TIMEOUT: u64 = 8;
 
 /// Tipos de vehículos
 #[derive(Copy, Clone, Hash, Debug, PartialEq, Eq)]
@@ -62,7 +78,7 @@ impl Vehicle {
         Vehicle {
             id,
             kind,
-            route: r.unwrap_or_else(|| vec![]),
+            route: r.unwrap_or_default(),
         }
     }
 }
@@ -74,14 +90,17 @@ extern "C" fn vehicle_thread(arg: *mut c_void) -> *mut c_void {
         let id   = boxed_args.id;
         let kind = boxed_args.kind;
         let mut route = std::mem::take(&mut boxed_args.route);
-        let count = 0;
         drop(boxed_args);
 
         if route.is_empty() {
-            println!("[{} {}] Ruta vacía, terminando.", kind.to_string(), id);
+            println!("[{} {}] Ruta vacía, terminando.", kind, id);
+            vehicles_running().done();
             return ptr::null_mut();
         }
 
+        // Esperar lugar en la ciudad
+        my_sem_wait(vehicle_slots());
+
         // Posición inicial
         let mut pos = route.remove(0);
 
@@ -93,7 +112,7 @@ extern "C" fn vehicle_thread(arg: *mut c_void) -> *mut c_void {
             block.set_occupant(Some(id));
         }
 
-        println!("[{} {}] Inicia en {:?}, destino {:?}", kind.to_string(), id, pos, route.last());
+        println!("[{} {}] Inicia en {:?}, destino {:?}", kind, id, pos, route.last());
 
         // Recorrer la ruta
         while let Some(next_pos) = route.first().copied() {
@@ -103,7 +122,7 @@ extern "C" fn vehicle_thread(arg: *mut c_void) -> *mut c_void {
                 None => {
                     println!(
                         "[{} {}] ERROR: {:?} no es vecino directo de {:?}, abortando ruta.",
-                        kind.to_string(), id, next_pos, pos
+                        kind, id, next_pos, pos
                     );
                     break;
                 }
@@ -115,33 +134,36 @@ extern "C" fn vehicle_thread(arg: *mut c_void) -> *mut c_void {
                 if !curr_block.allows_direction(dir) {
                     println!(
                         "[{} {}] ERROR: intento mover {:?} -> {:?} en dirección {} pero el bloque no lo permite, abortando ruta.",
-                        kind.to_string(), id, pos, next_pos, dir.to_string(),
+                        kind, id, pos, next_pos, dir,
                     );
                     break;
                 }
             }
 
             // 2) Intentar tomar el lock del bloque destino SIN bloquear (para detectar contención)
-            let rc = {
-                let city_ref = city();
-                let next_block_ptr = city_ref.get_mut(next_pos.0, next_pos.1) as *mut Block;
-                my_mutex_trylock(&mut (*next_block_ptr).lock)
-            };
+            let rc = my_mutex_trylock(city().get(next_pos.0, next_pos.1).get_lock());
 
             if rc != 0 {
                 // Condición de carrera / contención sobre el recurso (bloque destino)
                 println!(
                     "[RACE] {} {} quiere entrar a {:?} (dir {}) pero el recurso está ocupado; \
 scheduler prioriza a otro vehículo mientras este hilo cede CPU.",
-                    kind.to_string(),
+                    kind,
                     id,
                     next_pos,
-                    dir.to_string(),
+                    dir,
                 );
 
-                // Ceder CPU explícitamente: aquí el scheduler (RR/Lottery/RT) decide a quién correr
-                my_thread_yield();
-                continue;
+                // Esperar la celda un rato; si no se libera, soltar la CPU y reintentar
+                let rc = my_mutex_timedlock(city().get(next_pos.0, next_pos.1).get_lock(), CELL_LOCK_TIMEOUT);
+                if rc != 0 {
+                    println!(
+                        "[{} {}] {:?} sigue ocupado tras {} ticks; cede CPU y reintenta.",
+                        kind, id, next_pos, CELL_LOCK_TIMEOUT,
+                    );
+                    my_thread_yield();
+                    continue;
+                }
             }
 
             // 3) Tenemos lock de destino + todavía mantenemos lock de origen
@@ -149,33 +171,32 @@ scheduler prioriza a otro vehículo mientras este hilo cede CPU.",
             {
                 let city_ref = city();
 
-                let curr_block_ptr = city_ref.get_mut(pos.0, pos.1) as *mut Block;
-                let next_block_ptr = city_ref.get_mut(next_pos.0, next_pos.1) as *mut Block;
-
                 // Por seguridad, verificar que destino no tenía ocupante
-                if (*next_block_ptr).get_occupant().is_some() {
+                let next_block = city_ref.get_mut(next_pos.0, next_pos.1);
+                if next_block.get_occupant().is_some() {
                     println!(
                         "[{} {}] WARNING: bloque {:?} ya tenía ocupante a pesar del lock, liberando y reintentando.",
-                        kind.to_string(), id, next_pos
+                        kind, id, next_pos
                     );
-                    my_mutex_unlock(&mut (*next_block_ptr).lock);
+                    next_block.unlock_block();
                     my_thread_yield();
                     continue;
                 }
+                next_block.set_occupant(Some(id));
 
-                (*next_block_ptr).set_occupant(Some(id));
-                (*curr_block_ptr).set_occupant(None);
-                my_mutex_unlock(&mut (*curr_block_ptr).lock);
+                let curr_block = city_ref.get_mut(pos.0, pos.1);
+                curr_block.set_occupant(None);
+                curr_block.unlock_block();
             }
 
             // 4) Loguear movimiento con dirección
             println!(
                 "[{} {}] Mueve {:?} -> {:?} hacia {}",
-                kind.to_string(),
+                kind,
                 id,
                 pos,
                 next_pos,
-                dir.to_string(),
+                dir,
             );
 
             // Actualizar posición y seguir con la ruta
@@ -194,14 +215,17 @@ scheduler prioriza a otro vehículo mientras este hilo cede CPU.",
             last_block.unlock_block();
         }
 
+        my_sem_post(vehicle_slots());
+
         println!("[{} {}] Terminado en {:?}", kind, id, pos);
+        vehicles_running().done();
         ptr::null_mut()
     }
 }
 
-/// --------------------------------------------------------------------------- ///
-///                                  Ciudad                                     ///
-/// --------------------------------------------------------------------------- ///
+// --------------------------------------------------------------------------- //
+//                                  Ciudad                                     //
+// --------------------------------------------------------------------------- //
 
 
 
@@ -355,12 +379,12 @@ impl Block {
 
     // Métodos para bloquear/desbloquear el mutex del bloque
 
-    pub fn lock_block(&mut self) {
-        my_mutex_lock(&mut self.lock);
+    pub fn lock_block(&self) {
+        my_mutex_lock(&self.lock);
     }
 
-    pub fn unlock_block(&mut self) {
-        my_mutex_unlock(&mut self.lock);
+    pub fn unlock_block(&self) {
+        my_mutex_unlock(&self.lock);
     }
 
     // Métodos GET para cada dirección
@@ -466,16 +490,16 @@ pub type City = Matrix<Block>;
 /// Crea una ciudad con el patrón especificado
 pub fn build_city() -> City {
 
-    let mut height = city_design::GRID_HEIGHT;
-    let mut width = city_design::GRID_WIDTH;
-    let mut design = CITY_DESIGN;
+    let height = city_design::GRID_HEIGHT;
+    let width = city_design::GRID_WIDTH;
+    let design = CITY_DESIGN;
     let mut city = City::new(height, width);
 
     // 1) Setear kind y directions.
-    for row in 0..height {
-        for col in 0..width {
+    for (row, line) in design.iter().enumerate() {
+        for (col, &symbol) in line.iter().enumerate() {
 
-            let kind = match design[row][col] {
+            let kind = match symbol {
                 '↑' | '↓' | '→' | '←' | '↗' | '↖' | '↘' | '↙' | '◁' => BlockKind::Path,
                 'b' => BlockKind::Building,
                 'r' => BlockKind::River,
@@ -486,7 +510,7 @@ pub fn build_city() -> City {
                 _   => BlockKind::Path,
             };
 
-            let directions = match design[row][col] {
+            let directions = match symbol {
                 '↑' => Directions::north(),
                 '↓' => Directions::south(),
                 '→' => Directions::east(),
@@ -529,10 +553,16 @@ pub fn build_city() -> City {
 
 static mut CITY_PTR: *mut City = null_mut();
 
+// La ciudad se construye la primera vez que alguien la pide
+static mut CITY_INIT: MyOnce = MyOnce::new();
+
 fn city() -> &'static mut City {
     unsafe {
-        if CITY_PTR.is_null() {
-            panic!("CITY_PTR no inicializado");
+        let rc = my_once_call(&mut *ptr::addr_of_mut!(CITY_INIT), || {
+            CITY_PTR = Box::into_raw(Box::new(build_city()));
+        });
+        if rc != 0 {
+            panic!("no se pudo construir la ciudad (rc={rc})");
         }
         &mut *CITY_PTR
     }
@@ -579,7 +609,7 @@ pub fn print_detailed_city(city: &Matrix<Block>) {
 
 
 
-/// --------------------------------------------------------------------------- ///
+// --------------------------------------------------------------------------- //
 
 /// Función para contar bloques por tipo
 pub fn count_blocks_by_kind(city: &Matrix<Block>) -> HashMap<BlockKind, usize> {
@@ -685,20 +715,23 @@ pub fn is_valid_position_for_vehicle(city: &Matrix<Block>, pos: Coord, vehicle_k
 }
 
 pub fn call_car(id : VehicleId) -> usize {
-    let spawns = find_spawn_positions(&city());
-    let shops = find_shops(&city());
+    let spawns = find_spawn_positions(city());
+    let shops = find_shops(city());
 
     let spawnplace = rand::thread_rng().gen_range(0..spawns.len());
     let shopsplace = rand::thread_rng().gen_range(0..shops.len());
 
     let vehicle = Vehicle::new(id, VehicleKind::Car, spawns[spawnplace], shops[shopsplace], city());
     
+    let name = format!("{}-{}", vehicle.kind, vehicle.id);
     let boxed = Box::new(vehicle);
     let arg_ptr = Box::into_raw(boxed) as *mut c_void;
 
     let policy: SchedPolicy = SchedPolicy::RoundRobin;
 
+    vehicles_running().add(1);
     let tid = my_thread_create(vehicle_thread, arg_ptr, policy);
+    my_thread_setname(tid, &name);
 
     println!("[MAIN] Creado carro {} con tid {} y política {:?}", id, tid, policy);
 
@@ -706,20 +739,23 @@ pub fn call_car(id : VehicleId) -> usize {
 }
 
 pub fn call_ambulance(id : VehicleId) -> usize {
-    let spawns = find_spawn_positions(&city());
-    let hospitals = find_hospitals(&city());
+    let spawns = find_spawn_positions(city());
+    let hospitals = find_hospitals(city());
 
     let spawnplace = rand::thread_rng().gen_range(0..spawns.len());
     let hospitalsplace = rand::thread_rng().gen_range(0..hospitals.len());
 
     let vehicle = Vehicle::new(id, VehicleKind::Ambulance, spawns[spawnplace], hospitals[hospitalsplace], city());
     
+    let name = format!("{}-{}", vehicle.kind, vehicle.id);
     let boxed = Box::new(vehicle);
     let arg_ptr = Box::into_raw(boxed) as *mut c_void;
 
     let policy: SchedPolicy = SchedPolicy::Lottery { tickets: 50 };
 
+    vehicles_running().add(1);
     let tid = my_thread_create(vehicle_thread, arg_ptr, policy);
+    my_thread_setname(tid, &name);
 
     println!("[MAIN] Creado ambulancia {} con tid {} y política {:?}", id, tid, policy);
 
@@ -727,40 +763,46 @@ pub fn call_ambulance(id : VehicleId) -> usize {
 }
 
 pub fn call_truck_water(id : VehicleId, deadline: u64) -> usize {
-    let spawns = find_spawn_positions(&city());
-    let nuclear_plants = find_nuclear_plants(&city());
+    let spawns = find_spawn_positions(city());
+    let nuclear_plants = find_nuclear_plants(city());
 
     let spawnplace = rand::thread_rng().gen_range(0..spawns.len());
     let nuclear_plants_place = rand::thread_rng().gen_range(0..nuclear_plants.len());
 
     let vehicle = Vehicle::new(id, VehicleKind::TruckWater, spawns[spawnplace], nuclear_plants[nuclear_plants_place], city());
 
+    let name = format!("{}-{}", vehicle.kind, vehicle.id);
     let boxed = Box::new(vehicle);
     let arg_ptr = Box::into_raw(boxed) as *mut c_void;
 
     let policy: SchedPolicy = SchedPolicy::RealTime { deadline };
 
+    vehicles_running().add(1);
     let tid = my_thread_create(vehicle_thread, arg_ptr, policy);
+    my_thread_setname(tid, &name);
 
     println!("[MAIN] Creado camión de agua {} con tid {} y política {:?}", id, tid, policy);
 
     tid
 }
 pub fn call_truck_radioactive(id : VehicleId, deadline: u64) -> usize {
-    let spawns = find_spawn_positions(&city());
-    let nuclear_plants = find_nuclear_plants(&city());
+    let spawns = find_spawn_positions(city());
+    let nuclear_plants = find_nuclear_plants(city());
 
     let spawnplace = rand::thread_rng().gen_range(0..spawns.len());
     let nuclear_plants_place = rand::thread_rng().gen_range(0..nuclear_plants.len());
 
     let vehicle = Vehicle::new(id, VehicleKind::TruckRadioactive, spawns[spawnplace], nuclear_plants[nuclear_plants_place], city());
 
+    let name = format!("{}-{}", vehicle.kind, vehicle.id);
     let boxed = Box::new(vehicle);
     let arg_ptr = Box::into_raw(boxed) as *mut c_void;
 
     let policy: SchedPolicy = SchedPolicy::RealTime { deadline };
 
+    vehicles_running().add(1);
     let tid = my_thread_create(vehicle_thread, arg_ptr, policy);
+    my_thread_setname(tid, &name);
 
     println!("[MAIN] Creado camión radioactivo {} con tid {} y política {:?}", id, tid, policy);
 
@@ -783,41 +825,137 @@ fn run_simulation() {
     let truck_water1 = call_truck_water(22, 15);
     let truck_radioactive1 = call_truck_radioactive(23, 10);
 
-    let tids1 = vec![
-        cars,
+    let tids1 = [cars,
         ambulances,
-        vec![truck_water1, truck_radioactive1],
-    ].concat();
+        vec![truck_water1, truck_radioactive1]].concat();
 
-    // Esperar a que terminen vehículos
+    // Nadie hace join: se espera al grupo completo
     for tid in tids1 {
-        my_thread_join(tid);
+        my_thread_detach(tid);
     }
+    vehicles_running().wait();
 
     let truck_water2 = call_truck_water(24, 8);
     let truck_radioactive2 = call_truck_radioactive(25, 12);
 
-    let tids2 = vec![truck_water2, truck_radioactive2];
-
-        // Esperar a que terminen vehículos
-    for tid in tids2 {
-        my_thread_join(tid);
+    // Esta vez se espera a cada camión, pero con un plazo: uno que quede
+    // atascado no frena el cierre de la simulación.
+    for (tid, id) in [(truck_water2, 24), (truck_radioactive2, 25)] {
+        match my_thread_join_timeout(tid, VEHICLE_JOIN_TIMEOUT) {
+            Ok(_) => {}
+            Err(e) => {
+                // Si todavía esperaba lugar en la ciudad, no llegó a tomar
+                // un permiso del semáforo.
+                let has_slot = my_thread_block_reason(tid) != Some(MyBlockReason::Semaphore);
+                let rc = my_thread_kill(tid);
+                if rc != 0 {
+                    println!("[MAIN] {e}: no se pudo matar al vehículo {tid} (rc={rc})");
+                    continue;
+                }
+                println!("[MAIN] {e}: se mata al vehículo {tid}");
+                // El vehículo muerto no devuelve lo que tomó: su TCB, la
+                // celda que ocupaba, su lugar en la ciudad ni su parte del
+                // grupo.
+                my_thread_join(tid);
+                clear_occupant(city(), id);
+                if has_slot {
+                    my_sem_post(vehicle_slots());
+                }
+                vehicles_running().done();
+            }
+        }
     }
 
     println!("[MAIN] Todos los vehículos de prueba han terminado.");
+    print_lock_stats(city());
 }
 
-/// --------------------------------------------------------------------------- ///
-///                                  Ejecución                                  ///
-/// --------------------------------------------------------------------------- ///
+/// Vacía la celda que sigue marcada como ocupada por `id`. El lock de la
+/// celda ya quedó libre al matar el hilo, así que se toma para no pisar a
+/// otro vehículo que esté entrando.
+fn clear_occupant(city: &mut City, id: VehicleId) {
+    for row in 0..city.rows() {
+        for col in 0..city.cols() {
+            let block = city.get_mut(row, col);
+            if block.get_occupant() != Some(id) {
+                continue;
+            }
+            block.lock_block();
+            if block.get_occupant() == Some(id) {
+                block.set_occupant(None);
+            }
+            block.unlock_block();
+        }
+    }
+}
+
+/// Contención de los locks de las celdas durante la simulación, para
+/// comparar `MutexHandoff::Direct` con `--barging`.
+fn print_lock_stats(city: &City) {
+    let mut total = MyMutexStats::default();
+    for row in 0..city.rows() {
+        for col in 0..city.cols() {
+            let st = my_mutex_stats(city.get(row, col).get_lock());
+            total.acquisitions += st.acquisitions;
+            total.handoffs += st.handoffs;
+            total.steals += st.steals;
+        }
+    }
+    println!(
+        "[MAIN] Locks de celdas: {} adquisiciones, {} traspasos directos, {} robos en {} ticks",
+        total.acquisitions, total.handoffs, total.steals, my_sched_ticks()
+    );
+}
+
+// --------------------------------------------------------------------------- //
+//                                  Ejecución                                  //
+// --------------------------------------------------------------------------- //
+
+/// `--barging` hace que los locks de las celdas usen `MutexHandoff::Barging`.
+fn parse_barging() -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--barging")
+}
+
+/// Lee `flag N` de la línea de comandos, si viene.
+fn parse_flag<T: std::str::FromStr>(flag: &str) -> Option<T> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            let value = args.next().unwrap_or_else(|| panic!("{flag} requiere un valor"));
+            return Some(value.parse().unwrap_or_else(|_| panic!("{flag} debe ser un entero sin signo")));
+        }
+    }
+    None
+}
 
 fn main() {
 
+    // Semilla del sorteo Lottery, para poder reproducir una corrida
+    if let Some(seed) = parse_flag("--seed") {
+        my_sched_set_seed(seed);
+    }
+    println!("Semilla del scheduler: {}", my_sched_get_seed());
+
+    // Quantum de carros y camiones: más slots por dispatch dan menos cambios
+    // de contexto a costa de que cada vehículo espere más su turno.
+    if let Some(quantum) = parse_flag::<u32>("--quantum") {
+        assert!(quantum > 0, "--quantum debe ser mayor que 0");
+        my_sched_set_quantum(SchedPolicyKind::RoundRobin, quantum);
+        my_sched_set_quantum(SchedPolicyKind::Lottery, quantum);
+        println!("Quantum RR y Lottery: {quantum}");
+    }
+
     // Crear ciudad
-    let city_box = Box::new(build_city());
-    unsafe { CITY_PTR = Box::into_raw(city_box); }
     let city = city();
-    print_detailed_city(&city);
+    if parse_barging() {
+        for row in 0..city.rows() {
+            for col in 0..city.cols() {
+                my_mutex_init_with_handoff(&mut city.get_mut(row, col).lock, MutexHandoff::Barging);
+            }
+        }
+        println!("Locks de celdas en modo barging");
+    }
+    print_detailed_city(city);
 
     let kind_stats = count_blocks_by_kind(city);
     let spawn_positions = find_spawn_positions(city);
@@ -40,16 +40,16 @@ extern "C" fn rr_worker(arg: *mut c_void) -> *mut c_void {
             // Cada 3 iteraciones hacemos lock bloqueante,
             // las otras veces intentamos con trylock.
             if i % 3 == 0 {
-                my_mutex_lock(&mut (*shared).mutex);
+                my_mutex_lock(&(*shared).mutex);
                 (*shared).rr_counter += 1;
                 ok += 1;
-                my_mutex_unlock(&mut (*shared).mutex);
+                my_mutex_unlock(&(*shared).mutex);
             } else {
-                let r = my_mutex_trylock(&mut (*shared).mutex);
+                let r = my_mutex_trylock(&(*shared).mutex);
                 if r == 0 {
                     (*shared).rr_counter += 1;
                     ok += 1;
-                    my_mutex_unlock(&mut (*shared).mutex);
+                    my_mutex_unlock(&(*shared).mutex);
                 } else {
                     fail += 1;
                 }
@@ -73,9 +73,9 @@ extern "C" fn lottery_worker(arg: *mut c_void) -> *mut c_void {
         let idx = args_box.index; // 0, 1, 2
 
         for _ in 0..4000 {
-            my_mutex_lock(&mut (*shared).mutex);
+            my_mutex_lock(&(*shared).mutex);
             (*shared).lottery_counter[idx] += 1;
-            my_mutex_unlock(&mut (*shared).mutex);
+            my_mutex_unlock(&(*shared).mutex);
 
             my_thread_yield();
         }
@@ -96,9 +96,9 @@ extern "C" fn rt_worker(arg: *mut c_void) -> *mut c_void {
         let id = args_box.index;
 
         for i in 0..80 {
-            my_mutex_lock(&mut (*shared).mutex);
+            my_mutex_lock(&(*shared).mutex);
             (*shared).rt_counter += 1;
-            my_mutex_unlock(&mut (*shared).mutex);
+            my_mutex_unlock(&(*shared).mutex);
 
             println!("[RT] tarea {id} tick {i}");
 
@@ -235,4 +235,11 @@ fn main() {
     println!("lottery_counters  = {:?}", shared.lottery_counter);
     println!("rt_counter        = {}", shared.rt_counter);
     println!("======================");
+
+    // ----- 6) Costo de los cambios de contexto -----
+    println!(
+        "[BENCH] yield ida y vuelta: {:?}, traspaso de mutex: {:?}",
+        bench::measure_switch_latency(10_000),
+        bench::measure_mutex_roundtrip(10_000),
+    );
 }